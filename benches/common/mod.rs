@@ -0,0 +1,264 @@
+//! Structures compared with [`PostfixSegmentTree`], shared by the benchmark and its cross-check test.
+
+use postfix_segment_tree::PostfixSegmentTree;
+
+pub trait PrefixSumTree: FromIterator<i64> {
+    const NAME: &'static str;
+
+    fn push(&mut self, element: i64);
+    fn update(&mut self, index: usize, element: i64);
+    fn prefix_sum(&self, index: usize) -> i64;
+    fn sum(&self, index: usize, len: usize) -> i64;
+    fn insert(&mut self, index: usize, element: i64);
+    fn remove(&mut self, index: usize) -> i64;
+    fn pop(&mut self) -> Option<i64>;
+}
+
+impl PrefixSumTree for PostfixSegmentTree<i64> {
+    const NAME: &'static str = "postfix";
+
+    fn push(&mut self, element: i64) {
+        PostfixSegmentTree::push(self, element);
+    }
+
+    fn update(&mut self, index: usize, element: i64) {
+        PostfixSegmentTree::update(self, index, element);
+    }
+
+    fn prefix_sum(&self, index: usize) -> i64 {
+        PostfixSegmentTree::prefix_sum(self, index)
+    }
+
+    fn sum(&self, index: usize, len: usize) -> i64 {
+        PostfixSegmentTree::sum(self, index, len)
+    }
+
+    fn insert(&mut self, index: usize, element: i64) {
+        PostfixSegmentTree::insert(self, index, element);
+    }
+
+    fn remove(&mut self, index: usize) -> i64 {
+        PostfixSegmentTree::remove(self, index)
+    }
+
+    fn pop(&mut self) -> Option<i64> {
+        PostfixSegmentTree::pop(self)
+    }
+}
+
+/// A Fenwick tree, which keeps elements as well for *O*(1) access.
+pub struct Fenwick {
+    elements: Vec<i64>,
+    // 1-based, nodes[i - 1] is the sum of (i - lowbit(i), i]
+    nodes: Vec<i64>,
+}
+
+impl Fenwick {
+    fn add(&mut self, index: usize, delta: i64) {
+        let mut i = index + 1;
+        while i <= self.nodes.len() {
+            self.nodes[i - 1] += delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    fn rebuild(&mut self) {
+        self.nodes.clone_from(&self.elements);
+        for i in 1..=self.nodes.len() {
+            let parent = i + (i & i.wrapping_neg());
+            if parent <= self.nodes.len() {
+                self.nodes[parent - 1] += self.nodes[i - 1];
+            }
+        }
+    }
+}
+
+impl FromIterator<i64> for Fenwick {
+    fn from_iter<I: IntoIterator<Item = i64>>(iter: I) -> Self {
+        let mut fenwick = Fenwick {
+            elements: iter.into_iter().collect(),
+            nodes: Vec::new(),
+        };
+        fenwick.rebuild();
+        fenwick
+    }
+}
+
+impl PrefixSumTree for Fenwick {
+    const NAME: &'static str = "fenwick";
+
+    fn push(&mut self, element: i64) {
+        let i = self.nodes.len() + 1;
+        let node = element + self.prefix_sum(i - 1) - self.prefix_sum(i - (i & i.wrapping_neg()));
+        self.elements.push(element);
+        self.nodes.push(node);
+    }
+
+    fn update(&mut self, index: usize, element: i64) {
+        let delta = element - self.elements[index];
+        self.elements[index] = element;
+        self.add(index, delta);
+    }
+
+    fn prefix_sum(&self, index: usize) -> i64 {
+        let mut sum = 0;
+        let mut i = index;
+        while i > 0 {
+            sum += self.nodes[i - 1];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+
+    fn sum(&self, index: usize, len: usize) -> i64 {
+        self.prefix_sum(index + len) - self.prefix_sum(index)
+    }
+
+    fn insert(&mut self, index: usize, element: i64) {
+        self.elements.insert(index, element);
+        self.rebuild();
+    }
+
+    fn remove(&mut self, index: usize) -> i64 {
+        let element = self.elements.remove(index);
+        self.rebuild();
+        element
+    }
+
+    fn pop(&mut self) -> Option<i64> {
+        // the last node covers nothing after the last element
+        self.nodes.pop();
+        self.elements.pop()
+    }
+}
+
+/// A bottom-up segment tree in prefix order, whose leaves are at `nodes[capacity..]`.
+pub struct SegmentTree {
+    nodes: Vec<i64>,
+    len: usize,
+}
+
+impl SegmentTree {
+    fn capacity(&self) -> usize {
+        self.nodes.len() / 2
+    }
+
+    fn rebuild(&mut self) {
+        for i in (1..self.capacity()).rev() {
+            self.nodes[i] = self.nodes[2 * i] + self.nodes[2 * i + 1];
+        }
+    }
+
+    fn grow(&mut self) {
+        let capacity = (self.capacity() * 2).max(1);
+        let mut nodes = vec![0; capacity * 2];
+        nodes[capacity..capacity + self.len]
+            .copy_from_slice(&self.nodes[self.capacity()..self.capacity() + self.len]);
+        self.nodes = nodes;
+        self.rebuild();
+    }
+}
+
+impl FromIterator<i64> for SegmentTree {
+    fn from_iter<I: IntoIterator<Item = i64>>(iter: I) -> Self {
+        let elements: Vec<i64> = iter.into_iter().collect();
+        let capacity = elements.len().next_power_of_two();
+        let mut nodes = vec![0; capacity * 2];
+        nodes[capacity..capacity + elements.len()].copy_from_slice(&elements);
+        let mut tree = SegmentTree {
+            nodes,
+            len: elements.len(),
+        };
+        tree.rebuild();
+        tree
+    }
+}
+
+impl PrefixSumTree for SegmentTree {
+    const NAME: &'static str = "segment";
+
+    fn push(&mut self, element: i64) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+        self.len += 1;
+        self.update(self.len - 1, element);
+    }
+
+    fn update(&mut self, index: usize, element: i64) {
+        let mut i = self.capacity() + index;
+        self.nodes[i] = element;
+        while i > 1 {
+            i /= 2;
+            self.nodes[i] = self.nodes[2 * i] + self.nodes[2 * i + 1];
+        }
+    }
+
+    fn prefix_sum(&self, index: usize) -> i64 {
+        self.sum(0, index)
+    }
+
+    fn sum(&self, index: usize, len: usize) -> i64 {
+        let mut sum = 0;
+        let (mut l, mut r) = (self.capacity() + index, self.capacity() + index + len);
+        while l < r {
+            if l % 2 == 1 {
+                sum += self.nodes[l];
+                l += 1;
+            }
+            if r % 2 == 1 {
+                r -= 1;
+                sum += self.nodes[r];
+            }
+            l /= 2;
+            r /= 2;
+        }
+        sum
+    }
+
+    fn insert(&mut self, index: usize, element: i64) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+        let leaves = self.capacity();
+        self.nodes
+            .copy_within(leaves + index..leaves + self.len, leaves + index + 1);
+        self.nodes[leaves + index] = element;
+        self.len += 1;
+        self.rebuild();
+    }
+
+    fn remove(&mut self, index: usize) -> i64 {
+        let leaves = self.capacity();
+        let element = self.nodes[leaves + index];
+        self.nodes
+            .copy_within(leaves + index + 1..leaves + self.len, leaves + index);
+        self.len -= 1;
+        self.nodes[leaves + self.len] = 0;
+        self.rebuild();
+        element
+    }
+
+    fn pop(&mut self) -> Option<i64> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let element = self.nodes[self.capacity() + self.len - 1];
+        self.update(self.len - 1, 0);
+        self.len -= 1;
+        Some(element)
+    }
+}
+
+/// Pseudo-random indices, so all structures see the same sequence.
+pub struct Indices(pub u64);
+
+impl Indices {
+    pub fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
@@ -0,0 +1,127 @@
+//! Compares [`PostfixSegmentTree`] with a Fenwick tree and a prefix-order segment tree.
+//!
+//! Run with `cargo bench`, and pass a substring of names to filter, such as `cargo bench -- insert`.
+//! Each operation is a group, which has a benchmark of each structure across sizes.
+//! Structures are cross-checked against each other in `tests/comparison.rs`.
+
+mod common;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use criterion::measurement::WallTime;
+use criterion::{
+    BenchmarkGroup, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main,
+};
+use postfix_segment_tree::PostfixSegmentTree;
+
+use crate::common::{Fenwick, Indices, PrefixSumTree, SegmentTree};
+
+const SIZES: [usize; 3] = [1 << 10, 1 << 14, 1 << 18];
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+fn elements(size: usize) -> impl Iterator<Item = i64> {
+    (0..size as i64).map(|i| i * 7 % 1000)
+}
+
+fn push<T: PrefixSumTree>(group: &mut BenchmarkGroup<'_, WallTime>, size: usize) {
+    group.throughput(Throughput::Elements(size as u64));
+    group.bench_with_input(BenchmarkId::new(T::NAME, size), &size, |b, &size| {
+        b.iter_with_large_drop(|| {
+            let mut tree = T::from_iter([]);
+            for element in elements(size) {
+                tree.push(black_box(element));
+            }
+            tree
+        });
+    });
+}
+
+fn update<T: PrefixSumTree>(group: &mut BenchmarkGroup<'_, WallTime>, size: usize) {
+    let mut tree = T::from_iter(elements(size));
+    let mut indices = Indices(SEED);
+    group.bench_with_input(BenchmarkId::new(T::NAME, size), &size, |b, &size| {
+        b.iter(|| tree.update(indices.next(size), black_box(1)));
+    });
+}
+
+fn prefix_sum<T: PrefixSumTree>(group: &mut BenchmarkGroup<'_, WallTime>, size: usize) {
+    let tree = T::from_iter(elements(size));
+    let mut indices = Indices(SEED);
+    group.bench_with_input(BenchmarkId::new(T::NAME, size), &size, |b, &size| {
+        b.iter(|| tree.prefix_sum(indices.next(size + 1)));
+    });
+}
+
+fn sum<T: PrefixSumTree>(group: &mut BenchmarkGroup<'_, WallTime>, size: usize) {
+    let tree = T::from_iter(elements(size));
+    let mut indices = Indices(SEED);
+    group.bench_with_input(BenchmarkId::new(T::NAME, size), &size, |b, &size| {
+        b.iter(|| {
+            let index = indices.next(size + 1);
+            tree.sum(index, indices.next(size - index + 1))
+        });
+    });
+}
+
+// only insertions are timed, and the tree is shrunk back after each of them
+fn insert<T: PrefixSumTree>(group: &mut BenchmarkGroup<'_, WallTime>, size: usize) {
+    let mut tree = T::from_iter(elements(size));
+    let mut indices = Indices(SEED);
+    group.bench_with_input(BenchmarkId::new(T::NAME, size), &size, |b, &size| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                let index = indices.next(size + 1);
+                let start = Instant::now();
+                tree.insert(index, black_box(1));
+                elapsed += start.elapsed();
+                tree.pop();
+            }
+            elapsed
+        });
+    });
+}
+
+// only removals are timed, and the tree is grown back after each of them
+fn remove<T: PrefixSumTree>(group: &mut BenchmarkGroup<'_, WallTime>, size: usize) {
+    let mut tree = T::from_iter(elements(size));
+    let mut indices = Indices(SEED);
+    group.bench_with_input(BenchmarkId::new(T::NAME, size), &size, |b, &size| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                let index = indices.next(size);
+                let start = Instant::now();
+                black_box(tree.remove(index));
+                elapsed += start.elapsed();
+                tree.push(1);
+            }
+            elapsed
+        });
+    });
+}
+
+macro_rules! compare {
+    ($c:expr, $operation:ident) => {{
+        let mut group = $c.benchmark_group(stringify!($operation));
+        for size in SIZES {
+            $operation::<PostfixSegmentTree<i64>>(&mut group, size);
+            $operation::<Fenwick>(&mut group, size);
+            $operation::<SegmentTree>(&mut group, size);
+        }
+        group.finish();
+    }};
+}
+
+fn comparison(c: &mut Criterion) {
+    compare!(c, push);
+    compare!(c, update);
+    compare!(c, prefix_sum);
+    compare!(c, sum);
+    compare!(c, insert);
+    compare!(c, remove);
+}
+
+criterion_group!(benches, comparison);
+criterion_main!(benches);
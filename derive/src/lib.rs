@@ -0,0 +1,203 @@
+//! Derive macro for `postfix_segment_tree::Aggregate`.
+//!
+//! Use it through the `derive` feature of `postfix-segment-tree`, rather than depending on this crate directly.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// How a field is combined.
+enum Operation {
+    /// `Combine::combine` of the field type, which is `+=` for numbers.
+    Aggregate,
+    Min,
+    Max,
+}
+
+struct Field {
+    name: String,
+    ty: String,
+    operation: Operation,
+}
+
+/// Derives `Aggregate` and `Combine` for a struct by combining its fields one by one.
+///
+/// Fields are combined with their own `Aggregate` implementation by default, which is addition for numbers.
+/// `#[aggregate(min)]` and `#[aggregate(max)]` keep the minimum and the maximum of the field instead,
+/// and the field type should implement `Bounded`, `PartialOrd`, and `Clone`.
+#[proc_macro_derive(Aggregate, attributes(aggregate))]
+pub fn derive_aggregate(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(output) => output,
+        Err(message) => format!("::core::compile_error!({message:?});")
+            .parse()
+            .unwrap(),
+    }
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, String> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+
+    let struct_index = tokens
+        .iter()
+        .position(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "struct"))
+        .ok_or("`Aggregate` can only be derived for structs")?;
+
+    let name = match tokens.get(struct_index + 1) {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected a struct name".to_string()),
+    };
+
+    let fields = match tokens.get(struct_index + 2) {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            parse_fields(group.stream())?
+        }
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => {
+            return Err("`Aggregate` can't be derived for generic structs".to_string());
+        }
+        _ => {
+            return Err(
+                "`Aggregate` can only be derived for structs with named fields".to_string(),
+            );
+        }
+    };
+
+    let mut identity = String::new();
+    let mut combine = String::new();
+    for Field {
+        name,
+        ty,
+        operation,
+    } in &fields
+    {
+        match operation {
+            Operation::Aggregate => {
+                identity +=
+                    &format!("{name}: <{ty} as ::postfix_segment_tree::Aggregate>::identity(),");
+                combine += &format!(
+                    "::postfix_segment_tree::Combine::combine(&mut self.{name}, &rhs.{name});"
+                );
+            }
+            Operation::Min => {
+                identity += &format!("{name}: <{ty} as ::postfix_segment_tree::Bounded>::MAX,");
+                combine += &format!(
+                    "if rhs.{name} < self.{name} {{ self.{name} = ::core::clone::Clone::clone(&rhs.{name}); }}"
+                );
+            }
+            Operation::Max => {
+                identity += &format!("{name}: <{ty} as ::postfix_segment_tree::Bounded>::MIN,");
+                combine += &format!(
+                    "if rhs.{name} > self.{name} {{ self.{name} = ::core::clone::Clone::clone(&rhs.{name}); }}"
+                );
+            }
+        }
+    }
+
+    let output = format!(
+        "impl ::postfix_segment_tree::Aggregate for {name} {{
+            fn identity() -> Self {{
+                Self {{ {identity} }}
+            }}
+        }}
+
+        impl ::postfix_segment_tree::Combine for {name} {{
+            fn combine(&mut self, rhs: &Self) {{
+                {combine}
+            }}
+        }}"
+    );
+
+    output.parse().map_err(|error| format!("{error:?}"))
+}
+
+fn parse_fields(stream: TokenStream) -> Result<Vec<Field>, String> {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+
+    let mut fields = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let mut operation = Operation::Aggregate;
+
+        // attributes
+        while let Some(TokenTree::Punct(punct)) = tokens.get(i) {
+            if punct.as_char() != '#' {
+                break;
+            }
+
+            if let Some(TokenTree::Group(group)) = tokens.get(i + 1)
+                && let Some(parsed) = parse_attribute(group.stream())?
+            {
+                operation = parsed;
+            }
+
+            i += 2;
+        }
+
+        // visibility
+        if matches!(tokens.get(i), Some(TokenTree::Ident(ident)) if ident.to_string() == "pub") {
+            i += 1;
+            if matches!(tokens.get(i), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis)
+            {
+                i += 1;
+            }
+        }
+
+        let name = match tokens.get(i) {
+            Some(TokenTree::Ident(ident)) => ident.to_string(),
+            _ => return Err("expected a field name".to_string()),
+        };
+        i += 1;
+
+        match tokens.get(i) {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => i += 1,
+            _ => return Err(format!("expected `:` after the field `{name}`")),
+        }
+
+        // the type ends at `,` that is not in generic arguments
+        let mut ty = TokenStream::new();
+        let mut depth = 0;
+        let mut arrow = false;
+        while let Some(token) = tokens.get(i) {
+            i += 1;
+            if let TokenTree::Punct(punct) = token {
+                match punct.as_char() {
+                    '<' => depth += 1,
+                    // `>` of `->` in function pointer types doesn't close generic arguments
+                    '>' if !arrow => depth -= 1,
+                    ',' if depth == 0 => break,
+                    _ => {}
+                }
+            }
+            arrow = matches!(token, TokenTree::Punct(punct) if punct.as_char() == '-' && punct.spacing() == Spacing::Joint);
+
+            ty.extend([token.clone()]);
+        }
+
+        fields.push(Field {
+            name,
+            ty: ty.to_string(),
+            operation,
+        });
+    }
+
+    Ok(fields)
+}
+
+/// Parses `aggregate(min)` in `#[aggregate(min)]`. Other attributes are ignored.
+fn parse_attribute(stream: TokenStream) -> Result<Option<Operation>, String> {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    match tokens.as_slice() {
+        [TokenTree::Ident(ident), TokenTree::Group(arguments)]
+            if ident.to_string() == "aggregate" =>
+        {
+            let argument = arguments.stream().to_string();
+            match argument.as_str() {
+                "sum" => Ok(Some(Operation::Aggregate)),
+                "min" => Ok(Some(Operation::Min)),
+                "max" => Ok(Some(Operation::Max)),
+                _ => Err(format!(
+                    "unknown aggregate operation `{argument}`, expected `sum`, `min`, or `max`"
+                )),
+            }
+        }
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use postfix_segment_tree::model::{Operation, check};
+
+fuzz_target!(|data: &[u8]| {
+    check(&Operation::parse(data));
+});
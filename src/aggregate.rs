@@ -0,0 +1,919 @@
+use std::iter;
+#[cfg(feature = "num-traits")]
+use std::mem;
+use std::ops::{Add, AddAssign, BitXorAssign};
+
+#[cfg(feature = "num-traits")]
+use num_traits::Zero;
+
+/// An associative operation, which is maintained by [`PostfixSegmentTree`].
+///
+/// Storing elements only requires `Combine + Clone`, since parent nodes are recalculated
+/// from a clone of their left child combined with their right child.
+/// So types without a meaningful identity or `Default` can be stored, and queried with [`reduce`].
+/// Queries over possibly empty ranges, such as [`prefix_sum`] and [`sum`], require [`Aggregate`].
+///
+/// It is implemented for every type that implements `AddAssign<&T>`, where `combine` is `+=`.
+///
+/// # Laws
+///
+/// * Associativity: `(a ⊕ b) ⊕ c == a ⊕ (b ⊕ c)`
+///
+/// Commutativity is not required. Nodes are always combined in strict left-to-right element order,
+/// so `combine` is always called with `rhs` covering elements right after the elements of `self`.
+/// It allows non-commutative operations such as string concatenation and function composition.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{Combine, PostfixSegmentTree};
+///
+/// // there's no meaningful identity for the bounding interval
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Interval(f64, f64);
+///
+/// impl Combine for Interval {
+///     fn combine(&mut self, rhs: &Self) {
+///         self.0 = self.0.min(rhs.0);
+///         self.1 = self.1.max(rhs.1);
+///     }
+/// }
+///
+/// let tree = PostfixSegmentTree::from_iter([Interval(1.0, 2.0), Interval(0.5, 1.5), Interval(3.0, 4.0)]);
+/// assert_eq!(tree.reduce(0, 2), Some(Interval(0.5, 2.0)));
+/// assert_eq!(tree.reduce(1, 2), Some(Interval(0.5, 4.0)));
+/// assert_eq!(tree.reduce(1, 0), None);
+/// ```
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+/// [`prefix_sum`]: crate::PostfixSegmentTree::prefix_sum
+/// [`sum`]: crate::PostfixSegmentTree::sum
+/// [`reduce`]: crate::PostfixSegmentTree::reduce
+pub trait Combine {
+    /// Combines `rhs` into `self`, which is on the left side of `rhs`.
+    fn combine(&mut self, rhs: &Self);
+
+    /// Overwrites `self` with `left` combined with `right`.
+    ///
+    /// It is used to recalculate a parent node from its children in place.
+    /// The default implementation reuses resources of `self` with [`Clone::clone_from`],
+    /// so heavy types such as big integers and strings don't allocate a temporary value.
+    /// Override it if there's a cheaper way without cloning `left`.
+    fn combine_from(&mut self, left: &Self, right: &Self)
+    where
+        Self: Clone,
+    {
+        self.clone_from(left);
+        self.combine(right);
+    }
+}
+
+/// An associative operation with an identity, which is maintained by [`PostfixSegmentTree`].
+///
+/// [`prefix_sum`], [`sum`], and [`update`] are generalized to the operation,
+/// so the "sum" of elements is `identity()` combined with the elements from left to right.
+///
+/// It is implemented for every type that implements `AddAssign<&T> + Default`,
+/// where `combine` is `+=` and `identity` is `T::default()`.
+///
+/// # Laws
+///
+/// * Associativity of [`Combine`]: `(a ⊕ b) ⊕ c == a ⊕ (b ⊕ c)`
+/// * Identity: `identity() ⊕ a == a ⊕ identity() == a`
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{Aggregate, Combine, PostfixSegmentTree};
+///
+/// #[derive(Clone)]
+/// struct Max(i32);
+///
+/// impl Combine for Max {
+///     fn combine(&mut self, rhs: &Self) {
+///         self.0 = self.0.max(rhs.0);
+///     }
+/// }
+///
+/// impl Aggregate for Max {
+///     fn identity() -> Self {
+///         Max(i32::MIN)
+///     }
+/// }
+///
+/// let tree = PostfixSegmentTree::from_iter([3, 1, 4, 1, 5].map(Max));
+/// assert_eq!(tree.prefix_sum(2).0, 3);
+/// assert_eq!(tree.sum(1, 3).0, 4);
+/// ```
+///
+/// # Derive
+///
+/// With the `derive` feature, `#[derive(Aggregate)]` generates implementations of [`Combine`] and [`Aggregate`]
+/// for a struct with named fields, which combine the fields one by one.
+/// Fields are combined with their own [`Aggregate`] by default,
+/// and fields annotated with `#[aggregate(min)]` or `#[aggregate(max)]` keep the minimum or the maximum.
+/// Those fields should implement [`Bounded`], `PartialOrd`, and `Clone`.
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use postfix_segment_tree::{Aggregate, PostfixSegmentTree};
+///
+/// #[derive(Clone, Aggregate)]
+/// struct Stats {
+///     sum: i64,
+///     #[aggregate(min)]
+///     min: i64,
+///     #[aggregate(max)]
+///     max: i64,
+/// }
+///
+/// let tree = PostfixSegmentTree::from_iter([3, 1, 4, 1, 5].map(|x| Stats { sum: x, min: x, max: x }));
+/// let stats = tree.sum(1, 3);
+/// assert_eq!((stats.sum, stats.min, stats.max), (6, 1, 4));
+/// # }
+/// ```
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+/// [`prefix_sum`]: crate::PostfixSegmentTree::prefix_sum
+/// [`sum`]: crate::PostfixSegmentTree::sum
+/// [`update`]: crate::PostfixSegmentTree::update
+pub trait Aggregate: Combine {
+    /// Returns the identity of [`Combine::combine`].
+    fn identity() -> Self;
+}
+
+impl<T> Combine for T
+where
+    for<'a> T: AddAssign<&'a T>,
+{
+    fn combine(&mut self, rhs: &Self) {
+        *self += rhs;
+    }
+}
+
+impl<T> Aggregate for T
+where
+    for<'a> T: AddAssign<&'a T> + Default,
+{
+    fn identity() -> Self {
+        T::default()
+    }
+}
+
+/// An [`Aggregate`] of the minimum, for range-minimum queries.
+///
+/// `Min(None)` is the identity, and the leftmost one is kept among equal elements.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{Min, PostfixSegmentTree};
+///
+/// let mut tree = PostfixSegmentTree::from_iter([5, 3, 8, 6].map(Min::new));
+/// assert_eq!(tree.sum(0, 4), Min::new(3));
+/// assert_eq!(tree.sum(2, 2), Min::new(6));
+/// assert_eq!(tree.sum(2, 0), Min(None));
+///
+/// tree.update(2, Min::new(1));
+/// tree.insert(0, Min::new(2));
+/// assert_eq!(tree.prefix_sum(3), Min::new(2));
+/// assert_eq!(tree.sum(1, 4), Min::new(1));
+///
+/// tree.remove(3);
+/// assert_eq!(tree.sum(1, 3), Min::new(3));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Min<T>(pub Option<T>);
+
+impl<T> Min<T> {
+    pub fn new(value: T) -> Self {
+        Min(Some(value))
+    }
+
+    /// Returns the minimum, or `None` if it is the identity.
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_ref()
+    }
+
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+}
+
+impl<T> From<T> for Min<T> {
+    fn from(value: T) -> Self {
+        Min::new(value)
+    }
+}
+
+impl<T> Aggregate for Min<T>
+where
+    T: PartialOrd + Clone,
+{
+    fn identity() -> Self {
+        Min(None)
+    }
+}
+
+impl<T> Combine for Min<T>
+where
+    T: PartialOrd + Clone,
+{
+    fn combine(&mut self, rhs: &Self) {
+        if let Some(rhs) = &rhs.0 {
+            match &self.0 {
+                Some(lhs) if lhs <= rhs => {}
+                _ => self.0 = Some(rhs.clone()),
+            }
+        }
+    }
+}
+
+/// An [`Aggregate`] of the maximum, for range-maximum queries.
+///
+/// `Max(None)` is the identity, and the leftmost one is kept among equal elements.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{Max, PostfixSegmentTree};
+///
+/// let mut tree = PostfixSegmentTree::from_iter([5, 3, 8, 6].map(Max::new));
+/// assert_eq!(tree.sum(0, 4), Max::new(8));
+/// assert_eq!(tree.sum(0, 2), Max::new(5));
+///
+/// tree.update(2, Max::new(1));
+/// assert_eq!(tree.sum(0, 4), Max::new(6));
+///
+/// tree.push(Max::new(9));
+/// assert_eq!(tree.postfix_sum(3), Max::new(9));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Max<T>(pub Option<T>);
+
+impl<T> Max<T> {
+    pub fn new(value: T) -> Self {
+        Max(Some(value))
+    }
+
+    /// Returns the maximum, or `None` if it is the identity.
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_ref()
+    }
+
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+}
+
+impl<T> From<T> for Max<T> {
+    fn from(value: T) -> Self {
+        Max::new(value)
+    }
+}
+
+impl<T> Aggregate for Max<T>
+where
+    T: PartialOrd + Clone,
+{
+    fn identity() -> Self {
+        Max(None)
+    }
+}
+
+impl<T> Combine for Max<T>
+where
+    T: PartialOrd + Clone,
+{
+    fn combine(&mut self, rhs: &Self) {
+        if let Some(rhs) = &rhs.0 {
+            match &self.0 {
+                Some(lhs) if lhs >= rhs => {}
+                _ => self.0 = Some(rhs.clone()),
+            }
+        }
+    }
+}
+
+/// An [`Aggregate`] of bitwise exclusive or, for range-xor queries.
+///
+/// `Xor(T::default())`, which is zero for integers, is the identity.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{PostfixSegmentTree, Xor};
+///
+/// let mut tree = PostfixSegmentTree::from_iter([0b0011u8, 0b0101, 0b1001].map(Xor));
+/// assert_eq!(tree.prefix_sum(2), Xor(0b0110));
+/// assert_eq!(tree.sum(1, 2), Xor(0b1100));
+///
+/// tree.update(0, Xor(0b1111));
+/// assert_eq!(tree.prefix_sum(3), Xor(0b0011));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Xor<T>(pub T);
+
+impl<T> Aggregate for Xor<T>
+where
+    for<'a> T: BitXorAssign<&'a T> + Default,
+{
+    fn identity() -> Self {
+        Xor(T::default())
+    }
+}
+
+impl<T> Combine for Xor<T>
+where
+    for<'a> T: BitXorAssign<&'a T>,
+{
+    fn combine(&mut self, rhs: &Self) {
+        self.0 ^= &rhs.0;
+    }
+}
+
+/// An [`Aggregate`] of the greatest common divisor, for range-gcd queries on unsigned integers.
+///
+/// `Gcd(0)` is the identity since `gcd(0, x) == x`, so the gcd of an empty range is 0.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{Gcd, PostfixSegmentTree};
+///
+/// let mut tree = PostfixSegmentTree::from_iter([12u32, 18, 27, 9].map(Gcd));
+/// assert_eq!(tree.prefix_sum(2), Gcd(6));
+/// assert_eq!(tree.sum(1, 3), Gcd(9));
+/// assert_eq!(tree.sum(1, 0), Gcd(0));
+///
+/// tree.update(3, Gcd(4));
+/// assert_eq!(tree.sum(0, 4), Gcd(1));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Gcd<T>(pub T);
+
+macro_rules! impl_gcd {
+    ($($ty:ty),*) => {
+        $(
+            impl Aggregate for Gcd<$ty> {
+                fn identity() -> Self {
+                    Gcd(0)
+                }
+            }
+
+            impl Combine for Gcd<$ty> {
+                fn combine(&mut self, rhs: &Self) {
+                    let mut a = self.0;
+                    let mut b = rhs.0;
+                    while b != 0 {
+                        (a, b) = (b, a % b);
+                    }
+
+                    self.0 = a;
+                }
+            }
+        )*
+    };
+}
+
+impl_gcd!(u8, u16, u32, u64, u128, usize);
+
+/// An [`Aggregate`] of the count, the sum, the minimum, and the maximum,
+/// so a single tree answers range count, sum, mean, min, and max queries.
+///
+/// The identity is the statistics of no elements, which has no minimum, maximum, and mean.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{PostfixSegmentTree, Stats};
+///
+/// let mut latencies = PostfixSegmentTree::from_iter([12.0, 30.0, 8.0, 50.0].map(Stats::new));
+/// let stats = latencies.sum(0, 3);
+/// assert_eq!(stats.count(), 3);
+/// assert_eq!(*stats.sum(), 50.0);
+/// assert_eq!(stats.min(), Some(&8.0));
+/// assert_eq!(stats.max(), Some(&30.0));
+///
+/// latencies.update(1, Stats::new(10.0));
+/// assert_eq!(latencies.sum(0, 2).mean(), Some(11.0));
+/// assert_eq!(latencies.sum(2, 0).mean(), None);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Stats<T> {
+    count: usize,
+    sum: T,
+    min: Min<T>,
+    max: Max<T>,
+}
+
+impl<T> Stats<T>
+where
+    T: Clone,
+{
+    /// Creates statistics of a single `value`.
+    pub fn new(value: T) -> Self {
+        Stats {
+            count: 1,
+            sum: value.clone(),
+            min: Min::new(value.clone()),
+            max: Max::new(value),
+        }
+    }
+}
+
+impl<T> Stats<T> {
+    /// Returns the number of elements.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the sum of elements.
+    pub fn sum(&self) -> &T {
+        &self.sum
+    }
+
+    /// Returns the minimum, or `None` if there are no elements.
+    pub fn min(&self) -> Option<&T> {
+        self.min.get()
+    }
+
+    /// Returns the maximum, or `None` if there are no elements.
+    pub fn max(&self) -> Option<&T> {
+        self.max.get()
+    }
+
+    /// Returns the arithmetic mean, or `None` if there are no elements.
+    ///
+    /// The sum is converted with [`ToF64`], which may lose precision for large 64-bit and 128-bit integers.
+    pub fn mean(&self) -> Option<f64>
+    where
+        T: ToF64,
+    {
+        if self.count == 0 {
+            return None;
+        }
+
+        Some(self.sum.to_f64() / self.count as f64)
+    }
+}
+
+impl<T> From<T> for Stats<T>
+where
+    T: Clone,
+{
+    fn from(value: T) -> Self {
+        Stats::new(value)
+    }
+}
+
+impl<T> Aggregate for Stats<T>
+where
+    for<'a> T: AddAssign<&'a T> + Default + PartialOrd + Clone,
+{
+    fn identity() -> Self {
+        Stats {
+            count: 0,
+            sum: T::default(),
+            min: Min(None),
+            max: Max(None),
+        }
+    }
+}
+
+impl<T> Combine for Stats<T>
+where
+    for<'a> T: AddAssign<&'a T> + PartialOrd + Clone,
+{
+    fn combine(&mut self, rhs: &Self) {
+        self.count += rhs.count;
+        self.sum += &rhs.sum;
+        self.min.combine(&rhs.min);
+        self.max.combine(&rhs.max);
+    }
+}
+
+/// Numbers that can be converted to `f64` with `as`, which is lossy for large 64-bit and 128-bit integers.
+///
+/// It's used by [`Stats::mean`], since `Into<f64>` is not implemented for `i64`, `u64`, and wider integers.
+pub trait ToF64 {
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! impl_to_f64 {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl ToF64 for $ty {
+                fn to_f64(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_to_f64!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+/// An [`Aggregate`] of the maximum subarray sum, for "maximum sum of a non-empty subarray within a range" queries.
+///
+/// It carries the total, the best prefix sum, the best suffix sum, and the best subarray sum of its elements,
+/// which are combined like Kadane's algorithm.
+/// The identity has no elements, so it has the zero total and no best sums.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{MaxSubarray, PostfixSegmentTree};
+///
+/// let mut tree = PostfixSegmentTree::from_iter([-2, 1, -3, 4, -1, 2, 1, -5, 4].map(MaxSubarray::new));
+/// assert_eq!(tree.sum(0, 9).best(), Some(&6)); // [4, -1, 2, 1]
+/// assert_eq!(tree.sum(0, 3).best(), Some(&1));
+/// assert_eq!(tree.sum(7, 1).best(), Some(&-5));
+/// assert_eq!(tree.sum(7, 0).best(), None);
+///
+/// tree.update(4, MaxSubarray::new(-10));
+/// assert_eq!(tree.sum(0, 9).best(), Some(&4));
+/// assert_eq!(tree.sum(0, 9).best_prefix(), Some(&0)); // [-2, 1, -3, 4]
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct MaxSubarray<T> {
+    total: T,
+    prefix: Option<T>,
+    suffix: Option<T>,
+    best: Option<T>,
+}
+
+impl<T> MaxSubarray<T>
+where
+    T: Clone,
+{
+    /// Creates a node of a single `value`.
+    pub fn new(value: T) -> Self {
+        MaxSubarray {
+            total: value.clone(),
+            prefix: Some(value.clone()),
+            suffix: Some(value.clone()),
+            best: Some(value),
+        }
+    }
+}
+
+impl<T> MaxSubarray<T> {
+    /// Returns the sum of all elements.
+    pub fn total(&self) -> &T {
+        &self.total
+    }
+
+    /// Returns the maximum sum of non-empty prefixes, or `None` if there are no elements.
+    pub fn best_prefix(&self) -> Option<&T> {
+        self.prefix.as_ref()
+    }
+
+    /// Returns the maximum sum of non-empty suffixes, or `None` if there are no elements.
+    pub fn best_suffix(&self) -> Option<&T> {
+        self.suffix.as_ref()
+    }
+
+    /// Returns the maximum sum of non-empty subarrays, or `None` if there are no elements.
+    pub fn best(&self) -> Option<&T> {
+        self.best.as_ref()
+    }
+}
+
+impl<T> From<T> for MaxSubarray<T>
+where
+    T: Clone,
+{
+    fn from(value: T) -> Self {
+        MaxSubarray::new(value)
+    }
+}
+
+impl<T> Aggregate for MaxSubarray<T>
+where
+    for<'a> T: AddAssign<&'a T> + Default + PartialOrd + Clone,
+{
+    fn identity() -> Self {
+        MaxSubarray {
+            total: T::default(),
+            prefix: None,
+            suffix: None,
+            best: None,
+        }
+    }
+}
+
+impl<T> Combine for MaxSubarray<T>
+where
+    for<'a> T: AddAssign<&'a T> + PartialOrd + Clone,
+{
+    fn combine(&mut self, rhs: &Self) {
+        fn add<T: for<'a> AddAssign<&'a T> + Clone>(lhs: &T, rhs: &T) -> T {
+            let mut sum = lhs.clone();
+            sum += rhs;
+            sum
+        }
+
+        // keeps `lhs` among equal sums
+        fn max<T: PartialOrd>(lhs: Option<T>, rhs: Option<T>) -> Option<T> {
+            match (lhs, rhs) {
+                (Some(lhs), Some(rhs)) if lhs < rhs => Some(rhs),
+                (None, rhs) => rhs,
+                (lhs, _) => lhs,
+            }
+        }
+
+        let crossing = match (&self.suffix, &rhs.prefix) {
+            (Some(suffix), Some(prefix)) => Some(add(suffix, prefix)),
+            (suffix, prefix) => suffix.clone().or_else(|| prefix.clone()),
+        };
+        self.best = max(max(self.best.take(), crossing), rhs.best.clone());
+        self.prefix = max(
+            self.prefix.take(),
+            rhs.prefix.as_ref().map(|prefix| add(&self.total, prefix)),
+        );
+        self.suffix = max(
+            self.suffix.as_ref().map(|suffix| add(suffix, &rhs.total)),
+            rhs.suffix.clone(),
+        );
+        self.total += &rhs.total;
+    }
+}
+
+/// An [`Aggregate`] of addition for numeric types that implement `Add` and `Sum`,
+/// rather than `AddAssign<&T>` and `Default`.
+///
+/// Numeric types of third-party crates, such as big integers, decimals, and fixed-point numbers,
+/// usually implement `Add<Output = T>` and [`Sum`], so they can be stored without bespoke trait impls.
+/// `Additive(T::sum(iter::empty()))`, which is zero, is the identity.
+/// A node is recalculated by adding clones, so types that implement `AddAssign<&T>` and `Default` are cheaper to store directly.
+/// Types that implement `num_traits::Zero` but not `Sum` can use `Num` of the `num-traits` feature instead.
+///
+/// # Examples
+///
+/// ```
+/// use std::iter::Sum;
+/// use std::ops::Add;
+///
+/// use postfix_segment_tree::{Additive, PostfixSegmentTree};
+///
+/// // a third-party numeric type that doesn't implement `AddAssign<&Self>` nor `Default`
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Cents(i64);
+///
+/// impl Add for Cents {
+///     type Output = Cents;
+///
+///     fn add(self, rhs: Self) -> Self {
+///         Cents(self.0 + rhs.0)
+///     }
+/// }
+///
+/// impl Sum for Cents {
+///     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+///         iter.fold(Cents(0), Add::add)
+///     }
+/// }
+///
+/// let mut tree = PostfixSegmentTree::from_iter([150, 275, 30].map(|c| Additive(Cents(c))));
+/// assert_eq!(tree.prefix_sum(2), Additive(Cents(425)));
+///
+/// tree.update(0, Additive(Cents(-50)));
+/// assert_eq!(tree.sum(0, 3), Additive(Cents(255)));
+/// assert_eq!(tree.sum(1, 0), Additive(Cents(0)));
+/// ```
+///
+/// [`Sum`]: std::iter::Sum
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Additive<T>(pub T);
+
+impl<T> Aggregate for Additive<T>
+where
+    T: Add<Output = T> + iter::Sum + Clone,
+{
+    fn identity() -> Self {
+        Additive(iter::empty::<T>().sum())
+    }
+}
+
+impl<T> Combine for Additive<T>
+where
+    T: Add<Output = T> + Clone,
+{
+    fn combine(&mut self, rhs: &Self) {
+        self.0 = self.0.clone() + rhs.0.clone();
+    }
+}
+
+/// An element of a `num-traits` numeric type, which is summed with `Add` from [`Zero::zero`].
+///
+/// It requires the `num-traits` feature. Unlike [`Additive`], the identity comes from `Zero` instead of `Sum`,
+/// and [`CheckedCombine`] is implemented with `CheckedAdd`, so checked queries and updates are available too.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "num-traits")]
+/// # {
+/// use postfix_segment_tree::{Num, PostfixSegmentTree};
+///
+/// let mut tree = PostfixSegmentTree::from_iter([100u8, 100, 100].map(Num));
+/// assert_eq!(tree.prefix_sum(2), Num(200));
+/// assert_eq!(tree.checked_sum(0, 3), None);
+/// assert_eq!(tree.checked_update(2, Num(5)), Ok(()));
+/// assert_eq!(tree.checked_sum(0, 3), Some(Num(205)));
+/// # }
+/// ```
+///
+/// [`Zero::zero`]: num_traits::Zero::zero
+/// [`CheckedCombine`]: crate::CheckedCombine
+#[cfg(feature = "num-traits")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Num<T>(pub T);
+
+#[cfg(feature = "num-traits")]
+impl<T> Aggregate for Num<T>
+where
+    T: Zero + Clone,
+{
+    fn identity() -> Self {
+        Num(T::zero())
+    }
+}
+
+#[cfg(feature = "num-traits")]
+impl<T> Combine for Num<T>
+where
+    T: Zero + Clone,
+{
+    fn combine(&mut self, rhs: &Self) {
+        self.0 = mem::replace(&mut self.0, T::zero()) + rhs.0.clone();
+    }
+}
+
+/// Types that have the minimum and the maximum value.
+///
+/// They are the identities of the maximum and the minimum respectively.
+pub trait Bounded {
+    const MIN: Self;
+    const MAX: Self;
+}
+
+macro_rules! impl_bounded {
+    ($($ty:ty => $min:expr, $max:expr;)*) => {
+        $(
+            impl Bounded for $ty {
+                const MIN: Self = $min;
+                const MAX: Self = $max;
+            }
+        )*
+    };
+}
+
+impl_bounded! {
+    u8 => u8::MIN, u8::MAX;
+    u16 => u16::MIN, u16::MAX;
+    u32 => u32::MIN, u32::MAX;
+    u64 => u64::MIN, u64::MAX;
+    u128 => u128::MIN, u128::MAX;
+    usize => usize::MIN, usize::MAX;
+    i8 => i8::MIN, i8::MAX;
+    i16 => i16::MIN, i16::MAX;
+    i32 => i32::MIN, i32::MAX;
+    i64 => i64::MIN, i64::MAX;
+    i128 => i128::MIN, i128::MAX;
+    isize => isize::MIN, isize::MAX;
+    f32 => f32::NEG_INFINITY, f32::INFINITY;
+    f64 => f64::NEG_INFINITY, f64::INFINITY;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PostfixSegmentTree;
+
+    #[derive(Clone)]
+    struct Concat(String);
+
+    impl Aggregate for Concat {
+        fn identity() -> Self {
+            Concat(String::new())
+        }
+    }
+
+    impl Combine for Concat {
+        fn combine(&mut self, rhs: &Self) {
+            self.0.push_str(&rhs.0);
+        }
+    }
+
+    #[test]
+    fn test_combine_order_is_left_to_right() {
+        const N: usize = 40;
+        let elements: Vec<String> = (0..N).map(|i| format!("{i},")).collect();
+
+        let mut tree = PostfixSegmentTree::new();
+        for element in &elements {
+            tree.push(Concat(element.clone()));
+        }
+
+        for index in 0..=N {
+            assert_eq!(tree.prefix_sum(index).0, elements[..index].concat());
+            for len in 0..=N - index {
+                assert_eq!(
+                    tree.sum(index, len).0,
+                    elements[index..index + len].concat()
+                );
+            }
+        }
+
+        // nodes are also recalculated in order
+        tree.insert(3, Concat("x,".to_string()));
+        tree.update(10, Concat("y,".to_string()));
+        tree.remove(0);
+        let mut elements = elements;
+        elements.insert(3, "x,".to_string());
+        elements[10] = "y,".to_string();
+        elements.remove(0);
+        for index in 0..=elements.len() {
+            assert_eq!(tree.postfix_sum(index).0, elements[index..].concat());
+        }
+    }
+
+    #[test]
+    fn test_wrapping_and_saturating() {
+        use std::num::{Saturating, Wrapping};
+
+        let elements: Vec<u8> = (0..50).map(|i| (i * 37 % 256) as u8).collect();
+        let wrapping = PostfixSegmentTree::from_iter(elements.iter().copied().map(Wrapping));
+        let saturating = PostfixSegmentTree::from_iter(elements.iter().copied().map(Saturating));
+        for index in 0..=elements.len() {
+            for len in 0..=elements.len() - index {
+                let range = &elements[index..index + len];
+                let sum: u32 = range.iter().map(|&e| e as u32).sum();
+                assert_eq!(wrapping.sum(index, len), Wrapping(sum as u8));
+                assert_eq!(
+                    saturating.sum(index, len),
+                    Saturating(u32::min(sum, u8::MAX as u32) as u8)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stats_matches_naive() {
+        let elements: Vec<i32> = (0..40).map(|i| (i * 29 % 17) - 8).collect();
+        let tree = PostfixSegmentTree::from_iter(elements.iter().copied().map(Stats::new));
+        for index in 0..=elements.len() {
+            for len in 0..=elements.len() - index {
+                let range = &elements[index..index + len];
+                let stats = tree.sum(index, len);
+                assert_eq!(stats.count(), len);
+                assert_eq!(*stats.sum(), range.iter().sum::<i32>());
+                assert_eq!(stats.min(), range.iter().min());
+                assert_eq!(stats.max(), range.iter().max());
+                assert_eq!(
+                    stats.mean(),
+                    (len > 0).then(|| range.iter().sum::<i32>() as f64 / len as f64)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stats_mean_of_wide_integers() {
+        let counters = PostfixSegmentTree::from_iter([3u64, 4, 8].map(Stats::new));
+        assert_eq!(counters.sum(0, 3).mean(), Some(5.0));
+
+        let offsets = PostfixSegmentTree::from_iter([-3i128, 4, -10].map(Stats::new));
+        assert_eq!(offsets.sum(0, 3).mean(), Some(-3.0));
+        assert_eq!(offsets.sum(1, 0).mean(), None);
+    }
+
+    #[test]
+    fn test_max_subarray_matches_naive() {
+        let elements: Vec<i32> = (0..40).map(|i| (i * 37 % 23) - 12).collect();
+        let tree = PostfixSegmentTree::from_iter(elements.iter().copied().map(MaxSubarray::new));
+        for index in 0..=elements.len() {
+            for len in 0..=elements.len() - index {
+                let range = &elements[index..index + len];
+                let sums = |start: usize| {
+                    (start + 1..=len).map(move |end| range[start..end].iter().sum::<i32>())
+                };
+                let node = tree.sum(index, len);
+                assert_eq!(*node.total(), range.iter().sum::<i32>());
+                assert_eq!(node.best_prefix().copied(), sums(0).max());
+                assert_eq!(
+                    node.best_suffix().copied(),
+                    (0..len)
+                        .map(|start| range[start..].iter().sum::<i32>())
+                        .max()
+                );
+                assert_eq!(node.best().copied(), (0..len).flat_map(sums).max());
+            }
+        }
+    }
+}
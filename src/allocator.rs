@@ -0,0 +1,113 @@
+use std::alloc::Allocator;
+
+use crate::PostfixSegmentTree;
+
+impl<T, A: Allocator> PostfixSegmentTree<T, A> {
+    /// Constructs a new, empty tree whose nodes are allocated with `alloc`.
+    ///
+    /// It requires the `allocator_api` feature and a nightly compiler.
+    /// Element access, queries, and mutations such as [`push`], [`update`], [`insert`], and [`remove`]
+    /// are available for any allocator, while the other operations still require [`Global`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(allocator_api)]
+    /// use std::alloc::System;
+    ///
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::new_in(System);
+    /// tree.push(1);
+    /// tree.push(2);
+    /// tree.update(0, 3);
+    /// assert_eq!(tree.prefix_sum(2), 5);
+    /// ```
+    ///
+    /// [`push`]: PostfixSegmentTree::push
+    /// [`update`]: PostfixSegmentTree::update
+    /// [`insert`]: PostfixSegmentTree::insert
+    /// [`remove`]: PostfixSegmentTree::remove
+    /// [`Global`]: std::alloc::Global
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            nodes: Vec::new_in(alloc),
+            len: 0,
+        }
+    }
+
+    /// Returns a reference to the allocator of nodes.
+    pub fn allocator(&self) -> &A {
+        self.nodes.allocator()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{AllocError, Global, Layout};
+    use std::cell::Cell;
+    use std::ptr::NonNull;
+
+    use super::*;
+
+    /// Counts live allocations, and delegates to [`Global`].
+    #[derive(Default)]
+    struct Counting {
+        live: Cell<usize>,
+    }
+
+    unsafe impl Allocator for &Counting {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.live.set(self.live.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.live.set(self.live.get() - 1);
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn test_operations_in_allocator() {
+        let alloc = Counting::default();
+        {
+            let mut tree = PostfixSegmentTree::new_in(&alloc);
+            let mut model = Vec::new();
+            for step in 0..200u64 {
+                let value = (step * 7919) % 101;
+                match step % 5 {
+                    0 | 1 => {
+                        tree.push(value);
+                        model.push(value);
+                    }
+                    2 if !model.is_empty() => {
+                        let index = (value as usize) % model.len();
+                        tree.update(index, value);
+                        model[index] = value;
+                    }
+                    3 => {
+                        let index = (value as usize) % (model.len() + 1);
+                        tree.insert(index, value);
+                        model.insert(index, value);
+                    }
+                    4 if !model.is_empty() => {
+                        let index = (value as usize) % model.len();
+                        assert_eq!(tree.remove(index), model.remove(index));
+                    }
+                    _ => assert_eq!(tree.pop(), model.pop()),
+                }
+
+                assert_eq!(tree.len(), model.len());
+                for index in 0..=model.len() {
+                    assert_eq!(tree.prefix_sum(index), model[..index].iter().sum());
+                }
+                assert_eq!(tree.get(model.len() / 2), model.get(model.len() / 2));
+            }
+
+            assert_eq!(alloc.live.get(), 1);
+            assert!(std::ptr::eq(*tree.allocator(), &alloc));
+        }
+        assert_eq!(alloc.live.get(), 0);
+    }
+}
@@ -0,0 +1,56 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+
+/// Generates a sequence of elements, and builds a tree of them with [`from_vec`].
+///
+/// [`from_vec`]: PostfixSegmentTree::from_vec
+impl<'a, T> Arbitrary<'a> for PostfixSegmentTree<T>
+where
+    T: Arbitrary<'a> + Combine + Clone,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Vec::<T>::arbitrary(u).map(Self::from_vec)
+    }
+
+    fn arbitrary_take_rest(u: Unstructured<'a>) -> Result<Self> {
+        Vec::<T>::arbitrary_take_rest(u).map(Self::from_vec)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Vec::<T>::size_hint(depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::Wrapping;
+
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_builds_from_elements() {
+        // odd bytes keep the generated sequence going
+        let data: Vec<u8> = (0..=255).map(|byte| byte | 1).collect();
+        let elements = Vec::<Wrapping<u32>>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let tree =
+            PostfixSegmentTree::<Wrapping<u32>>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+        assert!(!elements.is_empty());
+        assert_eq!(tree.len(), elements.len());
+        assert_eq!(tree.validate(), Ok(()));
+        for index in 0..=elements.len() {
+            assert_eq!(tree.prefix_sum(index), elements[..index].iter().sum());
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_take_rest() {
+        let data: Vec<u8> = (0..64).collect();
+        let elements = Vec::<u16>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+        let tree =
+            PostfixSegmentTree::<u16>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), elements);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::internal::node_id::LeafNodeId;
+
+/// A guard that defers recalculation of partial sums, which is returned by [`PostfixSegmentTree::begin_batch`].
+///
+/// Modifications through the guard only write leaf nodes, and track the first modified index.
+/// Partial sums after it are recalculated in a single *O*([`nodes_len`]) pass on [`commit`] or drop.
+/// Sum queries are not available until then, since the guard borrows the tree mutably.
+///
+/// [`nodes_len`]: PostfixSegmentTree::nodes_len
+/// [`commit`]: Batch::commit
+pub struct Batch<'a, T>
+where
+    T: Combine + Clone,
+{
+    tree: &'a mut PostfixSegmentTree<T>,
+    // partial sums of elements from this index are DIRTY
+    dirty_from: usize,
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Begins a batch of modifications, whose recalculation is deferred until [`Batch::commit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    ///
+    /// let mut batch = tree.begin_batch();
+    /// batch.update(0, 10);
+    /// batch.insert(1, 20);
+    /// batch.push(4);
+    /// batch.remove(2);
+    /// batch.commit();
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![10, 20, 3, 4]);
+    /// assert_eq!(tree.prefix_sum(4), 37);
+    /// ```
+    pub fn begin_batch(&mut self) -> Batch<'_, T> {
+        let dirty_from = self.len();
+        Batch {
+            tree: self,
+            dirty_from,
+        }
+    }
+}
+
+impl<T> Batch<'_, T>
+where
+    T: Combine + Clone,
+{
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns an element at `index`.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.tree.get(index)
+    }
+
+    /// Deferred version of [`PostfixSegmentTree::update`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn update(&mut self, index: usize, element: T) {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        *self.tree.get_leaf_node_mut(id) = element; // DIRTY: parents of `id`
+        self.mark_dirty(index);
+    }
+
+    /// Deferred version of [`PostfixSegmentTree::push`].
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, element: T) {
+        assert!(self.tree.remaining_capacity() > 0, "capacity overflow");
+
+        // parents are calculated from possibly DIRTY children, which are recalculated on commit.
+        self.tree.push_leaf(element);
+    }
+
+    /// Deferred version of [`PostfixSegmentTree::insert`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`]) for shifting elements
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn insert(&mut self, index: usize, element: T) {
+        assert!(self.tree.remaining_capacity() > 0, "capacity overflow");
+        assert!(index <= self.len());
+
+        self.tree.push_leaf(element);
+        self.tree
+            .rotate_leaf_nodes_right_by_one_dirty(LeafNodeId::new(index)); // DIRTY: all parents of `>= index`
+        self.mark_dirty(index);
+    }
+
+    /// Deferred version of [`PostfixSegmentTree::remove`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`]) for shifting elements
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len());
+
+        self.tree
+            .rotate_leaf_nodes_left_by_one_dirty(LeafNodeId::new(index)); // DIRTY: all parents of `>= index`
+        self.mark_dirty(index);
+        self.tree.pop_leaf()
+    }
+
+    /// Recalculates partial sums of modified elements, and ends the batch.
+    ///
+    /// It is also called when the guard is dropped.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`nodes_len`])
+    ///
+    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    pub fn commit(self) {
+        // recalculated on drop
+    }
+
+    fn mark_dirty(&mut self, index: usize) {
+        self.dirty_from = usize::min(self.dirty_from, index);
+    }
+}
+
+impl<T> Drop for Batch<'_, T>
+where
+    T: Combine + Clone,
+{
+    fn drop(&mut self) {
+        if self.dirty_from < self.tree.len() {
+            let id = LeafNodeId::new(self.dirty_from);
+            self.tree.recalculate_nodes_after_bulk_update(id); // CLEAN: all parents of `>= dirty_from`
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_matches_naive() {
+        let mut model: Vec<u32> = (0..10).collect();
+        let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+        for round in 0..10u32 {
+            let mut batch = tree.begin_batch();
+            for step in 0..10u32 {
+                let value = round * 100 + step;
+                match (round + step) % 4 {
+                    0 => {
+                        batch.push(value);
+                        model.push(value);
+                    }
+                    1 => {
+                        let index = (value as usize) % model.len();
+                        batch.update(index, value);
+                        model[index] = value;
+                    }
+                    2 => {
+                        let index = (value as usize) % (model.len() + 1);
+                        batch.insert(index, value);
+                        model.insert(index, value);
+                    }
+                    _ => {
+                        let index = (value as usize) % model.len();
+                        assert_eq!(batch.remove(index), model.remove(index));
+                    }
+                }
+            }
+            batch.commit();
+
+            assert!(tree.iter().eq(model.iter()));
+            for index in 0..=model.len() {
+                assert_eq!(tree.prefix_sum(index), model[..index].iter().sum());
+            }
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::cmp::Ordering;
+use std::ops::{Range, RangeBounds};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::internal::node_id::{LeafNodeId, NodeId};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::range::to_range;
+use crate::internal::skipping_iterator::SkippingIterator;
+
+/// A node of [`BeatsPostfixSegmentTree`], which has enough to apply chmin or chmax to the whole node at once
+/// if only the maximums or the minimums are changed.
+#[derive(Copy, Clone, Debug)]
+struct BeatsNode {
+    sum: i64,
+    max: i64,
+    second_max: i64,
+    max_count: usize,
+    min: i64,
+    second_min: i64,
+    min_count: usize,
+}
+
+impl BeatsNode {
+    fn leaf(value: i64) -> Self {
+        Self {
+            sum: value,
+            max: value,
+            second_max: i64::MIN,
+            max_count: 1,
+            min: value,
+            second_min: i64::MAX,
+            min_count: 1,
+        }
+    }
+
+    /// Returns `true` if the node has a single distinct value, which includes leaf nodes.
+    fn is_flat(&self) -> bool {
+        self.min == self.max
+    }
+
+    /// Applies chmin to every element, which should only change the maximums.
+    fn apply_chmin(&mut self, value: i64) {
+        if self.max <= value {
+            return;
+        }
+
+        debug_assert!(self.is_flat() || self.second_max < value);
+        self.sum -= (self.max - value) * self.max_count as i64;
+        if self.is_flat() {
+            self.min = value;
+        } else if self.second_min == self.max {
+            self.second_min = value;
+        }
+        self.max = value;
+    }
+
+    /// Applies chmax to every element, which should only change the minimums.
+    fn apply_chmax(&mut self, value: i64) {
+        if self.min >= value {
+            return;
+        }
+
+        debug_assert!(self.is_flat() || self.second_min > value);
+        self.sum += (value - self.min) * self.min_count as i64;
+        if self.is_flat() {
+            self.max = value;
+        } else if self.second_max == self.min {
+            self.second_max = value;
+        }
+        self.min = value;
+    }
+
+    /// Returns the child with pending chmin and chmax of the parent applied.
+    ///
+    /// The maximum and the minimum of the parent are always up to date,
+    /// and they only differ from those of the children by pending chmin and chmax.
+    fn clamped(mut self, parent: &Self) -> Self {
+        self.apply_chmin(parent.max);
+        self.apply_chmax(parent.min);
+        self
+    }
+}
+
+impl Combine for BeatsNode {
+    fn combine(&mut self, rhs: &Self) {
+        self.sum += rhs.sum;
+
+        match self.max.cmp(&rhs.max) {
+            Ordering::Greater => self.second_max = self.second_max.max(rhs.max),
+            Ordering::Equal => {
+                self.second_max = self.second_max.max(rhs.second_max);
+                self.max_count += rhs.max_count;
+            }
+            Ordering::Less => {
+                self.second_max = self.max.max(rhs.second_max);
+                self.max = rhs.max;
+                self.max_count = rhs.max_count;
+            }
+        }
+
+        match self.min.cmp(&rhs.min) {
+            Ordering::Less => self.second_min = self.second_min.min(rhs.min),
+            Ordering::Equal => {
+                self.second_min = self.second_min.min(rhs.second_min);
+                self.min_count += rhs.min_count;
+            }
+            Ordering::Greater => {
+                self.second_min = self.min.min(rhs.second_min);
+                self.min = rhs.min;
+                self.min_count = rhs.min_count;
+            }
+        }
+    }
+}
+
+/// A variant of [`PostfixSegmentTree`] of `i64`, which supports range chmin, range chmax, and range sums
+/// with the Segment Tree Beats technique.
+///
+/// Each node has the sum, the maximum, the second maximum, and the count of the maximum, and the same for the minimum.
+/// A range chmin only descends into nodes where it changes more than the maximums,
+/// and pending chmin and chmax of a node are pushed down to its children before descending into them.
+/// So [`range_chmin`] and [`range_chmax`] are amortized *O*(log² [`len`]),
+/// while [`sum`] is *O*(log [`len`]).
+///
+/// Pending chmin and chmax are stored per node, so elements can't be shifted by `insert` or `remove`.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::BeatsPostfixSegmentTree;
+///
+/// let mut tree = BeatsPostfixSegmentTree::from_iter([5, 1, 8, 3, 9, 2]);
+/// tree.range_chmin(0..4, 4);
+/// assert_eq!(tree.sum(0, 6), 4 + 1 + 4 + 3 + 9 + 2);
+///
+/// tree.range_chmax(1.., 3);
+/// assert_eq!(tree.get(1), 3);
+/// assert_eq!(tree.get(5), 3);
+/// assert_eq!(tree.sum(3, 3), 3 + 9 + 3);
+/// ```
+///
+/// [`range_chmin`]: BeatsPostfixSegmentTree::range_chmin
+/// [`range_chmax`]: BeatsPostfixSegmentTree::range_chmax
+/// [`sum`]: BeatsPostfixSegmentTree::sum
+/// [`len`]: BeatsPostfixSegmentTree::len
+pub struct BeatsPostfixSegmentTree {
+    // a node is up to date except for pending chmin and chmax of its ancestors
+    nodes: PostfixSegmentTree<BeatsNode>,
+}
+
+impl BeatsPostfixSegmentTree {
+    pub fn new() -> Self {
+        Self {
+            nodes: PostfixSegmentTree::new(),
+        }
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, element: i64) {
+        // new parent nodes are roots of subtrees without ancestors, so nothing is pending on their children.
+        self.nodes.push(BeatsNode::leaf(element));
+    }
+
+    /// Returns an element at `index`, including pending chmin and chmax.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: BeatsPostfixSegmentTree::len
+    pub fn get(&self, index: usize) -> i64 {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        let mut element = self.nodes.get_leaf_node(id).max;
+        for parent in ParentsIterator::new(id, self.len()) {
+            let parent = self.nodes.get_node(parent);
+            element = element.clamp(parent.min, parent.max);
+        }
+
+        element
+    }
+
+    /// Replaces every element `x` in `range` with `min(x, value)`.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(log² [`len`])
+    ///
+    /// [`len`]: BeatsPostfixSegmentTree::len
+    pub fn range_chmin(&mut self, range: impl RangeBounds<usize>, value: i64) {
+        let range = to_range(range, self.len());
+        if range.is_empty() {
+            return;
+        }
+
+        for root in SkippingIterator::new(self.len()) {
+            self.chmin_node(root, &range, value);
+        }
+    }
+
+    /// Replaces every element `x` in `range` with `max(x, value)`.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(log² [`len`])
+    ///
+    /// [`len`]: BeatsPostfixSegmentTree::len
+    pub fn range_chmax(&mut self, range: impl RangeBounds<usize>, value: i64) {
+        let range = to_range(range, self.len());
+        if range.is_empty() {
+            return;
+        }
+
+        for root in SkippingIterator::new(self.len()) {
+            self.chmax_node(root, &range, value);
+        }
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`, including pending chmin and chmax.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: BeatsPostfixSegmentTree::len
+    pub fn prefix_sum(&self, index: usize) -> i64 {
+        self.sum(0, index)
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`, including pending chmin and chmax.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: BeatsPostfixSegmentTree::len
+    pub fn sum(&self, index: usize, len: usize) -> i64 {
+        assert!(index <= self.len());
+        assert!(len <= self.len() - index);
+
+        let range = index..index + len;
+        let mut sum = 0;
+        for root in SkippingIterator::new(self.len()) {
+            self.sum_node(root, *self.nodes.get_node(root), &range, &mut sum);
+        }
+
+        sum
+    }
+
+    fn chmin_node(&mut self, id: NodeId, range: &Range<usize>, value: i64) {
+        let covered = id.first_index()..id.index() + 1;
+        let node = &mut self.nodes.nodes[id.node_index()];
+        if range.end <= covered.start || covered.end <= range.start || node.max <= value {
+            return;
+        }
+
+        if range.start <= covered.start
+            && covered.end <= range.end
+            && (node.is_flat() || node.second_max < value)
+        {
+            node.apply_chmin(value);
+            return;
+        }
+
+        // flat nodes are always applied, so it is never a leaf node
+        self.push_down(id);
+        self.chmin_node(id.left_child(), range, value);
+        self.chmin_node(id.right_child(), range, value);
+        self.nodes.recalculate_node(id);
+    }
+
+    fn chmax_node(&mut self, id: NodeId, range: &Range<usize>, value: i64) {
+        let covered = id.first_index()..id.index() + 1;
+        let node = &mut self.nodes.nodes[id.node_index()];
+        if range.end <= covered.start || covered.end <= range.start || node.min >= value {
+            return;
+        }
+
+        if range.start <= covered.start
+            && covered.end <= range.end
+            && (node.is_flat() || node.second_min > value)
+        {
+            node.apply_chmax(value);
+            return;
+        }
+
+        // flat nodes are always applied, so it is never a leaf node
+        self.push_down(id);
+        self.chmax_node(id.left_child(), range, value);
+        self.chmax_node(id.right_child(), range, value);
+        self.nodes.recalculate_node(id);
+    }
+
+    fn push_down(&mut self, id: NodeId) {
+        let parent = *self.nodes.get_node(id);
+        for child in [id.left_child(), id.right_child()] {
+            let child = &mut self.nodes.nodes[child.node_index()];
+            *child = child.clamped(&parent);
+        }
+    }
+
+    /// Adds the sum of `range` in the subtree of `id` to `sum`,
+    /// where `node` is the node of `id` with pending chmin and chmax of its ancestors applied.
+    fn sum_node(&self, id: NodeId, node: BeatsNode, range: &Range<usize>, sum: &mut i64) {
+        let covered = id.first_index()..id.index() + 1;
+        if range.end <= covered.start || covered.end <= range.start {
+            return;
+        }
+
+        if range.start <= covered.start && covered.end <= range.end {
+            *sum += node.sum;
+            return;
+        }
+
+        for child in [id.left_child(), id.right_child()] {
+            let child_node = self.nodes.get_node(child).clamped(&node);
+            self.sum_node(child, child_node, range, sum);
+        }
+    }
+}
+
+impl Default for BeatsPostfixSegmentTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<i64> for BeatsPostfixSegmentTree {
+    fn from_iter<I: IntoIterator<Item = i64>>(iter: I) -> Self {
+        Self {
+            nodes: iter.into_iter().map(BeatsNode::leaf).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beats_matches_naive() {
+        let mut elements: Vec<i64> = (0..17).map(|i| (i * 37 % 29) - 14).collect();
+        let mut tree = BeatsPostfixSegmentTree::from_iter(elements.iter().copied());
+
+        for step in 0..200i64 {
+            if step % 9 == 0 {
+                tree.push(step % 31 - 15);
+                elements.push(step % 31 - 15);
+            }
+
+            let len = elements.len();
+            let start = (step as usize * 7) % len;
+            let end = start + (step as usize * 5) % (len - start + 1);
+            let value = (step * 13) % 29 - 14;
+            if step % 2 == 0 {
+                tree.range_chmin(start..end, value);
+                for element in &mut elements[start..end] {
+                    *element = (*element).min(value);
+                }
+            } else {
+                tree.range_chmax(start..end, value);
+                for element in &mut elements[start..end] {
+                    *element = (*element).max(value);
+                }
+            }
+
+            for (index, element) in elements.iter().enumerate() {
+                assert_eq!(tree.get(index), *element);
+            }
+            for index in 0..=len {
+                for count in 0..=len - index {
+                    assert_eq!(
+                        tree.sum(index, count),
+                        elements[index..index + count].iter().sum::<i64>()
+                    );
+                }
+            }
+        }
+    }
+}
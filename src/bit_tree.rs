@@ -0,0 +1,257 @@
+use crate::PostfixSegmentTree;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A bitvector with rank and select queries, which stores packed `u64` words with their popcounts in a [`PostfixSegmentTree`].
+///
+/// A bit takes about 2 bits of memory including the tree of popcounts,
+/// rather than about 16 bits of `PostfixSegmentTree<u8>` of 0/1 values.
+/// Popcounts are `u32`, so it can store up to `u32::MAX` bits.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::BitTree;
+///
+/// let mut bits = BitTree::from_iter([true, false, true, true, false]);
+/// assert_eq!(bits.rank1(3), 2);
+/// assert_eq!(bits.select1(2), Some(3));
+///
+/// bits.set(1, true);
+/// bits.set(3, false);
+/// assert_eq!(bits.count_ones(), 3);
+/// assert_eq!(bits.select1(2), Some(2));
+/// assert_eq!(bits.select1(3), None);
+/// ```
+pub struct BitTree {
+    words: Vec<u64>,
+    counts: PostfixSegmentTree<u32>,
+    len: usize,
+}
+
+impl BitTree {
+    /// Creates an empty bitvector.
+    pub fn new() -> Self {
+        Self {
+            words: Vec::new(),
+            counts: PostfixSegmentTree::new(),
+            len: 0,
+        }
+    }
+
+    /// Creates a bitvector of `len` zeros.
+    pub fn zeros(len: usize) -> Self {
+        assert!(len <= u32::MAX as usize, "capacity overflow");
+
+        let words = len.div_ceil(WORD_BITS);
+        Self {
+            words: vec![0; words],
+            counts: PostfixSegmentTree::from_vec(vec![0; words]),
+            len,
+        }
+    }
+
+    /// Returns the number of bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the bitvector has no bits.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bit at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len {
+            return None;
+        }
+
+        let (word, bit) = split(index);
+        Some(self.words[word] >> bit & 1 == 1)
+    }
+
+    /// Sets the bit at `index` to `value`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *words*)
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "index {index} is out of bounds");
+
+        let (word, bit) = split(index);
+        let old = self.words[word];
+        let new = if value {
+            old | 1 << bit
+        } else {
+            old & !(1 << bit)
+        };
+        if old != new {
+            self.words[word] = new;
+            self.counts.update(word, new.count_ones());
+        }
+    }
+
+    /// Appends a bit to the back.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, value: bool) {
+        assert!(self.len < u32::MAX as usize, "capacity overflow");
+
+        let (word, bit) = split(self.len);
+        if bit == 0 {
+            self.words.push(0);
+            self.counts.push(0);
+        }
+        self.len += 1;
+
+        if value {
+            self.words[word] |= 1 << bit;
+            self.counts.update(word, self.words[word].count_ones());
+        }
+    }
+
+    /// Returns the number of ones.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *words*)
+    pub fn count_ones(&self) -> usize {
+        self.counts.prefix_sum(self.counts.len()) as usize
+    }
+
+    /// Returns the number of ones before `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *words*)
+    pub fn rank1(&self, index: usize) -> usize {
+        assert!(index <= self.len, "index {index} is out of bounds");
+
+        let (word, bit) = split(index);
+        let mut rank = self.counts.prefix_sum(word) as usize;
+        if bit > 0 {
+            rank += (self.words[word] & ((1 << bit) - 1)).count_ones() as usize;
+        }
+
+        rank
+    }
+
+    /// Returns the number of zeros before `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *words*)
+    pub fn rank0(&self, index: usize) -> usize {
+        index - self.rank1(index)
+    }
+
+    /// Returns the index of the `k`-th one (0-based), or `None` if there are no more than `k` ones.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *words*)
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        let k = u32::try_from(k).ok()?;
+        let word = self.counts.kth(&k)?;
+
+        // clear lower ones of the word until the one is the lowest
+        let mut bits = self.words[word];
+        for _ in 0..k - self.counts.prefix_sum(word) {
+            bits &= bits - 1;
+        }
+
+        Some(word * WORD_BITS + bits.trailing_zeros() as usize)
+    }
+
+    /// Returns an iterator over bits.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|index| {
+            let (word, bit) = split(index);
+            self.words[word] >> bit & 1 == 1
+        })
+    }
+}
+
+/// Splits a bit index into the index of the word and the bit in the word.
+fn split(index: usize) -> (usize, usize) {
+    (index / WORD_BITS, index % WORD_BITS)
+}
+
+impl Default for BitTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<bool> for BitTree {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut words = Vec::new();
+        let mut len = 0;
+        for value in iter {
+            assert!(len < u32::MAX as usize, "capacity overflow");
+
+            let (word, bit) = split(len);
+            if bit == 0 {
+                words.push(0);
+            }
+            words[word] |= (value as u64) << bit;
+            len += 1;
+        }
+
+        let counts = PostfixSegmentTree::from_iter(words.iter().map(|word| word.count_ones()));
+        Self { words, counts, len }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_tree_matches_naive() {
+        let mut model: Vec<bool> = (0..150).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let mut bits = BitTree::from_iter(model.iter().copied());
+        for step in 0..300usize {
+            if step % 5 == 0 {
+                let value = step % 2 == 0;
+                bits.push(value);
+                model.push(value);
+            } else {
+                let index = (step * 31) % model.len();
+                let value = step % 3 != 0;
+                bits.set(index, value);
+                model[index] = value;
+            }
+
+            assert!(bits.iter().eq(model.iter().copied()));
+            let ones: Vec<usize> = (0..model.len()).filter(|&index| model[index]).collect();
+            assert_eq!(bits.count_ones(), ones.len());
+            for index in 0..=model.len() {
+                let rank = model[..index].iter().filter(|&&bit| bit).count();
+                assert_eq!(bits.rank1(index), rank);
+                assert_eq!(bits.rank0(index), index - rank);
+            }
+            for k in 0..=ones.len() {
+                assert_eq!(bits.select1(k), ones.get(k).copied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_zeros() {
+        let mut bits = BitTree::zeros(130);
+        assert_eq!(bits.len(), 130);
+        assert_eq!(bits.count_ones(), 0);
+
+        bits.set(129, true);
+        assert_eq!(bits.select1(0), Some(129));
+        assert_eq!(bits.get(130), None);
+    }
+}
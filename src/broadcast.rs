@@ -0,0 +1,237 @@
+use std::ops::RangeBounds;
+
+use crate::PostfixSegmentTree;
+use crate::internal::range::to_range;
+
+/// Scales out of this range are applied to stored elements, so they don't underflow or overflow.
+const MIN_SCALE: f64 = 1e-100;
+const MAX_SCALE: f64 = 1e100;
+
+/// A variant of [`PostfixSegmentTree`] of `f64`, which broadcasts scalar operations to all elements lazily.
+///
+/// It keeps a pending transform `x * scale + offset` of all elements, which is applied on reads.
+/// [`scale_all`] and [`add_all`] only change the transform in *O*(1),
+/// and a sum of `n` elements is `stored_sum * scale + offset * n`.
+/// Writes store elements with the inverse transform applied.
+/// So decaying all weights every tick doesn't rebuild the tree.
+///
+/// The transform is applied to stored elements in *O*([`len`]) when the scale becomes zero or too small or large,
+/// or by [`materialize`]. Note that rounding errors may differ from applying operations eagerly.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::BroadcastPostfixSegmentTree;
+///
+/// let mut weights = BroadcastPostfixSegmentTree::from_iter([1.0, 2.0, 4.0]);
+/// weights.scale_all(0.5);
+/// assert_eq!(weights.sum(0, 3), 3.5);
+///
+/// weights.add_all(1.0);
+/// weights.update(0, 10.0);
+/// assert_eq!(weights.get(0), 10.0);
+/// assert_eq!(weights.get(2), 3.0);
+/// assert_eq!(weights.prefix_sum(3), 15.0);
+/// ```
+///
+/// [`scale_all`]: BroadcastPostfixSegmentTree::scale_all
+/// [`add_all`]: BroadcastPostfixSegmentTree::add_all
+/// [`materialize`]: BroadcastPostfixSegmentTree::materialize
+/// [`len`]: BroadcastPostfixSegmentTree::len
+pub struct BroadcastPostfixSegmentTree {
+    // elements with the inverse of the pending transform applied
+    stored: PostfixSegmentTree<f64>,
+    scale: f64,
+    offset: f64,
+}
+
+impl BroadcastPostfixSegmentTree {
+    pub fn new() -> Self {
+        Self {
+            stored: PostfixSegmentTree::new(),
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.stored.len()
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.stored.is_empty()
+    }
+
+    /// Returns the element at `index`, with the pending transform applied.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, index: usize) -> f64 {
+        self.stored[index] * self.scale + self.offset
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, element: f64) {
+        self.stored.push(self.inverse(element));
+    }
+
+    /// Updates the element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: BroadcastPostfixSegmentTree::len
+    pub fn update(&mut self, index: usize, element: f64) {
+        self.stored.update(index, self.inverse(element));
+    }
+
+    /// Multiplies every element by `factor`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1), or *O*([`len`]) when the transform is applied to stored elements
+    ///
+    /// [`len`]: BroadcastPostfixSegmentTree::len
+    pub fn scale_all(&mut self, factor: f64) {
+        self.scale *= factor;
+        self.offset *= factor;
+
+        let scale = self.scale.abs();
+        if !(MIN_SCALE..=MAX_SCALE).contains(&scale) {
+            self.materialize();
+        }
+    }
+
+    /// Adds `delta` to every element.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn add_all(&mut self, delta: f64) {
+        self.offset += delta;
+    }
+
+    /// Applies the pending transform to stored elements.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: BroadcastPostfixSegmentTree::len
+    pub fn materialize(&mut self) {
+        let (scale, offset) = (self.scale, self.offset);
+        // CLEAN: all parents, when the guard is dropped
+        for element in self.stored.elements_mut().iter_mut() {
+            *element = *element * scale + offset;
+        }
+
+        self.scale = 1.0;
+        self.offset = 0.0;
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`, with the pending transform applied.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn prefix_sum(&self, index: usize) -> f64 {
+        self.sum(0, index)
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`, with the pending transform applied.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`index` + `len`))
+    pub fn sum(&self, index: usize, len: usize) -> f64 {
+        self.stored.sum(index, len) * self.scale + self.offset * len as f64
+    }
+
+    /// Returns the sum of elements in `range`, with the pending transform applied.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `range.end`)
+    pub fn sum_range(&self, range: impl RangeBounds<usize>) -> f64 {
+        let range = to_range(range, self.len());
+        self.sum(range.start, range.len())
+    }
+
+    fn inverse(&self, element: f64) -> f64 {
+        (element - self.offset) / self.scale
+    }
+}
+
+impl Default for BroadcastPostfixSegmentTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<f64> for BroadcastPostfixSegmentTree {
+    fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
+        Self {
+            stored: PostfixSegmentTree::from_iter(iter),
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0),
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_broadcast_matches_eager() {
+        let mut model: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        let mut tree = BroadcastPostfixSegmentTree::from_iter(model.iter().copied());
+        for step in 0..300usize {
+            match step % 5 {
+                0 => {
+                    tree.scale_all(0.9);
+                    model.iter_mut().for_each(|element| *element *= 0.9);
+                }
+                1 => {
+                    tree.add_all(0.5);
+                    model.iter_mut().for_each(|element| *element += 0.5);
+                }
+                2 => {
+                    let index = (step * 7) % model.len();
+                    tree.update(index, step as f64);
+                    model[index] = step as f64;
+                }
+                3 if step % 45 == 3 => {
+                    tree.scale_all(0.0);
+                    model.iter_mut().for_each(|element| *element = 0.0);
+                }
+                _ => {
+                    tree.push(step as f64);
+                    model.push(step as f64);
+                }
+            }
+
+            for (index, element) in model.iter().enumerate() {
+                assert_close(tree.get(index), *element);
+            }
+            for index in 0..=model.len() {
+                assert_close(tree.prefix_sum(index), model[..index].iter().sum());
+            }
+        }
+    }
+}
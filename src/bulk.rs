@@ -0,0 +1,1131 @@
+use std::cmp::Ordering;
+use std::ops::RangeBounds;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::internal::node_id::LeafNodeId;
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::range::to_range;
+
+// bulk operations
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Moves elements in `range` out of this tree, and inserts them into `dest` at `at`.
+    ///
+    /// Elements are moved without cloning, and nodes on each side are recalculated only once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut source = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// let mut dest = PostfixSegmentTree::from_iter([10, 20, 30]);
+    /// source.transfer(1..3, &mut dest, 1);
+    ///
+    /// assert_eq!(source.iter().copied().collect::<Vec<_>>(), vec![1, 4, 5]);
+    /// assert_eq!(dest.iter().copied().collect::<Vec<_>>(), vec![10, 2, 3, 20, 30]);
+    /// assert_eq!(source.prefix_sum(3), 10);
+    /// assert_eq!(dest.prefix_sum(5), 65);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] + `dest.len()`)
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn transfer(&mut self, range: impl RangeBounds<usize>, dest: &mut Self, at: usize) {
+        let range = to_range(range, self.len());
+        let count = range.len();
+        assert!(at <= dest.len());
+        assert!(count <= dest.remaining_capacity(), "capacity overflow");
+
+        // move the range to the end: [prefix, range, suffix] => [prefix, suffix, range]
+        let len = self.len();
+        self.reverse_leaf_nodes_dirty(range.start, range.end); // DIRTY: all parents of `>= range.start`
+        self.reverse_leaf_nodes_dirty(range.end, len);
+        self.reverse_leaf_nodes_dirty(range.start, len);
+
+        // pop the range from the back, then push it to the back of `dest` in reversed order.
+        // dest: [prefix, suffix, reversed range]
+        let dest_len = dest.len();
+        dest.reserve(count);
+        for _ in 0..count {
+            let element = self.pop_leaf();
+            dest.push_leaf(element);
+        }
+
+        // [prefix, suffix, reversed range] => [prefix, range, reversed suffix] => [prefix, range, suffix]
+        dest.reverse_leaf_nodes_dirty(at, dest.len()); // DIRTY: all parents of `>= at`
+        dest.reverse_leaf_nodes_dirty(at + count, dest.len());
+
+        if range.start < self.len() {
+            self.recalculate_nodes_after_bulk_update(LeafNodeId::new(range.start)); // CLEAN: all parents of `>= range.start`
+        }
+        if at < dest.len() {
+            dest.recalculate_nodes_after_bulk_update(LeafNodeId::new(at)); // CLEAN: all parents of `>= at`
+        }
+        debug_assert!(dest_len + count == dest.len());
+    }
+
+    /// Moves all elements of `other` to the back of this tree, leaving `other` empty.
+    ///
+    /// Elements are moved without cloning, and only new nodes of this tree are calculated.
+    /// Partial sums of `other` are discarded, and the capacity of `other` is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// let mut other = PostfixSegmentTree::from_iter([4, 5]);
+    /// tree.append(&mut other);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(tree.prefix_sum(5), 15);
+    /// assert!(other.is_empty());
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(`other.len()`)
+    pub fn append(&mut self, other: &mut Self) {
+        self.reserve(other.len());
+        self.push_leaves(other.drain_leaves_from(0));
+    }
+
+    /// Splits the tree into two at `index`, and returns a new tree of elements in `index..`.
+    ///
+    /// Truncating this tree is *O*(1) thanks to the postfix layout, since nodes before `index` don't depend on elements after it.
+    /// Elements after `index` are moved without cloning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// let tail = tree.split_off(2);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+    /// assert_eq!(tail.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+    /// assert_eq!(tail.prefix_sum(3), 12);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] - `index`)
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn split_off(&mut self, index: usize) -> Self {
+        assert!(index <= self.len());
+
+        let mut tail = Self::new();
+        tail.reserve_exact(self.len() - index);
+        tail.push_leaves(self.drain_leaves_from(index));
+
+        tail
+    }
+
+    /// Removes `range` and inserts `replace_with` in its place, and returns the removed elements.
+    ///
+    /// Elements after `range.start` are moved out once and pushed back, so every node after `range.start`
+    /// is calculated exactly once. Unlike [`Vec::splice`], elements are removed eagerly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// let removed: Vec<_> = tree.splice(1..3, [10, 20, 30]).collect();
+    ///
+    /// assert_eq!(removed, vec![2, 3]);
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 10, 20, 30, 4, 5]);
+    /// assert_eq!(tree.prefix_sum(6), 70);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] - `range.start` + *m*) where *m* is the number of inserted elements
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn splice(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        replace_with: impl IntoIterator<Item = T>,
+    ) -> std::vec::IntoIter<T> {
+        let range = to_range(range, self.len());
+
+        let mut removed: Vec<T> = self.drain_leaves_from(range.start).collect();
+        let suffix = removed.split_off(range.len());
+
+        let replace_with = replace_with.into_iter();
+        let (lower, _) = replace_with.size_hint();
+        self.reserve(usize::min(lower + suffix.len(), self.remaining_capacity()));
+        for element in replace_with {
+            assert!(
+                self.remaining_capacity() > suffix.len(),
+                "capacity overflow"
+            );
+            self.push_leaf(element); // CLEAN: parents of the new element
+        }
+        self.push_leaves(suffix.into_iter());
+
+        removed.into_iter()
+    }
+
+    /// Inserts clones of `elements` at `index`, shifting all elements after it to the right.
+    ///
+    /// Elements after `index` are moved out once and pushed back after the inserted ones,
+    /// so every node after `index` is calculated exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// tree.insert_slice(1, &[10, 20]);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 10, 20, 2, 3]);
+    /// assert_eq!(tree.prefix_sum(3), 31);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] - `index` + `elements.len()`)
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn insert_slice(&mut self, index: usize, elements: &[T]) {
+        assert!(index <= self.len());
+        assert!(
+            elements.len() <= self.remaining_capacity(),
+            "capacity overflow"
+        );
+
+        self.reserve(elements.len());
+        let suffix: Vec<T> = self.drain_leaves_from(index).collect();
+        self.push_leaves(elements.iter().cloned());
+        self.push_leaves(suffix.into_iter());
+    }
+
+    /// Removes elements in `range`, shifting all elements after it to the left,
+    /// and returns the removed elements.
+    ///
+    /// Elements after `range.start` are moved out once and the remaining ones are pushed back,
+    /// so every node after `range.start` is calculated exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// let removed: Vec<_> = tree.remove_range(1..3).collect();
+    ///
+    /// assert_eq!(removed, vec![2, 3]);
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 4, 5]);
+    /// assert_eq!(tree.prefix_sum(3), 10);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] - `range.start`)
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn remove_range(&mut self, range: impl RangeBounds<usize>) -> std::vec::IntoIter<T> {
+        let range = to_range(range, self.len());
+
+        let mut removed: Vec<T> = self.drain_leaves_from(range.start).collect();
+        let suffix = removed.split_off(range.len());
+        self.push_leaves(suffix.into_iter());
+
+        removed.into_iter()
+    }
+
+    /// Retains only the elements specified by the predicate, visiting each element once in order.
+    ///
+    /// Elements after the first removed one are moved out once and the retained ones are pushed back,
+    /// so every node after it is calculated exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// tree.retain(|&element| element % 2 == 1);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5]);
+    /// assert_eq!(tree.prefix_sum(3), 9);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let Some(first) = self.iter().position(|element| !f(element)) else {
+            return;
+        };
+
+        let mut elements: Vec<T> = self.drain_leaves_from(first).collect();
+        for element in elements.drain(1..) {
+            if f(&element) {
+                self.push_leaf(element); // CLEAN: parents of the new element
+            }
+        }
+    }
+
+    /// Retains only the elements specified by the predicate, which can also modify the elements.
+    ///
+    /// Any element can be modified, so all elements are moved out and the retained ones are pushed back,
+    /// and every node is calculated exactly once. If `f` panics, the remaining elements are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// tree.retain_mut(|element| {
+    ///     *element *= 10;
+    ///     *element != 30
+    /// });
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![10, 20, 40, 50]);
+    /// assert_eq!(tree.prefix_sum(4), 120);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn retain_mut(&mut self, f: impl FnMut(&mut T) -> bool) {
+        self.with_drained_leaves(|elements| elements.retain_mut(f));
+    }
+
+    /// Removes consecutive repeated elements, like [`Vec::dedup`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 1, 2, 3, 3, 3, 1]);
+    /// tree.dedup();
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 1]);
+    /// assert_eq!(tree.prefix_sum(4), 7);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    /// Removes consecutive elements that resolve to the same key, like [`Vec::dedup_by_key`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn dedup_by_key<K: PartialEq>(&mut self, mut key: impl FnMut(&mut T) -> K) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive elements that satisfy `same_bucket`, like [`Vec::dedup_by`].
+    ///
+    /// `same_bucket(a, b)` is called with an element `a` and the last retained element `b` before it,
+    /// and `a` is removed if it returns `true`. Both can be modified,
+    /// so all elements are moved out and the retained ones are pushed back, and every node is calculated exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// // merge consecutive events of the same sign
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, -1, -3, 4]);
+    /// tree.dedup_by(|a, b| {
+    ///     let same = (*a > 0) == (*b > 0);
+    ///     if same {
+    ///         *b += *a;
+    ///     }
+    ///     same
+    /// });
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, -4, 4]);
+    /// assert_eq!(tree.prefix_sum(3), 3);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn dedup_by(&mut self, same_bucket: impl FnMut(&mut T, &mut T) -> bool) {
+        self.with_drained_leaves(|elements| elements.dedup_by(same_bucket));
+    }
+
+    /// Rotates the elements in-place such that the element at `mid` becomes the first, like [`slice::rotate_left`].
+    ///
+    /// Leaf nodes are moved by three reversals, and partial sums are recalculated in a single pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// tree.rotate_left(2);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5, 1, 2]);
+    /// assert_eq!(tree.prefix_sum(2), 7);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn rotate_left(&mut self, mid: usize) {
+        assert!(mid <= self.len());
+
+        let len = self.len();
+        if mid == 0 || mid == len {
+            return;
+        }
+
+        // [left, right] => [reversed right, reversed left] => [right, left]
+        self.reverse_leaf_nodes_dirty(0, len); // DIRTY: all parents
+        self.reverse_leaf_nodes_dirty(0, len - mid);
+        self.reverse_leaf_nodes_dirty(len - mid, len);
+        self.recalculate_nodes_after_bulk_update(LeafNodeId::new(0)); // CLEAN: all parents
+    }
+
+    /// Rotates the elements in-place such that the last `k` elements become the first, like [`slice::rotate_right`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// tree.rotate_right(2);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![4, 5, 1, 2, 3]);
+    /// assert_eq!(tree.prefix_sum(2), 9);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn rotate_right(&mut self, k: usize) {
+        assert!(k <= self.len());
+
+        self.rotate_left(self.len() - k);
+    }
+
+    /// Overwrites every element with clones of `value`, keeping the length and the capacity.
+    ///
+    /// Partial sums are recalculated in a single pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// tree.fill(0);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![0, 0, 0]);
+    /// assert_eq!(tree.prefix_sum(3), 0);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn fill(&mut self, value: T) {
+        self.fill_with(|| value.clone());
+    }
+
+    /// Overwrites every element with values returned by calling `f` in order, keeping the length and the capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([0; 4]);
+    /// let mut next = 0;
+    /// tree.fill_with(|| {
+    ///     next += 1;
+    ///     next
+    /// });
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    /// assert_eq!(tree.prefix_sum(4), 10);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn fill_with(&mut self, mut f: impl FnMut() -> T) {
+        // CLEAN: all parents, when the guard is dropped
+        for element in self.elements_mut().iter_mut() {
+            *element = f();
+        }
+    }
+
+    /// Sorts the elements, like [`slice::sort`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([3, 1, 2]);
+    /// tree.sort();
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+    /// assert_eq!(tree.prefix_sum(2), 3);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    /// Sorts the elements with a comparator function, like [`slice::sort_by`].
+    ///
+    /// Elements are moved out and sorted, then pushed back, so every node is calculated exactly once.
+    /// If `compare` panics, the elements are kept in an unspecified order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([3, 1, 2]);
+    /// tree.sort_by(|a, b| b.cmp(a));
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+    /// assert_eq!(tree.prefix_sum(2), 5);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn sort_by(&mut self, compare: impl FnMut(&T, &T) -> Ordering) {
+        self.with_drained_leaves(|elements| elements.sort_by(compare));
+    }
+
+    /// Sorts the elements without preserving the order of equal elements, like [`slice::sort_unstable`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn sort_unstable(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_unstable_by(T::cmp);
+    }
+
+    /// Sorts the elements with a comparator function without preserving the order of equal elements,
+    /// like [`slice::sort_unstable_by`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn sort_unstable_by(&mut self, compare: impl FnMut(&T, &T) -> Ordering) {
+        self.with_drained_leaves(|elements| elements.sort_unstable_by(compare));
+    }
+
+    /// Overwrites `elements[index..index + elements.len()]` with clones of `elements`.
+    ///
+    /// Only nodes that cover the overwritten range are recalculated, once each in postfix order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// tree.copy_from_slice(1, &[20, 30]);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 20, 30, 4, 5]);
+    /// assert_eq!(tree.prefix_sum(5), 60);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(`elements.len()` + log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn copy_from_slice(&mut self, index: usize, elements: &[T]) {
+        assert!(index <= self.len());
+        assert!(elements.len() <= self.len() - index);
+
+        if elements.is_empty() {
+            return;
+        }
+
+        let end = index + elements.len();
+        for (offset, element) in elements.iter().enumerate() {
+            let id = LeafNodeId::new(index + offset);
+            *self.get_leaf_node_mut(id) = element.clone(); // DIRTY: parents of `id`
+        }
+
+        self.recalculate_nodes_after_range_update(index, end); // CLEAN: parents of `index..end`
+    }
+
+    /// Analogous to `elements[index] = element` for each `(index, element)` in `updates`.
+    ///
+    /// All elements are written first, then only the union of their parents is recalculated once.
+    /// When an index appears multiple times, the last one wins.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// tree.batch_update([(0, 10), (3, 40), (0, 100)]);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![100, 2, 3, 40, 5]);
+    /// assert_eq!(tree.prefix_sum(5), 150);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is out of bounds, before any element is updated.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*k* log [`len`] log (*k* log [`len`])) where *k* is the number of updates
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn batch_update(&mut self, updates: impl IntoIterator<Item = (usize, T)>) {
+        let len = self.len();
+
+        // check all indices before writing, so a panic doesn't leave dirty parents behind
+        let updates: Vec<(usize, T)> = updates.into_iter().collect();
+        for (index, _) in &updates {
+            assert!(*index < len);
+        }
+
+        let mut dirty = Vec::new();
+        for (index, element) in updates {
+            let id = LeafNodeId::new(index);
+            *self.get_leaf_node_mut(id) = element; // DIRTY: parents of `id`
+            dirty.extend(ParentsIterator::new(id, len));
+        }
+
+        // children are always before their parents in `nodes`
+        dirty.sort_unstable_by_key(|id| id.node_index());
+        dirty.dedup_by_key(|id| id.node_index());
+        for id in dirty {
+            self.recalculate_node(id); // CLEAN: parents of every updated `id`
+        }
+    }
+
+    /// Truncates the tree to `index`, and returns an iterator that moves out elements after `index`.
+    ///
+    /// Parent nodes after `index` are dropped.
+    pub(crate) fn drain_leaves_from(&mut self, index: usize) -> impl Iterator<Item = T> + '_ {
+        debug_assert!(index <= self.len());
+
+        let end = self.len();
+        self.len = index;
+
+        let first_node_index = LeafNodeId::new(index).node_index();
+        let mut nodes = self.nodes.drain(first_node_index..);
+        (index..end).map(move |index| {
+            let element = nodes.next().expect("a leaf node");
+
+            // skip parent nodes that follow the leaf node
+            for _ in 0..LeafNodeId::new(index).max_level() {
+                nodes.next();
+            }
+
+            element
+        })
+    }
+
+    /// Pushes all `elements`, which only calculates new nodes.
+    pub(crate) fn push_leaves(&mut self, elements: impl Iterator<Item = T>) {
+        for element in elements {
+            self.push_leaf(element); // CLEAN: parents of the new element
+        }
+    }
+
+    /// Moves all elements out into a `Vec` for `f`, and pushes them back even if `f` panics.
+    ///
+    /// `Vec` methods leave the remaining elements in it when a callback panics,
+    /// so they are kept in the tree instead of being dropped with the `Vec`.
+    fn with_drained_leaves(&mut self, f: impl FnOnce(&mut Vec<T>)) {
+        let elements = self.drain_leaves_from(0).collect();
+        let mut guard = DrainedLeaves {
+            tree: self,
+            elements,
+        };
+        f(&mut guard.elements);
+    }
+}
+
+/// A guard of elements moved out of a tree, which pushes them back when it is dropped.
+struct DrainedLeaves<'a, T>
+where
+    T: Combine + Clone,
+{
+    tree: &'a mut PostfixSegmentTree<T>,
+    elements: Vec<T>,
+}
+
+impl<T> Drop for DrainedLeaves<'_, T>
+where
+    T: Combine + Clone,
+{
+    fn drop(&mut self) {
+        let elements = std::mem::take(&mut self.elements);
+        self.tree.push_leaves(elements.into_iter()); // CLEAN: all parents
+    }
+}
+
+// elementwise operations
+impl<T> PostfixSegmentTree<T> {
+    /// Combines elements of this tree and `other` pairwise with `f`, and returns a new tree of the results.
+    ///
+    /// Nodes of the new tree are calculated once as elements are pushed, without an intermediate `Vec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the trees have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let requests = PostfixSegmentTree::from_iter([10, 20, 30]);
+    /// let errors = PostfixSegmentTree::from_iter([1, 0, 3]);
+    /// let successes = requests.zip_with(&errors, |requests, errors| requests - errors);
+    ///
+    /// assert_eq!(successes.iter().copied().collect::<Vec<_>>(), vec![9, 20, 27]);
+    /// assert_eq!(successes.prefix_sum(3), 56);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn zip_with<U, V>(
+        &self,
+        other: &PostfixSegmentTree<U>,
+        mut f: impl FnMut(&T, &U) -> V,
+    ) -> PostfixSegmentTree<V>
+    where
+        V: Combine + Clone,
+    {
+        assert_eq!(self.len(), other.len(), "trees should have the same length");
+
+        let mut tree = PostfixSegmentTree::new();
+        tree.reserve_exact(self.len());
+        tree.push_leaves(self.iter().zip(other.iter()).map(|(lhs, rhs)| f(lhs, rhs)));
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PostfixSegmentTree;
+
+    fn assert_tree_eq(tree: &PostfixSegmentTree<u32>, expected: &[u32]) {
+        assert!(tree.iter().eq(expected.iter()));
+        for index in 0..=expected.len() {
+            assert_eq!(tree.prefix_sum(index), expected[..index].iter().sum());
+        }
+    }
+
+    #[test]
+    fn test_transfer() {
+        const N: u32 = 9;
+        for source_len in 0..N {
+            for dest_len in 0..N {
+                for start in 0..=source_len as usize {
+                    for end in start..=source_len as usize {
+                        for at in 0..=dest_len as usize {
+                            let mut source_model: Vec<u32> = (0..source_len).collect();
+                            let mut dest_model: Vec<u32> = (100..100 + dest_len).collect();
+                            let mut source = PostfixSegmentTree::from_iter(source_model.clone());
+                            let mut dest = PostfixSegmentTree::from_iter(dest_model.clone());
+
+                            source.transfer(start..end, &mut dest, at);
+                            let moved: Vec<u32> = source_model.drain(start..end).collect();
+                            dest_model.splice(at..at, moved);
+
+                            assert_tree_eq(&source, &source_model);
+                            assert_tree_eq(&dest, &dest_model);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_update() {
+        const N: u32 = 20;
+        for len in 1..N {
+            let mut model: Vec<u32> = (0..len).collect();
+            let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+            let updates: Vec<(usize, u32)> = (0..len)
+                .map(|i| ((i as usize * 7) % len as usize, 100 + i))
+                .take(len as usize / 2 + 1)
+                .collect();
+            tree.batch_update(updates.clone());
+            for (index, element) in updates {
+                model[index] = element;
+            }
+
+            assert_tree_eq(&tree, &model);
+        }
+    }
+
+    #[test]
+    fn test_batch_update_out_of_bounds() {
+        let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            tree.batch_update([(0, 10), (9, 1)]);
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(tree.validate(), Ok(()));
+        assert_tree_eq(&tree, &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_panicking_callbacks_keep_elements() {
+        fn assert_kept(f: impl FnOnce(&mut PostfixSegmentTree<u32>)) {
+            let mut tree = PostfixSegmentTree::from_iter([5, 3, 3, 1, 4]);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut tree)));
+
+            assert!(result.is_err());
+            assert_eq!(tree.validate(), Ok(()));
+            let mut elements: Vec<u32> = tree.iter().copied().collect();
+            elements.sort();
+            assert_eq!(elements, [1, 3, 3, 4, 5]);
+        }
+
+        fn panic_on_third_call() -> impl FnMut() {
+            let mut calls = 0;
+            move || {
+                calls += 1;
+                assert!(calls < 3);
+            }
+        }
+
+        let mut tick = panic_on_third_call();
+        assert_kept(|tree| {
+            tree.sort_by(|a, b| {
+                tick();
+                a.cmp(b)
+            })
+        });
+
+        let mut tick = panic_on_third_call();
+        assert_kept(|tree| {
+            tree.sort_unstable_by(|a, b| {
+                tick();
+                a.cmp(b)
+            })
+        });
+
+        // neither removes an element before panicking
+        let mut tick = panic_on_third_call();
+        assert_kept(|tree| {
+            tree.retain_mut(|_| {
+                tick();
+                true
+            })
+        });
+
+        let mut tick = panic_on_third_call();
+        assert_kept(|tree| {
+            tree.dedup_by(|_, _| {
+                tick();
+                false
+            })
+        });
+    }
+
+    #[test]
+    fn test_split_off_and_append() {
+        const N: u32 = 20;
+        for len in 0..N {
+            for index in 0..=len as usize {
+                let model: Vec<u32> = (0..len).collect();
+                let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+                let mut tail = tree.split_off(index);
+                assert_tree_eq(&tree, &model[..index]);
+                assert_tree_eq(&tail, &model[index..]);
+
+                tree.append(&mut tail);
+                assert_tree_eq(&tree, &model);
+                assert_tree_eq(&tail, &[]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_splice() {
+        const N: u32 = 12;
+        for len in 0..N {
+            for start in 0..=len as usize {
+                for end in start..=len as usize {
+                    for inserted in 0..4 {
+                        let mut model: Vec<u32> = (0..len).collect();
+                        let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+                        let replace_with = (0..inserted).map(|i| 100 + i);
+                        let removed: Vec<u32> =
+                            tree.splice(start..end, replace_with.clone()).collect();
+                        let expected: Vec<u32> = model.splice(start..end, replace_with).collect();
+
+                        assert_eq!(removed, expected);
+                        assert_tree_eq(&tree, &model);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_retain() {
+        const N: u32 = 20;
+        for len in 0..N {
+            for modulo in 1..4 {
+                let mut model: Vec<u32> = (0..len).collect();
+                let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+                let mut visited = Vec::new();
+                tree.retain(|&element| {
+                    visited.push(element);
+                    element % modulo != 1
+                });
+                model.retain(|&element| element % modulo != 1);
+
+                assert_eq!(visited, (0..len).collect::<Vec<_>>());
+                assert_tree_eq(&tree, &model);
+            }
+        }
+    }
+
+    #[test]
+    fn test_retain_mut() {
+        const N: u32 = 20;
+        for len in 0..N {
+            let mut model: Vec<u32> = (0..len).collect();
+            let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+            let f = |element: &mut u32| {
+                *element += 1;
+                !element.is_multiple_of(3)
+            };
+            tree.retain_mut(f);
+            model.retain_mut(f);
+
+            assert_tree_eq(&tree, &model);
+        }
+    }
+
+    #[test]
+    fn test_dedup_by() {
+        const N: u32 = 20;
+        for len in 0..N {
+            let mut model: Vec<u32> = (0..len).map(|i| i / 3 + i % 2).collect();
+            let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+            let f = |a: &mut u32, b: &mut u32| {
+                let same = *a / 2 == *b / 2;
+                if same {
+                    *b += *a;
+                }
+                same
+            };
+            tree.dedup_by(f);
+            model.dedup_by(f);
+
+            assert_tree_eq(&tree, &model);
+        }
+    }
+
+    #[test]
+    fn test_rotate() {
+        const N: usize = 20;
+        for len in 0..N as u32 {
+            for mid in 0..=len as usize {
+                let mut model: Vec<u32> = (0..len).collect();
+                let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+                tree.rotate_left(mid);
+                model.rotate_left(mid);
+                assert_tree_eq(&tree, &model);
+
+                tree.rotate_right(mid);
+                model.rotate_right(mid);
+                assert_tree_eq(&tree, &model);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_by() {
+        const N: u32 = 20;
+        for len in 0..N {
+            let model: Vec<u32> = (0..len).map(|i| (i * 7) % 11).collect();
+
+            let mut expected = model.clone();
+            expected.sort_by_key(|element| element % 3);
+            let mut tree = PostfixSegmentTree::from_iter(model.clone());
+            tree.sort_by(|a, b| (a % 3).cmp(&(b % 3)));
+            assert_tree_eq(&tree, &expected);
+
+            let mut expected = model.clone();
+            expected.sort_unstable();
+            let mut tree = PostfixSegmentTree::from_iter(model);
+            tree.sort_unstable();
+            assert_tree_eq(&tree, &expected);
+        }
+    }
+
+    #[test]
+    fn test_insert_slice() {
+        const N: u32 = 12;
+        for len in 0..N {
+            for index in 0..=len as usize {
+                for count in 0..4 {
+                    let mut model: Vec<u32> = (0..len).collect();
+                    let mut tree = PostfixSegmentTree::from_iter(model.clone());
+                    let inserted: Vec<u32> = (100..100 + count).collect();
+
+                    tree.insert_slice(index, &inserted);
+                    model.splice(index..index, inserted.iter().copied());
+
+                    assert_tree_eq(&tree, &model);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_range() {
+        const N: u32 = 12;
+        for len in 0..N {
+            for start in 0..=len as usize {
+                for end in start..=len as usize {
+                    let mut model: Vec<u32> = (0..len).collect();
+                    let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+                    assert!(tree.remove_range(start..end).eq(model.drain(start..end)));
+                    assert_tree_eq(&tree, &model);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill() {
+        const N: u32 = 20;
+        for len in 0..N {
+            let mut model: Vec<u32> = (0..len).collect();
+            let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+            tree.fill(3);
+            model.fill(3);
+            assert_tree_eq(&tree, &model);
+
+            let mut next = 0;
+            tree.fill_with(|| {
+                next += 2;
+                next
+            });
+            let mut next = 0;
+            model.fill_with(|| {
+                next += 2;
+                next
+            });
+            assert_tree_eq(&tree, &model);
+        }
+    }
+
+    #[test]
+    fn test_copy_from_slice() {
+        const N: u32 = 20;
+        for len in 0..N {
+            for index in 0..=len as usize {
+                for count in 0..=len as usize - index {
+                    let mut model: Vec<u32> = (0..len).collect();
+                    let mut tree = PostfixSegmentTree::from_iter(model.clone());
+                    let copied: Vec<u32> = (100..100 + count as u32).collect();
+
+                    tree.copy_from_slice(index, &copied);
+                    model[index..index + count].copy_from_slice(&copied);
+
+                    assert_tree_eq(&tree, &model);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_zip_with() {
+        let lhs = PostfixSegmentTree::from_iter(0..37u32);
+        let rhs = PostfixSegmentTree::from_iter((0..37u32).map(|i| i * 3 % 7));
+        let zipped = lhs.zip_with(&rhs, |lhs, rhs| lhs * rhs);
+
+        let expected: Vec<u32> = (0..37u32).map(|i| i * (i * 3 % 7)).collect();
+        assert_tree_eq(&zipped, &expected);
+        for index in 0..=expected.len() {
+            assert_eq!(zipped.prefix_sum(index), expected[..index].iter().sum());
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+
+/// A memoization wrapper of [`PostfixSegmentTree`] that caches recent [`sum`] results.
+///
+/// Mutations through the wrapper report their dirty ranges of elements,
+/// and cached results of ranges overlapping them are invalidated.
+/// It fits read-heavy workloads that repeatedly query the same ranges between sparse writes.
+///
+/// At most `capacity` results are cached, and the oldest one is evicted first.
+///
+/// The cache is a [`RefCell`] so that [`sum`] can fill it through `&self`, which makes the wrapper `!Sync`.
+/// To share it between threads, wrap it with a `Mutex` or an `RwLock` locked for writing,
+/// or keep a wrapper per thread.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{CachedPostfixSegmentTree, PostfixSegmentTree};
+///
+/// let mut cached = CachedPostfixSegmentTree::new(PostfixSegmentTree::from_iter([1, 2, 3, 4]), 16);
+/// assert_eq!(cached.sum(0, 2), 3);
+/// assert_eq!(cached.sum(2, 2), 7);
+/// assert_eq!(cached.cached_len(), 2);
+///
+/// // invalidates `0..2` only
+/// cached.update(1, 10);
+/// assert_eq!(cached.cached_len(), 1);
+/// assert_eq!(cached.sum(0, 2), 11);
+/// ```
+///
+/// [`sum`]: PostfixSegmentTree::sum
+pub struct CachedPostfixSegmentTree<T> {
+    tree: PostfixSegmentTree<T>,
+    cache: RefCell<Cache<T>>,
+}
+
+struct Cache<T> {
+    results: HashMap<(usize, usize), T>,
+    order: VecDeque<(usize, usize)>,
+    capacity: usize,
+}
+
+impl<T> CachedPostfixSegmentTree<T>
+where
+    T: Aggregate + Clone,
+{
+    /// Wraps `tree` with a cache of at most `capacity` results.
+    pub fn new(tree: PostfixSegmentTree<T>, capacity: usize) -> Self {
+        Self {
+            tree,
+            cache: RefCell::new(Cache {
+                results: HashMap::with_capacity(capacity),
+                order: VecDeque::with_capacity(capacity),
+                capacity,
+            }),
+        }
+    }
+
+    /// Returns the underlying tree.
+    pub fn tree(&self) -> &PostfixSegmentTree<T> {
+        &self.tree
+    }
+
+    pub fn into_inner(self) -> PostfixSegmentTree<T> {
+        self.tree
+    }
+
+    /// Returns the number of cached results.
+    pub fn cached_len(&self) -> usize {
+        self.cache.borrow().results.len()
+    }
+
+    /// Invalidates every cached result.
+    pub fn clear_cache(&mut self) {
+        let cache = self.cache.get_mut();
+        cache.results.clear();
+        cache.order.clear();
+    }
+
+    /// Cached version of [`PostfixSegmentTree::sum`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1) on hit, *O*(log `index`) on miss.
+    pub fn sum(&self, index: usize, len: usize) -> T {
+        let key = (index, index + len);
+        if let Some(result) = self.cache.borrow().results.get(&key) {
+            return result.clone();
+        }
+
+        let result = self.tree.sum(index, len);
+
+        let mut cache = self.cache.borrow_mut();
+        if cache.capacity == 0 {
+            return result;
+        }
+
+        while cache.results.len() >= cache.capacity {
+            // keys in `order` may have been invalidated already
+            let Some(oldest) = cache.order.pop_front() else {
+                break;
+            };
+            cache.results.remove(&oldest);
+        }
+        cache.results.insert(key, result.clone());
+        cache.order.push_back(key);
+
+        result
+    }
+
+    /// Cached version of [`PostfixSegmentTree::prefix_sum`].
+    pub fn prefix_sum(&self, index: usize) -> T {
+        self.sum(0, index)
+    }
+
+    /// [`PostfixSegmentTree::update`] that invalidates ranges containing `index`.
+    pub fn update(&mut self, index: usize, element: T) {
+        self.tree.update(index, element);
+        self.invalidate(index..index + 1);
+    }
+
+    /// [`PostfixSegmentTree::push`]. Cached ranges are not affected since they are before the new element.
+    pub fn push(&mut self, element: T) {
+        self.tree.push(element);
+    }
+
+    /// [`PostfixSegmentTree::insert`] that invalidates ranges overlapping `index..`.
+    pub fn insert(&mut self, index: usize, element: T) {
+        self.tree.insert(index, element);
+        self.invalidate(index..self.tree.len());
+    }
+
+    /// [`PostfixSegmentTree::remove`] that invalidates ranges overlapping `index..`.
+    pub fn remove(&mut self, index: usize) -> T {
+        let removed = self.tree.remove(index);
+        self.invalidate(index..self.tree.len() + 1);
+        removed
+    }
+
+    /// Invalidates cached results of ranges overlapping `dirty`.
+    fn invalidate(&mut self, dirty: Range<usize>) {
+        let cache = self.cache.get_mut();
+        cache
+            .results
+            .retain(|&(start, end), _| end <= dirty.start || dirty.end <= start);
+        let Cache { results, order, .. } = cache;
+        order.retain(|key| results.contains_key(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_matches_model() {
+        for capacity in [0, 1, 3, 16] {
+            let mut model: Vec<i64> = (0..20).collect();
+            let mut cached = CachedPostfixSegmentTree::new(
+                PostfixSegmentTree::from_iter(model.clone()),
+                capacity,
+            );
+
+            for step in 0..300usize {
+                let index = (step * 7919) % (model.len() + 1);
+                let value = (step as i64 * 31) % 17 - 8;
+                match step % 7 {
+                    0 if index < model.len() => {
+                        cached.update(index, value);
+                        model[index] = value;
+                    }
+                    1 => {
+                        cached.insert(index, value);
+                        model.insert(index, value);
+                    }
+                    2 if index < model.len() => {
+                        assert_eq!(cached.remove(index), model.remove(index));
+                    }
+                    3 => {
+                        cached.push(value);
+                        model.push(value);
+                    }
+                    _ => {}
+                }
+
+                // repeat queries of a few ranges, so that results are cached before mutations.
+                for query in 0..6 {
+                    let start = ((step / 3 + query * 5) * 13) % (model.len() + 1);
+                    let len = (query * 3) % (model.len() - start + 1);
+                    assert_eq!(
+                        cached.sum(start, len),
+                        model[start..start + len].iter().sum::<i64>()
+                    );
+                    assert_eq!(cached.prefix_sum(start), model[..start].iter().sum::<i64>());
+                }
+                assert!(cached.cached_len() <= capacity);
+            }
+
+            assert!(cached.tree().iter().eq(model.iter()));
+        }
+    }
+
+    #[test]
+    fn test_eviction_skips_invalidated_keys() {
+        let mut cached = CachedPostfixSegmentTree::new(PostfixSegmentTree::from_iter(0..10i64), 3);
+        assert_eq!(cached.sum(0, 2), 1);
+        assert_eq!(cached.sum(4, 2), 9);
+        assert_eq!(cached.sum(8, 2), 17);
+
+        // `0..2` is invalidated while it is still the oldest in `order`, so `4..6` is evicted next.
+        cached.update(1, 11);
+        assert_eq!(cached.cached_len(), 2);
+        assert_eq!(cached.sum(2, 2), 5);
+        assert_eq!(cached.sum(6, 2), 13);
+        assert_eq!(cached.cached_len(), 3);
+
+        cached.update(9, 0);
+        cached.update(2, 0);
+        assert_eq!(cached.cached_len(), 1);
+        assert_eq!(cached.sum(0, 10), 44);
+    }
+
+    #[test]
+    fn test_zero_capacity_caches_nothing() {
+        let cached = CachedPostfixSegmentTree::new(PostfixSegmentTree::from_iter([1, 2, 3]), 0);
+        assert_eq!(cached.sum(0, 3), 6);
+        assert_eq!(cached.sum(0, 3), 6);
+        assert_eq!(cached.cached_len(), 0);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::PostfixSegmentTree;
+#[cfg(feature = "num-traits")]
+use crate::aggregate::{Additive, Num};
+use crate::aggregate::{Aggregate, Combine};
+use crate::internal::node_id::LeafNodeId;
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::skipping_iterator::range_iterator;
+
+/// [`Combine::combine`] that detects overflow, for checked variants of queries and updates.
+///
+/// # Laws
+///
+/// * `a.checked_combine(b) == Some(c)` implies `c == a ⊕ b`
+pub trait CheckedCombine: Combine + Sized {
+    /// Returns `self ⊕ rhs`, or `None` if it overflows.
+    fn checked_combine(&self, rhs: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_combine {
+    ($($ty:ty),*) => {
+        $(
+            impl CheckedCombine for $ty {
+                fn checked_combine(&self, rhs: &Self) -> Option<Self> {
+                    self.checked_add(*rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_combine!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+#[cfg(feature = "num-traits")]
+impl<T> CheckedCombine for Additive<T>
+where
+    T: num_traits::CheckedAdd + Clone,
+{
+    fn checked_combine(&self, rhs: &Self) -> Option<Self> {
+        self.0.checked_add(&rhs.0).map(Additive)
+    }
+}
+
+#[cfg(feature = "num-traits")]
+impl<T> CheckedCombine for Num<T>
+where
+    T: num_traits::Zero + num_traits::CheckedAdd + Clone,
+{
+    fn checked_combine(&self, rhs: &Self) -> Option<Self> {
+        self.0.checked_add(&rhs.0).map(Num)
+    }
+}
+
+/// Checked variants of queries and updates.
+///
+/// Checked updates calculate all affected nodes before writing any of them,
+/// so the tree is left unchanged on overflow, and no node ever overflows if the tree is only modified by them.
+/// A sum of several nodes is not stored, so a checked query can still return `None` after successful checked updates.
+/// Nodes calculated by other operations are not checked, so they may have wrapped in release builds.
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate + CheckedCombine + Clone,
+{
+    /// Checked version of [`prefix_sum`], which returns `None` on overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([100u8, 100, 100]);
+    /// assert_eq!(tree.checked_prefix_sum(2), Some(200));
+    /// assert_eq!(tree.checked_prefix_sum(3), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    ///
+    /// [`prefix_sum`]: PostfixSegmentTree::prefix_sum
+    pub fn checked_prefix_sum(&self, index: usize) -> Option<T> {
+        self.checked_sum(0, index)
+    }
+
+    /// Checked version of [`sum`], which returns `None` on overflow.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`index` + `len`))
+    ///
+    /// [`sum`]: PostfixSegmentTree::sum
+    pub fn checked_sum(&self, index: usize, len: usize) -> Option<T> {
+        assert!(index <= self.len());
+        assert!(len <= self.len() - index);
+
+        let mut sum = T::identity();
+        for id in range_iterator(index, index + len) {
+            sum = sum.checked_combine(self.get_node(id))?;
+        }
+
+        Some(sum)
+    }
+
+    /// Checked version of [`update`].
+    /// It returns `element` back as an error if any node on the path from `index` overflows,
+    /// and the tree is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([100u8, 100, 50, 0]);
+    /// assert_eq!(tree.checked_update(3, 60), Err(60));
+    /// assert_eq!(tree.checked_update(3, 5), Ok(()));
+    /// assert_eq!(tree.checked_prefix_sum(4), Some(255));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`update`]: PostfixSegmentTree::update
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn checked_update(&mut self, index: usize, element: T) -> Result<(), T> {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        let Some(parents) = self.checked_parents(id, &element, self.len()) else {
+            return Err(element);
+        };
+
+        *self.get_leaf_node_mut(id) = element; // DIRTY: parents of `id`
+        for (parent, sum) in ParentsIterator::new(id, self.len()).zip(parents) {
+            self.nodes[parent.node_index()] = sum;
+        }
+        // CLEAN: parents of `id`
+        Ok(())
+    }
+
+    /// Checked version of [`push`].
+    /// It returns `element` back as an error if any new node overflows, and the tree is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::new();
+    /// assert_eq!(tree.checked_push(200u8), Ok(()));
+    /// assert_eq!(tree.checked_push(100), Err(100));
+    /// assert_eq!(tree.len(), 1);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    ///
+    /// [`push`]: PostfixSegmentTree::push
+    pub fn checked_push(&mut self, element: T) -> Result<(), T> {
+        assert!(self.remaining_capacity() > 0, "capacity overflow");
+
+        let id = LeafNodeId::new(self.len());
+        let Some(parents) = self.checked_parents(id, &element, self.len() + 1) else {
+            return Err(element);
+        };
+
+        self.nodes.push(element);
+        self.nodes.extend(parents);
+        self.len += 1;
+        // CLEAN: parents of `id`
+        Ok(())
+    }
+
+    /// Calculates parents of `id` with `element` at `id` in a tree of `len` elements, from the lowest level.
+    ///
+    /// Only the path from `id` is changed, so a parent is combined from the previous one and its sibling in the tree.
+    fn checked_parents(&self, id: LeafNodeId, element: &T, len: usize) -> Option<Vec<T>> {
+        let mut parents: Vec<T> = Vec::with_capacity(id.max_level() as usize);
+        let mut child_node_index = id.node_index();
+        for parent in ParentsIterator::new(id, len) {
+            let child = parents.last().unwrap_or(element);
+            let left = parent.left_child().node_index();
+            let right = parent.right_child().node_index();
+
+            let sum = if left == child_node_index {
+                child.checked_combine(&self.nodes[right])?
+            } else {
+                debug_assert_eq!(right, child_node_index);
+                self.nodes[left].checked_combine(child)?
+            };
+
+            parents.push(sum);
+            child_node_index = parent.node_index();
+        }
+
+        Some(parents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wide_sum(elements: &[u16]) -> u32 {
+        elements.iter().map(|&element| element as u32).sum()
+    }
+
+    #[test]
+    fn test_checked_matches_wide_sums() {
+        let mut model: Vec<u16> = Vec::new();
+        let mut tree = PostfixSegmentTree::new();
+        for step in 0..300u32 {
+            let element = ((step * 7919) % 20000) as u16;
+            let result = if step % 3 == 0 || model.is_empty() {
+                model.push(element);
+                tree.checked_push(element)
+            } else {
+                let index = (step as usize * 31) % model.len();
+                let old = std::mem::replace(&mut model[index], element);
+                let result = tree.checked_update(index, element);
+                if result.is_err() {
+                    model[index] = old;
+                }
+                result
+            };
+
+            // a node overflows only if the whole sum overflows
+            if result.is_err() {
+                if step % 3 == 0 {
+                    model.pop();
+                }
+                assert!(wide_sum(&model) + element as u32 > u16::MAX as u32);
+            }
+            assert!(tree.iter().eq(model.iter()));
+            for index in 0..=model.len() {
+                let expected = u16::try_from(wide_sum(&model[..index])).ok();
+                assert_eq!(tree.checked_prefix_sum(index), expected);
+            }
+        }
+    }
+
+    /// A fixed-point number with 2 decimal places, which only implements `num-traits` arithmetic.
+    #[cfg(feature = "num-traits")]
+    #[derive(Clone, Debug, PartialEq)]
+    struct Fixed(i16);
+
+    #[cfg(feature = "num-traits")]
+    impl std::ops::Add for Fixed {
+        type Output = Fixed;
+
+        fn add(self, rhs: Self) -> Self {
+            Fixed(self.0 + rhs.0)
+        }
+    }
+
+    #[cfg(feature = "num-traits")]
+    impl num_traits::Zero for Fixed {
+        fn zero() -> Self {
+            Fixed(0)
+        }
+
+        fn is_zero(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    #[cfg(feature = "num-traits")]
+    impl num_traits::CheckedAdd for Fixed {
+        fn checked_add(&self, rhs: &Self) -> Option<Self> {
+            self.0.checked_add(rhs.0).map(Fixed)
+        }
+    }
+
+    #[cfg(feature = "num-traits")]
+    #[test]
+    fn test_num_traits_type() {
+        let mut tree = PostfixSegmentTree::from_iter([1250, 325, 30000].map(|c| Num(Fixed(c))));
+        assert_eq!(tree.prefix_sum(2), Num(Fixed(1575)));
+        assert_eq!(tree.sum(1, 0), Num(Fixed(0)));
+        assert_eq!(tree.checked_sum(0, 3), Some(Num(Fixed(31575))));
+
+        assert_eq!(tree.checked_push(Num(Fixed(20000))), Err(Num(Fixed(20000))));
+        assert_eq!(
+            tree.checked_update(1, Num(Fixed(32000))),
+            Err(Num(Fixed(32000)))
+        );
+        assert_eq!(tree.checked_update(0, Num(Fixed(-100))), Ok(()));
+        assert_eq!(tree.prefix_sum(3), Num(Fixed(30225)));
+    }
+
+    #[cfg(feature = "num-traits")]
+    #[test]
+    fn test_checked_additive() {
+        let tree = PostfixSegmentTree::from_iter([100u8, 100, 100].map(Additive));
+        assert_eq!(tree.checked_prefix_sum(2), Some(Additive(200)));
+        assert_eq!(tree.checked_prefix_sum(3), None);
+    }
+}
@@ -0,0 +1,339 @@
+use std::ops::AddAssign;
+
+use crate::PostfixSegmentTree;
+use crate::internal::node_id::{LeafNodeId, NodeId, get_nodes_len_for};
+use crate::internal::skipping_iterator::{IncreasingSkippingIterator, SkippingIterator};
+
+/// The number of leaf nodes that are compressed together.
+const BLOCK_LEN: usize = 64;
+
+/// A primitive that can be stored in [`CompressedPostfixSegmentTree`].
+///
+/// Elements are encoded as zigzag-encoded wrapping deltas from the previous element.
+pub trait BlockCompressible: Copy + for<'a> AddAssign<&'a Self> + Default {
+    fn encode_delta(self, previous: Self) -> u64;
+    fn decode_delta(previous: Self, delta: u64) -> Self;
+}
+
+macro_rules! impl_block_compressible {
+    ($($element:ty => $signed:ty),* $(,)?) => {
+        $(
+            impl BlockCompressible for $element {
+                fn encode_delta(self, previous: Self) -> u64 {
+                    let delta = self.wrapping_sub(previous) as $signed as i64;
+                    ((delta << 1) ^ (delta >> 63)) as u64
+                }
+
+                fn decode_delta(previous: Self, delta: u64) -> Self {
+                    let delta = ((delta >> 1) as i64) ^ -((delta & 1) as i64);
+                    previous.wrapping_add(delta as $signed as $element)
+                }
+            }
+        )*
+    };
+}
+
+impl_block_compressible! {
+    u8 => i8,
+    u16 => i16,
+    u32 => i32,
+    u64 => i64,
+    usize => isize,
+    i8 => i8,
+    i16 => i16,
+    i32 => i32,
+    i64 => i64,
+    isize => isize,
+}
+
+struct Block<T> {
+    first: T,
+    width: u32,
+    bit_offset: usize,
+}
+
+/// A frozen [`PostfixSegmentTree`] of primitives with compressed leaf nodes.
+///
+/// Leaf nodes are compressed in blocks of 64 elements with delta encoding and bit packing,
+/// while internal nodes are kept uncompressed.
+/// Archival analytics trees are dominated by leaf memory, and deltas of adjacent elements are often small.
+///
+/// * [`get`]: *O*(block) since it decodes the block up to the element.
+/// * [`prefix_sum`]: *O*(log *n* + block), since at most one leaf node is decoded.
+/// * [`sum`]: *O*(log *n* + block), since at most two leaf nodes are decoded, one at each end.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PostfixSegmentTree;
+///
+/// let tree = PostfixSegmentTree::from_iter(1000u64..2000);
+/// let compressed = tree.compress();
+///
+/// assert_eq!(compressed.get(10), Some(1010));
+/// assert_eq!(compressed.prefix_sum(3), 1000 + 1001 + 1002);
+/// assert_eq!(compressed.sum(5, 3), tree.sum(5, 3));
+/// ```
+///
+/// [`get`]: CompressedPostfixSegmentTree::get
+/// [`prefix_sum`]: CompressedPostfixSegmentTree::prefix_sum
+/// [`sum`]: CompressedPostfixSegmentTree::sum
+pub struct CompressedPostfixSegmentTree<T> {
+    blocks: Vec<Block<T>>,
+    bits: Vec<u64>,
+    internal_nodes: Vec<T>,
+    len: usize,
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: BlockCompressible,
+{
+    /// Freezes this tree into a [`CompressedPostfixSegmentTree`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`nodes_len`])
+    ///
+    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    pub fn compress(&self) -> CompressedPostfixSegmentTree<T> {
+        let mut blocks = Vec::with_capacity(self.len().div_ceil(BLOCK_LEN));
+        let mut bits = Vec::new();
+        let mut bit_offset = 0;
+        for start in (0..self.len()).step_by(BLOCK_LEN) {
+            let end = usize::min(start + BLOCK_LEN, self.len());
+            let first = self[start];
+
+            let mut width = 0;
+            for index in start + 1..end {
+                let delta = self[index].encode_delta(self[index - 1]);
+                width = u32::max(width, u64::BITS - delta.leading_zeros());
+            }
+
+            blocks.push(Block {
+                first,
+                width,
+                bit_offset,
+            });
+
+            for index in start + 1..end {
+                let delta = self[index].encode_delta(self[index - 1]);
+                write_bits(&mut bits, bit_offset, width, delta);
+                bit_offset += width as usize;
+            }
+        }
+
+        let mut internal_nodes = Vec::with_capacity(self.nodes_len() - self.len());
+        for index in 0..self.len() {
+            let leaf_node_id = LeafNodeId::new(index);
+            for level in 1..=leaf_node_id.max_level() {
+                internal_nodes.push(*self.get_node(leaf_node_id.with_level(level)));
+            }
+        }
+
+        CompressedPostfixSegmentTree {
+            blocks,
+            bits,
+            internal_nodes,
+            len: self.len(),
+        }
+    }
+}
+
+impl<T> CompressedPostfixSegmentTree<T>
+where
+    T: BlockCompressible,
+{
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an element at `index`.
+    ///
+    /// # Time Complexity
+    ///
+    /// *O*(block)
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let block = &self.blocks[index / BLOCK_LEN];
+        let mut element = block.first;
+        let mut bit_offset = block.bit_offset;
+        for _ in 0..index % BLOCK_LEN {
+            let delta = read_bits(&self.bits, bit_offset, block.width);
+            element = T::decode_delta(element, delta);
+            bit_offset += block.width as usize;
+        }
+
+        Some(element)
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index` + block)
+    pub fn prefix_sum(&self, index: usize) -> T {
+        assert!(index <= self.len());
+
+        let mut sum = T::default();
+        for id in SkippingIterator::new(index) {
+            sum += &self.get_node(id);
+        }
+
+        sum
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`index` + `len`) + block)
+    pub fn sum(&self, index: usize, len: usize) -> T {
+        assert!(index <= self.len());
+        assert!(len <= self.len() - index);
+
+        let mut sum = T::default();
+        let mut iter = SkippingIterator::new(index + len);
+        let pivot = iter.skip_to_pivot(index);
+
+        for id in IncreasingSkippingIterator::new(index, pivot) {
+            sum += &self.get_node(id);
+        }
+
+        for id in iter {
+            sum += &self.get_node(id);
+        }
+
+        sum
+    }
+
+    /// Decompresses into a [`PostfixSegmentTree`].
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*)
+    pub fn decompress(&self) -> PostfixSegmentTree<T> {
+        let mut tree = PostfixSegmentTree::new();
+        tree.reserve(self.len());
+        for (block_index, block) in self.blocks.iter().enumerate() {
+            let start = block_index * BLOCK_LEN;
+            let end = usize::min(start + BLOCK_LEN, self.len());
+
+            let mut element = block.first;
+            let mut bit_offset = block.bit_offset;
+            tree.push(element);
+            for _ in start + 1..end {
+                let delta = read_bits(&self.bits, bit_offset, block.width);
+                element = T::decode_delta(element, delta);
+                bit_offset += block.width as usize;
+                tree.push(element);
+            }
+        }
+
+        tree
+    }
+
+    fn get_node(&self, id: NodeId) -> T {
+        if id.level() == 0 {
+            return self.get(id.index()).unwrap();
+        }
+
+        // internal nodes before `id` are the nodes before `id` excluding leaf nodes `..=id.index()`
+        let internal_index = get_nodes_len_for(id.index()) + id.level() as usize - id.index() - 1;
+        self.internal_nodes[internal_index]
+    }
+}
+
+fn write_bits(bits: &mut Vec<u64>, bit_offset: usize, width: u32, value: u64) {
+    if width == 0 {
+        return;
+    }
+
+    let words_len = (bit_offset + width as usize).div_ceil(u64::BITS as usize);
+    if bits.len() < words_len {
+        bits.resize(words_len, 0);
+    }
+
+    let word = bit_offset / u64::BITS as usize;
+    let shift = (bit_offset % u64::BITS as usize) as u32;
+    bits[word] |= value << shift;
+    if shift + width > u64::BITS {
+        bits[word + 1] |= value >> (u64::BITS - shift);
+    }
+}
+
+fn read_bits(bits: &[u64], bit_offset: usize, width: u32) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+
+    let word = bit_offset / u64::BITS as usize;
+    let shift = (bit_offset % u64::BITS as usize) as u32;
+    let mut value = bits[word] >> shift;
+    if shift + width > u64::BITS {
+        value |= bits[word + 1] << (u64::BITS - shift);
+    }
+
+    if width == u64::BITS {
+        value
+    } else {
+        value & ((1 << width) - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_roundtrip() {
+        let widths = [0, 1, 3, 7, 31, 63, 64, 5, 64, 2];
+        let mut bits = Vec::new();
+        let mut bit_offset = 0;
+        for (i, &width) in widths.iter().enumerate() {
+            let mask = if width == 0 {
+                0
+            } else {
+                u64::MAX >> (64 - width)
+            };
+            let value = mask ^ (i as u64 & mask);
+            write_bits(&mut bits, bit_offset, width, value);
+            assert_eq!(read_bits(&bits, bit_offset, width), value);
+            bit_offset += width as usize;
+        }
+    }
+
+    #[test]
+    fn test_compressed_tree_matches_original() {
+        let elements: Vec<i32> = (0..300).map(|i| (i * 7919) % 601 - 300).collect();
+        let tree = PostfixSegmentTree::from_iter(elements.iter().copied());
+        let compressed = tree.compress();
+
+        for (index, &element) in elements.iter().enumerate() {
+            assert_eq!(compressed.get(index), Some(element));
+        }
+        assert_eq!(compressed.get(elements.len()), None);
+
+        for index in 0..=elements.len() {
+            assert_eq!(compressed.prefix_sum(index), tree.prefix_sum(index));
+        }
+
+        for index in (0..elements.len()).step_by(13) {
+            for len in 0..elements.len() - index {
+                assert_eq!(compressed.sum(index, len), tree.sum(index, len));
+            }
+        }
+
+        let decompressed = compressed.decompress();
+        assert!(decompressed.iter().eq(elements.iter()));
+    }
+}
@@ -0,0 +1,130 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use crate::PostfixSegmentTree;
+
+/// A copy-on-write wrapper of [`PostfixSegmentTree`], which can be cloned in *O*(1).
+///
+/// Clones share the same nodes behind an [`Arc`] until one of them is mutated.
+/// It dereferences to [`PostfixSegmentTree`] for queries,
+/// and a mutable dereference materializes a private copy of nodes in *O*([`nodes_len`]) only if they are shared.
+/// So speculative computations can clone a large tree, and mutate the clone without affecting the original.
+///
+/// Note that any mutable dereference materializes a copy, even if nothing is changed.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{CowPostfixSegmentTree, PostfixSegmentTree};
+///
+/// let original = CowPostfixSegmentTree::from(PostfixSegmentTree::from_iter([1, 2, 3, 4]));
+/// let mut speculative = original.clone();
+/// assert!(speculative.is_shared());
+///
+/// speculative.update(0, 100);
+/// assert!(!speculative.is_shared());
+/// assert_eq!(speculative.prefix_sum(4), 109);
+/// assert_eq!(original.prefix_sum(4), 10);
+/// ```
+///
+/// [`nodes_len`]: PostfixSegmentTree::nodes_len
+pub struct CowPostfixSegmentTree<T> {
+    tree: Arc<PostfixSegmentTree<T>>,
+}
+
+impl<T> CowPostfixSegmentTree<T>
+where
+    T: Clone,
+{
+    /// Wraps `tree`.
+    pub fn new(tree: PostfixSegmentTree<T>) -> Self {
+        Self {
+            tree: Arc::new(tree),
+        }
+    }
+
+    /// Returns `true` if nodes are shared with other clones, so the next mutation copies them.
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.tree) > 1
+    }
+
+    /// Returns the underlying tree, which is copied if it is shared.
+    pub fn into_inner(self) -> PostfixSegmentTree<T> {
+        Arc::unwrap_or_clone(self.tree)
+    }
+}
+
+impl<T> Clone for CowPostfixSegmentTree<T> {
+    /// Shares nodes with `self` in *O*(1).
+    fn clone(&self) -> Self {
+        Self {
+            tree: Arc::clone(&self.tree),
+        }
+    }
+}
+
+impl<T> Default for CowPostfixSegmentTree<T>
+where
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new(PostfixSegmentTree::new())
+    }
+}
+
+impl<T> From<PostfixSegmentTree<T>> for CowPostfixSegmentTree<T>
+where
+    T: Clone,
+{
+    fn from(tree: PostfixSegmentTree<T>) -> Self {
+        Self::new(tree)
+    }
+}
+
+impl<T> Deref for CowPostfixSegmentTree<T> {
+    type Target = PostfixSegmentTree<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+impl<T> DerefMut for CowPostfixSegmentTree<T>
+where
+    T: Clone,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        Arc::make_mut(&mut self.tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_are_independent() {
+        let mut original = CowPostfixSegmentTree::from(PostfixSegmentTree::from_iter(0..20i64));
+        let mut clones: Vec<CowPostfixSegmentTree<i64>> = Vec::new();
+        let mut models: Vec<Vec<i64>> = Vec::new();
+        for step in 0..10usize {
+            let mut clone = original.clone();
+            let mut model: Vec<i64> = original.iter().copied().collect();
+            clone.update(step, -(step as i64));
+            model[step] = -(step as i64);
+            clone.push(step as i64);
+            model.push(step as i64);
+
+            original.update(19 - step, 100);
+            clones.push(clone);
+            models.push(model);
+        }
+
+        for (clone, model) in clones.iter().zip(&models) {
+            assert!(clone.iter().eq(model.iter()));
+            assert_eq!(clone.prefix_sum(model.len()), model.iter().sum());
+        }
+        assert_eq!(original.prefix_sum(10), 45);
+        assert_eq!(original.postfix_sum(10), 1000);
+    }
+}
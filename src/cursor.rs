@@ -0,0 +1,245 @@
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+
+/// A cursor for localized editing, which is returned by [`PostfixSegmentTree::cursor_mut`].
+///
+/// It works like a gap buffer. Elements before the cursor stay in the tree with CLEAN nodes,
+/// and elements from the cursor are moved out to a stack, so edits at the cursor don't shift the other elements.
+/// Moving the cursor or inserting before it pushes an element to the tree, which is amortized *O*(1).
+/// Elements after the cursor are pushed back to the tree when it is dropped.
+/// If it is leaked with `std::mem::forget`, those elements are lost.
+///
+/// The cursor points to an element at [`index`], or the end of the tree when `index == len`.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PostfixSegmentTree;
+///
+/// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+/// {
+///     let mut cursor = tree.cursor_mut(1);
+///     cursor.insert_before(10);
+///     cursor.insert_before(20);
+///     assert_eq!(cursor.current(), Some(&2));
+///     assert_eq!(cursor.remove_current(), Some(2));
+///     cursor.insert_after(30);
+///     cursor.move_next();
+///     assert_eq!(cursor.current(), Some(&30));
+///     assert_eq!(cursor.index(), 4);
+/// }
+///
+/// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 10, 20, 3, 30]);
+/// assert_eq!(tree.prefix_sum(5), 64);
+/// ```
+///
+/// [`index`]: CursorMut::index
+pub struct CursorMut<'a, T>
+where
+    T: Combine + Clone,
+{
+    tree: &'a mut PostfixSegmentTree<T>,
+    // elements from the cursor in reversed order, so the current element is the last one
+    rest: Vec<T>,
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Returns a [`CursorMut`] at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] - `index`), and the same on drop.
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn cursor_mut(&mut self, index: usize) -> CursorMut<'_, T> {
+        assert!(index <= self.len());
+
+        let mut rest: Vec<T> = self.drain_leaves_from(index).collect();
+        rest.reverse();
+        CursorMut { tree: self, rest }
+    }
+}
+
+impl<T> CursorMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    /// Returns the index of the cursor.
+    pub fn index(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.tree.len() + self.rest.len()
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at the cursor, or `None` if the cursor is at the end.
+    pub fn current(&self) -> Option<&T> {
+        self.rest.last()
+    }
+
+    /// Returns a mutable reference to the element at the cursor, or `None` if the cursor is at the end.
+    pub fn current_mut(&mut self) -> Option<&mut T> {
+        self.rest.last_mut()
+    }
+
+    /// Returns the element before the cursor.
+    pub fn peek_prev(&self) -> Option<&T> {
+        self.index()
+            .checked_sub(1)
+            .and_then(|index| self.tree.get(index))
+    }
+
+    /// Returns the element after the cursor.
+    pub fn peek_next(&self) -> Option<&T> {
+        self.rest
+            .len()
+            .checked_sub(2)
+            .map(|index| &self.rest[index])
+    }
+
+    /// Moves the cursor to the next element. It returns `false` and doesn't move if the cursor is at the end.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn move_next(&mut self) -> bool {
+        match self.rest.pop() {
+            Some(element) => {
+                self.tree.push_leaf(element); // CLEAN: parents of the element
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the cursor to the previous element. It returns `false` and doesn't move if the cursor is at the start.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn move_prev(&mut self) -> bool {
+        if self.tree.is_empty() {
+            return false;
+        }
+
+        let element = self.tree.pop_leaf();
+        self.rest.push(element);
+        true
+    }
+
+    /// Inserts an element before the cursor. The cursor keeps pointing to the same element.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn insert_before(&mut self, element: T) {
+        assert!(
+            self.tree.remaining_capacity() > self.rest.len(),
+            "capacity overflow"
+        );
+
+        self.tree.push_leaf(element); // CLEAN: parents of the new element
+    }
+
+    /// Inserts an element after the cursor. The cursor keeps pointing to the same element.
+    /// If the cursor is at the end, the element is inserted at the end, and the cursor points to it.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn insert_after(&mut self, element: T) {
+        assert!(
+            self.tree.remaining_capacity() > self.rest.len(),
+            "capacity overflow"
+        );
+
+        let index = self.rest.len().saturating_sub(1);
+        self.rest.insert(index, element);
+    }
+
+    /// Removes the element at the cursor, and moves the cursor to the next element.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.rest.pop()
+    }
+}
+
+impl<T> Drop for CursorMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    fn drop(&mut self) {
+        self.tree.reserve(self.rest.len());
+        self.tree.push_leaves(self.rest.drain(..).rev()); // CLEAN: parents of the elements after the cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_matches_naive() {
+        let mut model: Vec<u32> = (0..10).collect();
+        let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+        for round in 0..10u32 {
+            let mut index = (round as usize * 7) % (model.len() + 1);
+            let mut cursor = tree.cursor_mut(index);
+            for step in 0..20u32 {
+                let value = round * 100 + step;
+                match (round + step) % 5 {
+                    0 => {
+                        assert_eq!(cursor.move_next(), index < model.len());
+                        index = usize::min(index + 1, model.len());
+                    }
+                    1 => {
+                        assert_eq!(cursor.move_prev(), index > 0);
+                        index = index.saturating_sub(1);
+                    }
+                    2 => {
+                        cursor.insert_before(value);
+                        model.insert(index, value);
+                        index += 1;
+                    }
+                    3 => {
+                        cursor.insert_after(value);
+                        model.insert(usize::min(index + 1, model.len()), value);
+                    }
+                    _ => {
+                        let expected = (index < model.len()).then(|| model.remove(index));
+                        assert_eq!(cursor.remove_current(), expected);
+                    }
+                }
+
+                assert_eq!(cursor.index(), index);
+                assert_eq!(cursor.len(), model.len());
+                assert_eq!(cursor.current(), model.get(index));
+                assert_eq!(cursor.peek_next(), model.get(index + 1));
+                assert_eq!(
+                    cursor.peek_prev(),
+                    index.checked_sub(1).and_then(|index| model.get(index))
+                );
+            }
+            drop(cursor);
+
+            assert!(tree.iter().eq(model.iter()));
+            for index in 0..=model.len() {
+                assert_eq!(tree.prefix_sum(index), model[..index].iter().sum());
+            }
+        }
+    }
+}
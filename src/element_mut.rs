@@ -0,0 +1,259 @@
+use std::iter::FusedIterator;
+use std::ops::{Deref, DerefMut};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::internal::node_id::LeafNodeId;
+
+/// A guard of a mutable reference to an element, which is returned by [`PostfixSegmentTree::get_mut`].
+///
+/// Partial sums are recalculated when it is dropped.
+/// If it is leaked with `std::mem::forget`, partial sums are left stale until the element is updated again.
+pub struct ElementMut<'a, T>
+where
+    T: Combine + Clone,
+{
+    tree: &'a mut PostfixSegmentTree<T>,
+    id: LeafNodeId,
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Returns a mutable reference guard to an element at `index`.
+    ///
+    /// Partial sums are recalculated when the guard is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// if let Some(mut element) = tree.get_mut(1) {
+    ///     *element += 10;
+    /// }
+    ///
+    /// assert_eq!(tree[1], 12);
+    /// assert_eq!(tree.prefix_sum(3), 16);
+    /// assert!(tree.get_mut(3).is_none());
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1), and *O*(log [`len`]) on drop.
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn get_mut(&mut self, index: usize) -> Option<ElementMut<'_, T>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        Some(ElementMut {
+            tree: self,
+            id: LeafNodeId::new(index),
+        })
+    }
+}
+
+impl<T> Deref for ElementMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.tree.get_leaf_node(self.id)
+    }
+}
+
+impl<T> DerefMut for ElementMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.tree.get_leaf_node_mut(self.id) // DIRTY: parents of `id`
+    }
+}
+
+impl<T> Drop for ElementMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    fn drop(&mut self) {
+        self.tree.recalculate_nodes_after_update(self.id); // CLEAN: parents of `id`
+    }
+}
+
+/// A guard of mutable references to all elements, which is returned by [`PostfixSegmentTree::elements_mut`].
+///
+/// Elements are iterated with [`ElementsMut::iter_mut`], and partial sums are recalculated at once when it is dropped.
+/// References to elements can't outlive the guard, so partial sums are always recalculated after the last write.
+pub struct ElementsMut<'a, T>
+where
+    T: Combine + Clone,
+{
+    tree: &'a mut PostfixSegmentTree<T>,
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Returns a guard to modify all elements, which recalculates partial sums in a single pass when it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// tree.elements_mut().iter_mut().for_each(|element| *element *= 10);
+    ///
+    /// assert_eq!(tree[1], 20);
+    /// assert_eq!(tree.prefix_sum(4), 100);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1), and *O*([`nodes_len`]) on drop.
+    ///
+    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    pub fn elements_mut(&mut self) -> ElementsMut<'_, T> {
+        ElementsMut { tree: self }
+    }
+}
+
+impl<T> ElementsMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    /// Returns an iterator of mutable references to elements, which can't outlive the guard.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let len = self.tree.len();
+        IterMut::new(&mut self.tree.nodes, 0, len) // DIRTY: all parents
+    }
+}
+
+impl<'a, 'b, T> IntoIterator for &'b mut ElementsMut<'a, T>
+where
+    T: Combine + Clone,
+{
+    type Item = &'b mut T;
+    type IntoIter = IterMut<'b, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> Drop for ElementsMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    fn drop(&mut self) {
+        if !self.tree.is_empty() {
+            self.tree
+                .recalculate_nodes_after_bulk_update(LeafNodeId::new(0)); // CLEAN: all parents
+        }
+    }
+}
+
+/// Iterator for mutable references to elements, which is created by [`ElementsMut::iter_mut`].
+pub struct IterMut<'a, T> {
+    // nodes from the leaf node of `index`
+    nodes: &'a mut [T],
+    index: usize,
+    len: usize,
+}
+
+impl<'a, T> IterMut<'a, T> {
+    /// `nodes` starts from the leaf node of `index`, and elements until `len` are iterated.
+    pub(crate) fn new(nodes: &'a mut [T], index: usize, len: usize) -> Self {
+        IterMut { nodes, index, len }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+
+        let nodes = std::mem::take(&mut self.nodes);
+        let (leaf, rest) = nodes.split_first_mut()?;
+
+        // skip parent nodes that follow the leaf node
+        let parents = LeafNodeId::new(self.index).max_level() as usize;
+        self.nodes = &mut rest[parents..];
+        self.index += 1;
+
+        Some(leaf)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elements_mut_of_empty_tree() {
+        let mut tree = PostfixSegmentTree::<i32>::new();
+        assert_eq!(tree.elements_mut().iter_mut().count(), 0);
+        assert_eq!(tree.prefix_sum(0), 0);
+    }
+
+    #[test]
+    fn test_elements_mut_partial_iteration() {
+        let mut model: Vec<i64> = (0..37).collect();
+        let mut tree = PostfixSegmentTree::from_iter(model.iter().copied());
+
+        let mut elements = tree.elements_mut();
+        let mut iter = elements.iter_mut();
+        assert_eq!(iter.len(), model.len());
+        for element in iter.by_ref().take(10) {
+            *element = -*element;
+        }
+        assert_eq!(iter.len(), model.len() - 10);
+        drop(elements);
+        model[..10]
+            .iter_mut()
+            .for_each(|element| *element = -*element);
+
+        assert!(tree.iter().eq(model.iter()));
+        for index in 0..=model.len() {
+            assert_eq!(tree.prefix_sum(index), model[..index].iter().sum::<i64>());
+        }
+    }
+
+    #[test]
+    fn test_elements_mut_recalculates_every_prefix_sum() {
+        for len in 0..40 {
+            let mut model: Vec<i64> = (0..len).map(|i| (i * 7) % 5 - 2).collect();
+            let mut tree = PostfixSegmentTree::from_iter(model.iter().copied());
+
+            for (index, element) in (&mut tree.elements_mut()).into_iter().enumerate() {
+                *element *= index as i64 + 3;
+            }
+            for (index, element) in model.iter_mut().enumerate() {
+                *element *= index as i64 + 3;
+            }
+
+            for index in 0..=model.len() {
+                assert_eq!(tree.prefix_sum(index), model[..index].iter().sum::<i64>());
+            }
+        }
+    }
+}
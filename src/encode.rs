@@ -0,0 +1,258 @@
+use std::io::{self, Read, Write};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::error::DecodeError;
+
+const MAGIC: [u8; 4] = *b"PSTE";
+const FORMAT_VERSION: u16 = 1;
+
+/// An element that can be written to and read from [`encode_to`] snapshots.
+///
+/// Primitives are encoded in little-endian, and `usize` and `isize` are encoded as 64-bit integers,
+/// so snapshots are portable across platforms.
+///
+/// # Laws
+///
+/// * `T::decode_from(r)` reads back exactly what `t.encode_to(w)` has written, and returns `t`
+///
+/// [`encode_to`]: PostfixSegmentTree::encode_to
+pub trait Encode: Sized {
+    fn encode_to<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+    fn decode_from<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_encode {
+    ($($ty:ty => $repr:ty),* $(,)?) => {
+        $(
+            impl Encode for $ty {
+                fn encode_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&(*self as $repr).to_le_bytes())
+                }
+
+                fn decode_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0; size_of::<$repr>()];
+                    reader.read_exact(&mut bytes)?;
+                    <$repr>::from_le_bytes(bytes).try_into().map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidData, "element out of range")
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_encode! {
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+    u128 => u128,
+    usize => u64,
+    i8 => i8,
+    i16 => i16,
+    i32 => i32,
+    i64 => i64,
+    i128 => i128,
+    isize => i64,
+}
+
+macro_rules! impl_encode_float {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn decode_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0; size_of::<$ty>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$ty>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_float!(f32, f64);
+
+/// FNV-1a hash of bytes, for the integrity check of snapshots.
+struct Checksum(u64);
+
+impl Checksum {
+    fn new() -> Self {
+        Checksum(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+struct ChecksumWriter<'a, W> {
+    writer: &'a mut W,
+    checksum: Checksum,
+}
+
+impl<W: Write> Write for ChecksumWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.checksum.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+struct ChecksumReader<'a, R> {
+    reader: &'a mut R,
+    checksum: Checksum,
+}
+
+impl<R: Read> Read for ChecksumReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.checksum.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Binary snapshots.
+///
+/// A snapshot consists of a header of the magic bytes `PSTE`, the format version and the number of elements,
+/// elements in order, and a checksum of the header and elements.
+/// Only elements are written, and internal nodes are rebuilt on decoding,
+/// so snapshots don't depend on the layout of nodes and stay readable across versions of this crate.
+impl<T> PostfixSegmentTree<T>
+where
+    T: Encode + Combine + Clone,
+{
+    /// Writes a snapshot of the tree to `writer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([3u32, 1, 4, 1, 5]);
+    /// let mut bytes = Vec::new();
+    /// tree.encode_to(&mut bytes).unwrap();
+    ///
+    /// let decoded = PostfixSegmentTree::<u32>::decode_from(&mut bytes.as_slice()).unwrap();
+    /// assert_eq!(decoded.prefix_sum(5), 14);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn encode_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut writer = ChecksumWriter {
+            writer,
+            checksum: Checksum::new(),
+        };
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+        for element in self.iter() {
+            element.encode_to(&mut writer)?;
+        }
+
+        let checksum = writer.checksum.0;
+        writer.writer.write_all(&checksum.to_le_bytes())
+    }
+
+    /// Reads a snapshot written by [`encode_to`] from `reader`, and rebuilds the tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `reader` fails or ends early, the snapshot is not of a supported version,
+    /// or the checksum doesn't match.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`encode_to`]: PostfixSegmentTree::encode_to
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut reader = ChecksumReader {
+            reader,
+            checksum: Checksum::new(),
+        };
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(DecodeError::InvalidMagic);
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let mut len = [0; 8];
+        reader.read_exact(&mut len)?;
+        let len = u64::from_le_bytes(len);
+
+        // Elements are pushed as they are read, so a corrupted length doesn't allocate up front.
+        let mut tree = Self::new();
+        for _ in 0..len {
+            tree.push(T::decode_from(&mut reader)?);
+        }
+
+        let expected = reader.checksum.0;
+        let mut checksum = [0; 8];
+        reader.reader.read_exact(&mut checksum)?;
+        if u64::from_le_bytes(checksum) != expected {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_round_trip_and_corruption() {
+        for len in [0, 1, 2, 7, 64, 100] {
+            let tree = PostfixSegmentTree::from_iter((0..len).map(|i| i as i64 * 37 - 500));
+            let mut bytes = Vec::new();
+            tree.encode_to(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), 4 + 2 + 8 + len * 8 + 8);
+
+            let decoded = PostfixSegmentTree::<i64>::decode_from(&mut bytes.as_slice()).unwrap();
+            assert_eq!(decoded.nodes, tree.nodes);
+            assert_eq!(decoded.len(), tree.len());
+
+            for position in 0..bytes.len() {
+                let mut corrupted = bytes.clone();
+                corrupted[position] ^= 0x10;
+                assert!(PostfixSegmentTree::<i64>::decode_from(&mut corrupted.as_slice()).is_err());
+            }
+            assert!(matches!(
+                PostfixSegmentTree::<i64>::decode_from(&mut &bytes[..bytes.len() - 1]),
+                Err(DecodeError::Io(_))
+            ));
+        }
+
+        let mut bytes = Vec::new();
+        PostfixSegmentTree::from_iter([1u8])
+            .encode_to(&mut bytes)
+            .unwrap();
+        bytes[4] = 2;
+        assert!(matches!(
+            PostfixSegmentTree::<u8>::decode_from(&mut bytes.as_slice()),
+            Err(DecodeError::UnsupportedVersion(2))
+        ));
+    }
+}
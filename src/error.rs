@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::node::NodeAddress;
+
+/// The error type for `try_reserve` methods of [`PostfixSegmentTree`].
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TryReserveError {
+    /// The number of elements exceeds the maximum for the platform.
+    CapacityOverflow,
+    /// The allocator failed to allocate nodes.
+    AllocError(std::collections::TryReserveError),
+}
+
+impl Display for TryReserveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => {
+                write!(f, "capacity overflow: too many elements for the platform")
+            }
+            TryReserveError::AllocError(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl Error for TryReserveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TryReserveError::CapacityOverflow => None,
+            TryReserveError::AllocError(error) => Some(error),
+        }
+    }
+}
+
+impl From<std::collections::TryReserveError> for TryReserveError {
+    fn from(error: std::collections::TryReserveError) -> Self {
+        TryReserveError::AllocError(error)
+    }
+}
+
+/// The error type for [`PostfixSegmentTree::decode_from`].
+///
+/// [`PostfixSegmentTree::decode_from`]: crate::PostfixSegmentTree::decode_from
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The reader failed, ended early, or contained an invalid element.
+    Io(std::io::Error),
+    /// The input doesn't start with the magic bytes of snapshots.
+    InvalidMagic,
+    /// The snapshot is of a format version that is not supported by this version of the crate.
+    UnsupportedVersion(u16),
+    /// The checksum doesn't match the contents, so the snapshot is corrupted.
+    ChecksumMismatch,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Io(error) => Display::fmt(error, f),
+            DecodeError::InvalidMagic => write!(f, "not a snapshot of a postfix segment tree"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot format version {version}")
+            }
+            DecodeError::ChecksumMismatch => write!(f, "snapshot checksum mismatch"),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(error: std::io::Error) -> Self {
+        DecodeError::Io(error)
+    }
+}
+
+/// The error type for [`PostfixSegmentTree::validate`] and [`PostfixSegmentTree::from_raw_parts`],
+/// which describes the first violation of invariants.
+///
+/// [`PostfixSegmentTree::validate`]: crate::PostfixSegmentTree::validate
+/// [`PostfixSegmentTree::from_raw_parts`]: crate::PostfixSegmentTree::from_raw_parts
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValidationError {
+    /// The number of nodes doesn't match the number of elements.
+    NodesLen {
+        len: usize,
+        nodes_len: usize,
+        /// The number of nodes for `len` elements, or `None` if it overflows.
+        expected: Option<usize>,
+    },
+    /// The internal node at the address doesn't equal the combination of its children.
+    Node(NodeAddress),
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::NodesLen {
+                len,
+                nodes_len,
+                expected: Some(expected),
+            } => write!(
+                f,
+                "{len} elements need {expected} nodes, but there are {nodes_len} nodes"
+            ),
+            ValidationError::NodesLen { len, .. } => {
+                write!(
+                    f,
+                    "capacity overflow: too many elements ({len}) for the platform"
+                )
+            }
+            ValidationError::Node(address) => write!(
+                f,
+                "node {} of level {} for elements {:?} doesn't equal the combination of its children",
+                address.node_index(),
+                address.level(),
+                address.elements()
+            ),
+        }
+    }
+}
+
+impl Error for ValidationError {}
@@ -0,0 +1,151 @@
+use std::iter::FusedIterator;
+use std::ops::RangeBounds;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::internal::range::to_range;
+
+/// An iterator that removes elements matching a predicate, which is returned by [`PostfixSegmentTree::extract_if`].
+///
+/// Elements from the start of the range are moved out when it is created.
+/// Each non-matching element is pushed back as it is visited, so nodes are repaired along the iteration.
+/// Elements that are not visited are pushed back when it is dropped.
+/// If it is leaked with `std::mem::forget`, those elements are lost.
+pub struct ExtractIf<'a, T, F>
+where
+    T: Combine + Clone,
+    F: FnMut(&mut T) -> bool,
+{
+    tree: &'a mut PostfixSegmentTree<T>,
+    // elements from the start of the range, which are not visited yet
+    rest: std::vec::IntoIter<T>,
+    // the number of elements in `rest` that are in the range
+    remaining: usize,
+    predicate: F,
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Returns an iterator that removes and yields elements in `range` that match `predicate`,
+    /// like [`Vec::extract_if`].
+    ///
+    /// `predicate` can modify the elements, and non-matching elements are retained with the modification.
+    /// If the iterator is dropped before it is exhausted, the remaining elements are retained.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5, 6]);
+    /// let evens: Vec<_> = tree.extract_if(.., |element| *element % 2 == 0).collect();
+    ///
+    /// assert_eq!(evens, vec![2, 4, 6]);
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5]);
+    /// assert_eq!(tree.prefix_sum(3), 9);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`] - `range.start`) over the whole iteration.
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn extract_if<F>(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        predicate: F,
+    ) -> ExtractIf<'_, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let range = to_range(range, self.len());
+        let rest: Vec<T> = self.drain_leaves_from(range.start).collect();
+        ExtractIf {
+            tree: self,
+            rest: rest.into_iter(),
+            remaining: range.len(),
+            predicate,
+        }
+    }
+}
+
+impl<T, F> Iterator for ExtractIf<'_, T, F>
+where
+    T: Combine + Clone,
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining > 0 {
+            self.remaining -= 1;
+
+            let mut element = self.rest.next().expect("an element in the range");
+            if (self.predicate)(&mut element) {
+                return Some(element);
+            }
+
+            self.tree.push_leaf(element); // CLEAN: parents of the retained element
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+impl<T, F> FusedIterator for ExtractIf<'_, T, F>
+where
+    T: Combine + Clone,
+    F: FnMut(&mut T) -> bool,
+{
+}
+
+impl<T, F> Drop for ExtractIf<'_, T, F>
+where
+    T: Combine + Clone,
+    F: FnMut(&mut T) -> bool,
+{
+    fn drop(&mut self) {
+        self.tree.push_leaves(&mut self.rest); // CLEAN: parents of the retained elements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_if_matches_naive() {
+        const N: usize = 12;
+        for len in 0..N {
+            for start in 0..=len {
+                for end in start..=len {
+                    for taken in 0..=len {
+                        let mut model: Vec<u32> = (0..len as u32).collect();
+                        let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+                        let f = |element: &mut u32| {
+                            *element += 10;
+                            element.is_multiple_of(3)
+                        };
+                        let extracted: Vec<_> =
+                            tree.extract_if(start..end, f).take(taken).collect();
+                        let expected: Vec<_> =
+                            model.extract_if(start..end, f).take(taken).collect();
+
+                        assert_eq!(extracted, expected);
+                        assert!(tree.iter().eq(model.iter()));
+                        for index in 0..=model.len() {
+                            assert_eq!(tree.prefix_sum(index), model[..index].iter().sum());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
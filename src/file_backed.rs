@@ -0,0 +1,492 @@
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use memmap2::MmapMut;
+
+use crate::internal::node_id::{LeafNodeId, get_nodes_len_for};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::skipping_iterator::range_iterator;
+
+const NODE_SIZE: usize = size_of::<u64>();
+// the number of elements in little-endian
+const HEADER_SIZE: usize = size_of::<u64>();
+// the file grows by at least this many nodes, so small trees don't remap on every push
+const MIN_NODES_CAPACITY: usize = 64;
+
+/// A variant of [`PostfixSegmentTree`] of `u64` counters, whose nodes are stored in a memory-mapped file.
+///
+/// The file holds the number of elements and nodes in the same layout as [`PostfixSegmentTree`],
+/// so trees far larger than memory can be built with [`push`] and queried.
+/// Nodes are read and written through the map, and the OS pages them in and out of memory.
+/// The file grows by doubling its capacity, which remaps it.
+///
+/// Written nodes are tracked as dirty until [`flush`], which flushes them with
+/// [`MmapMut::flush_range`] in runs of consecutive nodes, and then updates the number of elements in the file.
+/// So only the tracking of dirty nodes takes memory, and the number of elements is only persisted by [`flush`].
+/// The tree is flushed when it is dropped, but errors are ignored then, like [`BufWriter`].
+///
+/// Writes return an error of [`io::ErrorKind::InvalidInput`] instead of overflowing, and leave the tree unchanged then.
+/// It requires the `mmap` feature.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::FilePostfixSegmentTree;
+///
+/// let path = std::env::temp_dir().join(format!("counters-{}.pst", std::process::id()));
+/// // SAFETY: nobody else modifies the file while it's mapped
+/// let mut counters = unsafe { FilePostfixSegmentTree::create(&path)? };
+/// for count in [3, 1, 4, 1, 5] {
+///     counters.push(count)?;
+/// }
+/// counters.add(1, 10)?;
+/// counters.flush()?;
+/// drop(counters);
+///
+/// // SAFETY: nobody else modifies the file while it's mapped
+/// let counters = unsafe { FilePostfixSegmentTree::open(&path)? };
+/// assert_eq!(counters.len(), 5);
+/// assert_eq!(counters.prefix_sum(3), Some(18));
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// # Time complexity
+///
+/// The same as [`PostfixSegmentTree`], in node reads and writes of the map.
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+/// [`push`]: FilePostfixSegmentTree::push
+/// [`flush`]: FilePostfixSegmentTree::flush
+/// [`BufWriter`]: std::io::BufWriter
+pub struct FilePostfixSegmentTree {
+    file: File,
+    map: MmapMut,
+    len: usize,
+    // node indices written since the last flush
+    dirty: BTreeSet<usize>,
+    // whether `len` has changed since the last flush
+    dirty_len: bool,
+}
+
+impl FilePostfixSegmentTree {
+    /// Creates an empty tree at `path`, truncating the file if it exists.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified by other processes or handles while the tree is alive,
+    /// since it is accessed through a memory map. See [`MmapMut::map_mut`].
+    pub unsafe fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((HEADER_SIZE + MIN_NODES_CAPACITY * NODE_SIZE) as u64)?;
+
+        // SAFETY: the caller guarantees that nobody else modifies the file
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let mut tree = Self {
+            file,
+            map,
+            len: 0,
+            dirty: BTreeSet::new(),
+            dirty_len: true,
+        };
+        tree.flush()?;
+        Ok(tree)
+    }
+
+    /// Opens a tree that has been flushed to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of [`io::ErrorKind::InvalidData`] if the file is shorter than its nodes.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified by other processes or handles while the tree is alive,
+    /// since it is accessed through a memory map. See [`MmapMut::map_mut`].
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        // SAFETY: the caller guarantees that nobody else modifies the file
+        let map = unsafe { MmapMut::map_mut(&file)? };
+
+        let len = map
+            .get(..HEADER_SIZE)
+            .and_then(|header| usize::try_from(u64::from_le_bytes(header.try_into().unwrap())).ok())
+            .filter(|&len| {
+                get_nodes_len_for(len)
+                    .checked_mul(NODE_SIZE)
+                    .is_some_and(|nodes_size| map.len() - HEADER_SIZE >= nodes_size)
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "truncated tree file"))?;
+
+        Ok(Self {
+            file,
+            map,
+            len,
+            dirty: BTreeSet::new(),
+            dirty_len: false,
+        })
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of nodes that are written but not flushed yet.
+    pub fn dirty_nodes(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Returns the element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, index: usize) -> u64 {
+        assert!(index < self.len);
+
+        self.read_node(LeafNodeId::new(index).node_index())
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file fails to grow, or a parent node overflows.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, element: u64) -> io::Result<()> {
+        let id = LeafNodeId::new(self.len);
+        let nodes = self.updated_nodes(id, element, self.len + 1)?;
+        self.reserve_nodes(get_nodes_len_for(self.len + 1))?;
+
+        self.len += 1;
+        self.dirty_len = true;
+        self.write_nodes(nodes);
+        Ok(())
+    }
+
+    /// Updates the element at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parent node overflows.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: FilePostfixSegmentTree::len
+    pub fn update(&mut self, index: usize, element: u64) -> io::Result<()> {
+        assert!(index < self.len);
+
+        let nodes = self.updated_nodes(LeafNodeId::new(index), element, self.len)?;
+        self.write_nodes(nodes);
+        Ok(())
+    }
+
+    /// Adds `delta` to the element at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the element or a parent node overflows.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: FilePostfixSegmentTree::len
+    pub fn add(&mut self, index: usize, delta: u64) -> io::Result<()> {
+        let element = checked_add(self.get(index), delta)?;
+        self.update(index, element)
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`, or `None` if it overflows.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn prefix_sum(&self, index: usize) -> Option<u64> {
+        self.sum(0, index)
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`, or `None` if it overflows.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`index` + `len`))
+    pub fn sum(&self, index: usize, len: usize) -> Option<u64> {
+        assert!(index <= self.len);
+        assert!(len <= self.len - index);
+
+        let mut sum: u64 = 0;
+        for id in range_iterator(index, index + len) {
+            sum = sum.checked_add(self.read_node(id.node_index()))?;
+        }
+
+        Some(sum)
+    }
+
+    /// Flushes dirty nodes to the file, and then updates the number of elements in it.
+    ///
+    /// Consecutive dirty nodes are flushed together with [`MmapMut::flush_range`], which waits for them to be written.
+    /// Nodes are flushed before the number of elements, so a file never refers to nodes that are not flushed yet.
+    /// On error, nodes after the last flushed run stay dirty.
+    pub fn flush(&mut self) -> io::Result<()> {
+        while let Some(&start) = self.dirty.first() {
+            let mut end = start + 1;
+            while self.dirty.range(end..).next() == Some(&end) {
+                end += 1;
+            }
+
+            self.map
+                .flush_range(node_offset(start), (end - start) * NODE_SIZE)?;
+            self.dirty = self.dirty.split_off(&end);
+        }
+
+        if self.dirty_len {
+            self.map[..HEADER_SIZE].copy_from_slice(&(self.len as u64).to_le_bytes());
+            self.map.flush_range(0, HEADER_SIZE)?;
+            self.dirty_len = false;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the tree, and also waits for metadata of the file such as its length with [`File::sync_data`].
+    pub fn sync_data(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.file.sync_data()
+    }
+
+    fn read_node(&self, node_index: usize) -> u64 {
+        let offset = node_offset(node_index);
+        u64::from_le_bytes(self.map[offset..offset + NODE_SIZE].try_into().unwrap())
+    }
+
+    fn write_nodes(&mut self, nodes: Vec<(usize, u64)>) {
+        for (node_index, node) in nodes {
+            let offset = node_offset(node_index);
+            self.map[offset..offset + NODE_SIZE].copy_from_slice(&node.to_le_bytes());
+            self.dirty.insert(node_index);
+        }
+    }
+
+    /// Grows the file and remaps it if it can't hold `nodes_len` nodes, by doubling its capacity.
+    ///
+    /// Dirty nodes are in the page cache of the file, so they are flushed through the new map.
+    fn reserve_nodes(&mut self, nodes_len: usize) -> io::Result<()> {
+        let nodes_capacity = (self.map.len() - HEADER_SIZE) / NODE_SIZE;
+        if nodes_len <= nodes_capacity {
+            return Ok(());
+        }
+
+        let nodes_capacity = usize::max(nodes_len, nodes_capacity * 2);
+        self.file
+            .set_len((HEADER_SIZE + nodes_capacity * NODE_SIZE) as u64)?;
+        // SAFETY: the caller of `create` or `open` guarantees that nobody else modifies the file
+        self.map = unsafe { MmapMut::map_mut(&self.file)? };
+        Ok(())
+    }
+
+    /// Returns the leaf node `id` with `element`, and its parents in a tree of `len` elements recalculated for it.
+    ///
+    /// Nothing is written, so the tree stays unchanged if it fails.
+    fn updated_nodes(
+        &self,
+        id: LeafNodeId,
+        element: u64,
+        len: usize,
+    ) -> io::Result<Vec<(usize, u64)>> {
+        let mut nodes = vec![(id.node_index(), element)];
+        let (mut child_index, mut child) = (id.node_index(), element);
+        for parent in ParentsIterator::new(id, len) {
+            let left = parent.left_child().node_index();
+            let right = parent.right_child().node_index();
+            let sibling = self.read_node(if child_index == left { right } else { left });
+
+            child_index = parent.node_index();
+            child = checked_add(child, sibling)?;
+            nodes.push((child_index, child));
+        }
+
+        Ok(nodes)
+    }
+}
+
+fn node_offset(node_index: usize) -> usize {
+    HEADER_SIZE + node_index * NODE_SIZE
+}
+
+fn checked_add(left: u64, right: u64) -> io::Result<u64> {
+    left.checked_add(right)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "counter overflow"))
+}
+
+impl Drop for FilePostfixSegmentTree {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "postfix-segment-tree-{name}-{}.pst",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_file_backed_matches_model() {
+        let path = temp_path("file-backed");
+        let mut model: Vec<u64> = Vec::new();
+        let mut tree = unsafe { FilePostfixSegmentTree::create(&path).unwrap() };
+        for step in 0..300u64 {
+            if step % 3 == 0 && !model.is_empty() {
+                let index = (step as usize * 7) % model.len();
+                tree.add(index, step).unwrap();
+                model[index] += step;
+            } else {
+                tree.push(step).unwrap();
+                model.push(step);
+            }
+            if step % 50 == 0 {
+                tree.flush().unwrap();
+                assert_eq!(tree.dirty_nodes(), 0);
+                tree = unsafe { FilePostfixSegmentTree::open(&path).unwrap() };
+            }
+
+            for index in 0..=model.len() {
+                assert_eq!(tree.prefix_sum(index), Some(model[..index].iter().sum()));
+            }
+        }
+
+        // dropping flushes
+        drop(tree);
+        let tree = unsafe { FilePostfixSegmentTree::open(&path).unwrap() };
+        for (index, element) in model.iter().enumerate() {
+            assert_eq!(tree.get(index), *element);
+            assert_eq!(
+                tree.sum(index, model.len() - index),
+                Some(model[index..].iter().sum())
+            );
+        }
+        drop(tree);
+
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len((HEADER_SIZE + NODE_SIZE) as u64).unwrap();
+        assert_eq!(
+            unsafe { FilePostfixSegmentTree::open(&path) }
+                .err()
+                .unwrap()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_flush_runs_and_growth() {
+        let path = temp_path("file-backed-flush");
+        let mut tree = unsafe { FilePostfixSegmentTree::create(&path).unwrap() };
+
+        // pushes write consecutive nodes, across several remaps of a growing file
+        for element in 0..10_000 {
+            tree.push(element).unwrap();
+        }
+        assert_eq!(tree.dirty_nodes(), get_nodes_len_for(10_000));
+        tree.flush().unwrap();
+        assert_eq!(tree.dirty_nodes(), 0);
+
+        // an update writes a leaf and its scattered parents
+        tree.update(5, 0).unwrap();
+        assert!(tree.dirty_nodes() > 1);
+        tree.flush().unwrap();
+        assert_eq!(tree.dirty_nodes(), 0);
+
+        // nothing is persisted without flushing the number of elements
+        tree.push(1).unwrap();
+        std::mem::forget(tree);
+        let tree = unsafe { FilePostfixSegmentTree::open(&path).unwrap() };
+        assert_eq!(tree.len(), 10_000);
+        assert_eq!(tree.prefix_sum(10_000), Some((0..10_000).sum::<u64>() - 5));
+
+        drop(tree);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_overflow_leaves_tree_unchanged() {
+        let path = temp_path("file-backed-overflow");
+        let mut tree = unsafe { FilePostfixSegmentTree::create(&path).unwrap() };
+        tree.push(u64::MAX - 1).unwrap();
+        assert_eq!(
+            tree.push(2).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(tree.len(), 1);
+        tree.push(1).unwrap();
+
+        assert_eq!(
+            tree.add(0, 2).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            tree.add(1, 1).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.get(0), u64::MAX - 1);
+        assert_eq!(tree.get(1), 1);
+        assert_eq!(tree.prefix_sum(2), Some(u64::MAX));
+
+        tree.push(1).unwrap();
+        assert_eq!(tree.prefix_sum(3), None);
+
+        drop(tree);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_reads() {
+        let path = temp_path("file-backed-concurrent");
+        let mut tree = unsafe { FilePostfixSegmentTree::create(&path).unwrap() };
+        for element in 0..1000 {
+            tree.push(element).unwrap();
+        }
+        tree.flush().unwrap();
+
+        std::thread::scope(|scope| {
+            for offset in 0..4 {
+                let tree = &tree;
+                scope.spawn(move || {
+                    for index in (offset..1000).step_by(4) {
+                        assert_eq!(tree.get(index), index as u64);
+                        assert_eq!(tree.prefix_sum(index), Some((0..index as u64).sum()));
+                    }
+                });
+            }
+        });
+
+        drop(tree);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
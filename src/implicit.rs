@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::ops::{Bound, RangeBounds};
+
+use crate::aggregate::Aggregate;
+use crate::internal::consts;
+use crate::internal::node_id::{LeafNodeId, NodeId};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::skipping_iterator::range_iterator;
+
+/// A variant of [`PostfixSegmentTree`] over a huge virtual domain of `u64` indices, such as coordinates up to 10<sup>18</sup>,
+/// which allocates nodes on first touch.
+///
+/// Nodes are addressed in the same layout as [`PostfixSegmentTree`], but stored in a `HashMap` by their node indices.
+/// Nodes that have never been touched are the identity, so the domain doesn't need to be compressed beforehand,
+/// and it takes *O*(log [`len`]) nodes of memory per touched index.
+/// [`update`] and [`sum`] are *O*(log [`len`]) hash map operations.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::ImplicitPostfixSegmentTree;
+///
+/// let mut tree = ImplicitPostfixSegmentTree::new(1_000_000_000_000_000_000);
+/// tree.add(3, 1);
+/// tree.add(123_456_789_012_345, 10);
+/// tree.add(999_999_999_999_999_999, 100);
+/// assert_eq!(tree.sum(0..1_000_000_000_000_000), 11);
+/// assert_eq!(tree.sum(4..), 110);
+/// assert_eq!(tree.get(3), 1);
+/// assert_eq!(tree.get(4), 0);
+/// ```
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+/// [`update`]: ImplicitPostfixSegmentTree::update
+/// [`sum`]: ImplicitPostfixSegmentTree::sum
+/// [`len`]: ImplicitPostfixSegmentTree::len
+pub struct ImplicitPostfixSegmentTree<T> {
+    // touched nodes by the node index
+    nodes: HashMap<usize, T>,
+    len: u64,
+}
+
+impl<T> ImplicitPostfixSegmentTree<T>
+where
+    T: Aggregate + Clone,
+{
+    /// Creates a tree of `len` identity elements without allocating any nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if node indices of `len` elements overflow `usize`.
+    pub fn new(len: u64) -> Self {
+        assert!(
+            usize::try_from(len).is_ok_and(|len| len <= consts::MAX_LEN),
+            "capacity overflow"
+        );
+
+        Self {
+            nodes: HashMap::new(),
+            len,
+        }
+    }
+
+    /// Returns the size of the domain.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the domain is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of nodes that have been allocated.
+    pub fn allocated_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, index: u64) -> T {
+        assert!(index < self.len, "index {index} is out of bounds");
+
+        self.node(LeafNodeId::new(index as usize).node_index())
+    }
+
+    /// Updates the element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: ImplicitPostfixSegmentTree::len
+    pub fn update(&mut self, index: u64, element: T) {
+        assert!(index < self.len, "index {index} is out of bounds");
+
+        let id = LeafNodeId::new(index as usize);
+        self.nodes.insert(id.node_index(), element);
+        for parent in ParentsIterator::new(id, self.len as usize) {
+            let sum = self.sum_of_children(parent);
+            self.nodes.insert(parent.node_index(), sum);
+        }
+    }
+
+    /// Combines `delta` into the element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: ImplicitPostfixSegmentTree::len
+    pub fn add(&mut self, index: u64, delta: T) {
+        let mut element = self.get(index);
+        element.combine(&delta);
+        self.update(index, element);
+    }
+
+    /// Returns the sum of elements in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: ImplicitPostfixSegmentTree::len
+    pub fn sum(&self, range: impl RangeBounds<u64>) -> T {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1).expect("range start overflow"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1).expect("range end overflow"),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len,
+        };
+        assert!(
+            start <= end && end <= self.len,
+            "range {start}..{end} is out of bounds"
+        );
+
+        let mut sum = T::identity();
+        for id in range_iterator(start as usize, end as usize) {
+            if let Some(node) = self.nodes.get(&id.node_index()) {
+                sum.combine(node);
+            }
+        }
+
+        sum
+    }
+
+    fn node(&self, node_index: usize) -> T {
+        self.nodes
+            .get(&node_index)
+            .cloned()
+            .unwrap_or_else(T::identity)
+    }
+
+    fn sum_of_children(&self, id: NodeId) -> T {
+        let mut sum = self.node(id.left_child().node_index());
+        if let Some(right) = self.nodes.get(&id.right_child().node_index()) {
+            sum.combine(right);
+        }
+
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_implicit_matches_naive() {
+        const LEN: u64 = 1 << 40;
+        let points: Vec<u64> = (0..20u64)
+            .map(|i| (i * 0x9E37_79B9_7F4A) % LEN)
+            .chain([0, LEN - 1])
+            .collect();
+        let mut model: Vec<(u64, i64)> = Vec::new();
+        let mut tree = ImplicitPostfixSegmentTree::new(LEN);
+        for (step, &point) in points.iter().enumerate() {
+            tree.update(point, step as i64 + 1);
+            model.retain(|&(index, _)| index != point);
+            model.push((point, step as i64 + 1));
+
+            for &start in &points {
+                for &end in &points {
+                    if start > end {
+                        continue;
+                    }
+                    let expected: i64 = model
+                        .iter()
+                        .filter(|&&(index, _)| start <= index && index < end)
+                        .map(|&(_, element)| element)
+                        .sum();
+                    assert_eq!(tree.sum(start..end), expected);
+                    assert_eq!(tree.sum(start..=end), expected + tree.get(end));
+                }
+            }
+        }
+
+        assert!(tree.allocated_nodes() <= points.len() * 41);
+    }
+}
@@ -1,9 +1,11 @@
+#[cfg(feature = "allocator_api")]
+use std::alloc::Allocator;
 use std::ops::Index;
 
-use crate::PostfixSegmentTree;
 use crate::internal::node_id::LeafNodeId;
+use crate::tree_in;
 
-impl<T> PostfixSegmentTree<T> {
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A) {
     /// Returns an element at `index`.
     ///
     /// # Examples
@@ -26,9 +28,47 @@ impl<T> PostfixSegmentTree<T> {
         let id = LeafNodeId::new(index);
         Some(self.get_leaf_node(id))
     }
+
+    /// Returns the first element, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// assert_eq!(tree.first(), Some(&1));
+    /// assert_eq!(PostfixSegmentTree::<i32>::new().first(), None);
+    /// ```
+    ///
+    /// # Time Complexity
+    ///
+    /// *O*(1)
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns the last element, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// assert_eq!(tree.last(), Some(&3));
+    /// assert_eq!(PostfixSegmentTree::<i32>::new().last(), None);
+    /// ```
+    ///
+    /// # Time Complexity
+    ///
+    /// *O*(1)
+    pub fn last(&self) -> Option<&T> {
+        self.get(self.len().checked_sub(1)?)
+    }
 }
 
-impl<T> Index<usize> for PostfixSegmentTree<T> {
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> Index<usize> for tree_in!(T, A) {
     type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
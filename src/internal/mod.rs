@@ -1,7 +1,31 @@
 pub(crate) mod node_id;
 pub(crate) mod operations;
+pub(crate) mod parents_iterator;
+pub(crate) mod range;
 pub(crate) mod skipping_iterator;
 
 pub(crate) mod consts {
     pub const MAX_LEN: usize = usize::MAX / 2;
+
+    /// The maximum number of elements for nodes of `T`, which is platform-aware.
+    ///
+    /// `Vec<T>` can't hold more than `isize::MAX` bytes, so it's far less than [`MAX_LEN`] on 32-bit targets.
+    pub const fn max_len_for<T>() -> usize {
+        let max_nodes_len = match size_of::<T>() {
+            0 => usize::MAX,
+            size => isize::MAX as usize / size,
+        };
+
+        // `get_nodes_len_for(len)` == `len * 2 - len.count_ones()` is between `len * 2 - usize::BITS` and `len * 2`,
+        // so start from the lower bound and step up.
+        let mut len = max_nodes_len / 2;
+        if len > MAX_LEN {
+            len = MAX_LEN;
+        }
+        while len < MAX_LEN && (len + 1) * 2 - (len + 1).count_ones() as usize <= max_nodes_len {
+            len += 1;
+        }
+
+        len
+    }
 }
@@ -1,5 +1,6 @@
 use crate::internal::consts;
 
+#[derive(Copy, Clone)]
 #[cfg_attr(test, derive(Eq, PartialEq, Debug))]
 pub(crate) struct NodeId {
     index: usize,
@@ -22,6 +23,11 @@ impl NodeId {
         self.level
     }
 
+    /// The index of the first element that this node covers.
+    pub(crate) fn first_index(&self) -> usize {
+        self.index + 1 - (1 << self.level)
+    }
+
     pub(crate) fn left_child(&self) -> NodeId {
         debug_assert!(self.level > 0);
 
@@ -126,6 +132,11 @@ pub(crate) fn get_nodes_len_for(len: usize) -> usize {
     len * 2 - len.count_ones() as usize
 }
 
+/// Checked version of [`get_nodes_len_for`], which returns `None` on overflow.
+pub(crate) fn checked_get_nodes_len_for(len: usize) -> Option<usize> {
+    len.checked_mul(2)?.checked_sub(len.count_ones() as usize)
+}
+
 /// How many adjacent parent nodes are following after the leaf node for the `index`.
 ///
 /// `get_max_level_from_index(2^n - 1) == n` will hold.
@@ -235,4 +246,21 @@ mod tests {
         assert_eq!(get(7), 3);
         assert_eq!(get(8), 0);
     }
+
+    #[test]
+    fn test_max_len_for() {
+        fn check<T>() {
+            let max_nodes_len = isize::MAX as usize / size_of::<T>();
+            let max_len = consts::max_len_for::<T>();
+            assert!(get_nodes_len_for(max_len) <= max_nodes_len);
+            assert!(checked_get_nodes_len_for(max_len + 1).unwrap() > max_nodes_len);
+        }
+
+        check::<u8>();
+        check::<u16>();
+        check::<u32>();
+        check::<u64>();
+        check::<[u64; 3]>();
+        assert_eq!(consts::max_len_for::<()>(), consts::MAX_LEN);
+    }
 }
@@ -3,26 +3,22 @@
 //! Internal nodes become dirty when you modify the value. ("DIRTY:" tag)
 //! They need to be cleaned by recalculation or truncate. ("CLEAN:" tag)
 
-use crate::PostfixSegmentTree;
+#[cfg(feature = "allocator_api")]
+use std::alloc::Allocator;
+
+use crate::aggregate::Combine;
 use crate::internal::consts;
 use crate::internal::node_id::{LeafNodeId, NodeId, get_nodes_len_for};
-use std::ops::AddAssign;
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::tree_in;
 
 // internal operations: node access
-impl<T> PostfixSegmentTree<T> {
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A) {
     pub(crate) fn get_node(&self, id: NodeId) -> &T {
         let node_index = id.node_index();
         &self.nodes[node_index]
     }
 
-    /// DIRTY: parents of `id`, when you arbitrarily modify the value of the returned reference
-    pub(crate) fn get_node_mut(&mut self, id: NodeId) -> &mut T {
-        debug_assert!(id.index() < self.len());
-
-        let node_index = id.node_index();
-        &mut self.nodes[node_index]
-    }
-
     pub(crate) fn get_leaf_node(&self, id: LeafNodeId) -> &T {
         debug_assert!(id.index() < self.len());
 
@@ -49,57 +45,68 @@ impl<T> PostfixSegmentTree<T> {
     }
 }
 
-// internal operations: push and pop
-impl<T> PostfixSegmentTree<T>
-where
-    T: Default,
-{
-    /// Push a new default element and its empty parent nodes.
-    ///
-    /// `nodes` are need to be recalculated appropriately after inserting a new element.
+// internal operations: pop
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A) {
+    /// Pop the last leaf node, and truncate nodes
     ///
     /// # Time complexity
     ///
-    /// *O*([`len`])
-    ///
-    /// [`len`]: PostfixSegmentTree::len
-    ///
-    /// DIRTY: parents of `len() - 1`
-    pub(crate) fn push_default_dirty(&mut self) -> LeafNodeId {
-        debug_assert!(self.len() <= consts::MAX_LEN - 1);
+    /// *O*(1)
+    pub(crate) fn pop_leaf(&mut self) -> T {
+        debug_assert!(!self.is_empty());
 
-        let len = self.len();
-        let nodes_len = get_nodes_len_for(len + 1);
-        debug_assert!(nodes_len > self.nodes_len());
+        let id = LeafNodeId::new(self.len() - 1);
 
-        self.nodes.resize_with(nodes_len, T::default);
-        self.len += 1;
+        // parents of the last leaf node are after it
+        self.nodes.truncate(id.node_index() + 1);
+        self.len -= 1;
 
-        LeafNodeId::new(self.len - 1)
+        self.nodes.pop().expect("the last leaf node")
     }
+}
 
-    /// Pop the last leaf node, and truncate nodes
+// internal operations: push
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A)
+where
+    T: Combine + Clone,
+{
+    /// Push a new element and its parent nodes.
+    ///
+    /// Parent nodes are calculated while they're pushed, so no dummy value is required for them.
     ///
     /// # Time complexity
     ///
-    /// *O*(1)
-    pub(crate) fn pop(&mut self) -> T {
-        debug_assert!(self.len() > 0);
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: crate::PostfixSegmentTree::len
+    ///
+    /// CLEAN: parents of `len() - 1`
+    pub(crate) fn push_leaf(&mut self, element: T) -> LeafNodeId {
+        debug_assert!(self.len() < consts::MAX_LEN);
 
-        let len = self.len();
+        let id = LeafNodeId::new(self.len());
+        debug_assert_eq!(id.node_index(), self.nodes_len());
 
-        let mut popped = T::default();
-        let last_node = self.get_leaf_node_mut(LeafNodeId::new(len - 1));
-        std::mem::swap(&mut popped, last_node);
+        self.nodes.push(element);
+        self.len += 1;
+
+        // starts from 1 since the leaf node is pushed already
+        for level in 1..=id.max_level() {
+            let node_id = id.with_level(level);
+            debug_assert_eq!(node_id.node_index(), self.nodes_len());
 
-        self.truncate(len - 1);
+            let mut sum = self.get_node(node_id.left_child()).clone();
+            sum.combine(self.get_node(node_id.right_child()));
+            self.nodes.push(sum);
+        }
 
-        popped
+        debug_assert_eq!(self.nodes_len(), get_nodes_len_for(self.len()));
+        id
     }
 }
 
 // internal operations: rotate
-impl<T> PostfixSegmentTree<T> {
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A) {
     /// Rotates all elements from `id` to the right by 1 to insert a new element.
     ///
     /// `elements[id]` at the end of this operation will be the last element before this operation as a result.
@@ -108,7 +115,7 @@ impl<T> PostfixSegmentTree<T> {
     ///
     /// *O*([`len`])
     ///
-    /// [`len`]: PostfixSegmentTree::len
+    /// [`len`]: crate::PostfixSegmentTree::len
     ///
     /// DIRTY: all parents of `node_id.index() >= id`
     pub(crate) fn rotate_leaf_nodes_right_by_one_dirty(&mut self, id: LeafNodeId) {
@@ -133,7 +140,7 @@ impl<T> PostfixSegmentTree<T> {
     ///
     /// *O*([`len`])
     ///
-    /// [`len`]: PostfixSegmentTree::len
+    /// [`len`]: crate::PostfixSegmentTree::len
     ///
     /// DIRTY: all parents of `node_id.index() >= id`
     pub(crate) fn rotate_leaf_nodes_left_by_one_dirty(&mut self, id: LeafNodeId) {
@@ -149,12 +156,34 @@ impl<T> PostfixSegmentTree<T> {
             self.swap_leaf_nodes(left, right); // use swap to not require Copy/Clone
         }
     }
+
+    /// Reverses the order of elements in `start..end`.
+    ///
+    /// Rotations by arbitrary amounts can be composed of three reversals.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(`end - start`)
+    ///
+    /// DIRTY: all parents of `start <= node_id.index() < end`
+    pub(crate) fn reverse_leaf_nodes_dirty(&mut self, start: usize, end: usize) {
+        debug_assert!(start <= end);
+        debug_assert!(end <= self.len());
+
+        let mut left = start;
+        let mut right = end;
+        while left + 1 < right {
+            right -= 1;
+            self.swap_leaf_nodes(LeafNodeId::new(left), LeafNodeId::new(right));
+            left += 1;
+        }
+    }
 }
 
 // internal operations: recalculate
-impl<T> PostfixSegmentTree<T>
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A)
 where
-    for<'a> T: AddAssign<&'a T> + Default,
+    T: Combine + Clone,
 {
     /// Recalculate internal nodes after updating an element at `id.index()`
     ///
@@ -162,26 +191,14 @@ where
     ///
     /// *O*(log [`nodes_len`])
     ///
-    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    /// [`nodes_len`]: crate::PostfixSegmentTree::nodes_len
     ///
     /// CLEAN: parents of `id`
     pub(crate) fn recalculate_nodes_after_update(&mut self, id: LeafNodeId) {
         debug_assert!(id.index() < self.len());
 
-        let mut current_index = id.index();
-        let mut current_level = 1; // starts from 1 since leaf nodes are always CLEAN
-        let len = self.len();
-        while current_index < len {
-            let leaf_node_id = LeafNodeId::new(current_index);
-            let max_level = leaf_node_id.max_level();
-            while current_level <= max_level {
-                let node_id = leaf_node_id.with_level(current_level);
-                self.recalculate_node(node_id);
-
-                current_level += 1;
-            }
-
-            current_index += 1 << (current_level - 1);
+        for node_id in ParentsIterator::new(id, self.len()) {
+            self.recalculate_node(node_id);
         }
     }
 
@@ -193,7 +210,7 @@ where
     ///
     /// *O*([`nodes_len`])
     ///
-    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    /// [`nodes_len`]: crate::PostfixSegmentTree::nodes_len
     ///
     /// CLEAN: all parents of `node_id.index() >= id.index()`
     pub(crate) fn recalculate_nodes_after_bulk_update(&mut self, id: LeafNodeId) {
@@ -212,20 +229,49 @@ where
         }
     }
 
+    /// Recalculate internal nodes after updating `elements[start..end]`
+    ///
+    /// It updates parents that end in the range, then ancestors of the last element that end after it.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(`end - start` + log [`nodes_len`])
+    ///
+    /// [`nodes_len`]: crate::PostfixSegmentTree::nodes_len
+    ///
+    /// CLEAN: parents of `start..end`
+    pub(crate) fn recalculate_nodes_after_range_update(&mut self, start: usize, end: usize) {
+        debug_assert!(start < end);
+        debug_assert!(end <= self.len());
+
+        for i in start..end {
+            let leaf_node_id = LeafNodeId::new(i);
+            for level in 1..=leaf_node_id.max_level() {
+                self.recalculate_node(leaf_node_id.with_level(level));
+            }
+        }
+        for node_id in ParentsIterator::new(LeafNodeId::new(end - 1), self.len()) {
+            if node_id.index() >= end {
+                self.recalculate_node(node_id);
+            }
+        }
+    }
+
     /// Recalculate a node at `id` using their children.
     ///
     /// CLEAN: `id`
-    fn recalculate_node(&mut self, id: NodeId) {
+    pub(crate) fn recalculate_node(&mut self, id: NodeId) {
         debug_assert!(id.index() < self.len());
         debug_assert!(id.level() >= 1);
 
-        let mut sum = T::default();
-
         // child.index() <= id.index()
         // child.level() == id.level() - 1
-        sum += self.get_node(id.left_child());
-        sum += self.get_node(id.right_child());
+        // so children are always before the parent in `nodes`.
+        let (children, rest) = self.nodes.split_at_mut(id.node_index());
+        let left = &children[id.left_child().node_index()];
+        let right = &children[id.right_child().node_index()];
 
-        *self.get_node_mut(id) = sum;
+        // reuses the resources of the parent node in place
+        rest[0].combine_from(left, right);
     }
 }
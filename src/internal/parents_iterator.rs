@@ -0,0 +1,70 @@
+use crate::internal::node_id::{LeafNodeId, NodeId};
+
+/// Iterates over parent nodes that cover a leaf node, from the lowest level to the highest.
+///
+/// The parents include ones that will cover the leaf node only after more elements are pushed,
+/// which are bounded by `len`.
+pub(crate) struct ParentsIterator {
+    index: usize,
+    level: u32,
+    len: usize,
+}
+
+impl ParentsIterator {
+    pub(crate) fn new(id: LeafNodeId, len: usize) -> Self {
+        debug_assert!(id.index() < len);
+
+        Self {
+            index: id.index(),
+            level: 1, // starts from 1 since leaf nodes are not parents
+            len,
+        }
+    }
+}
+
+impl Iterator for ParentsIterator {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.len {
+            let leaf_node_id = LeafNodeId::new(self.index);
+            if self.level <= leaf_node_id.max_level() {
+                let node_id = leaf_node_id.with_level(self.level);
+                self.level += 1;
+                return Some(node_id);
+            }
+
+            // jump to the right sibling of the current subtree, which has a higher root.
+            self.index += 1 << (self.level - 1);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(index: usize, level: u32) -> NodeId {
+        NodeId::new(index, level)
+    }
+
+    #[test]
+    fn test_parents_iterator() {
+        fn iter(index: usize, len: usize) -> Vec<NodeId> {
+            ParentsIterator::new(LeafNodeId::new(index), len).collect()
+        }
+
+        assert_eq!(iter(0, 1), vec![]);
+        assert_eq!(iter(0, 2), vec![id(1, 1)]);
+        assert_eq!(iter(1, 2), vec![id(1, 1)]);
+        assert_eq!(iter(0, 3), vec![id(1, 1)]);
+        assert_eq!(iter(2, 3), vec![]);
+        assert_eq!(iter(0, 4), vec![id(1, 1), id(3, 2)]);
+        assert_eq!(iter(2, 4), vec![id(3, 1), id(3, 2)]);
+        assert_eq!(iter(4, 8), vec![id(5, 1), id(7, 2), id(7, 3)]);
+        assert_eq!(iter(6, 8), vec![id(7, 1), id(7, 2), id(7, 3)]);
+        assert_eq!(iter(6, 7), vec![]);
+    }
+}
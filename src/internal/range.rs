@@ -0,0 +1,28 @@
+use std::ops::{Bound, Range, RangeBounds};
+
+/// Converts `range` into `start..end` that is bounded by `len`.
+///
+/// Panics if the range is out of bounds or `start > end`, like slice indexing.
+pub(crate) fn to_range(range: impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflow"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflow"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    assert!(
+        start <= end,
+        "range start {start} is greater than end {end}"
+    );
+    assert!(
+        end <= len,
+        "range end {end} is out of bounds for length {len}"
+    );
+
+    start..end
+}
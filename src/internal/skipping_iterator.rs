@@ -77,6 +77,17 @@ fn max_reachable_elements_for_current_index(index: usize) -> usize {
     index + ((1 << index.trailing_zeros() as usize) - 1)
 }
 
+/// Iterates nodes that cover `index..end` exactly, in left-to-right order.
+///
+/// Nodes in `index..pivot` are increasing, and nodes in `pivot..end` are decreasing in their widths.
+pub(crate) fn range_iterator(index: usize, end: usize) -> impl Iterator<Item = NodeId> {
+    debug_assert!(index <= end);
+
+    let mut iter = SkippingIterator::new(end);
+    let pivot = iter.skip_to_pivot(index);
+    IncreasingSkippingIterator::new(index, pivot).chain(iter)
+}
+
 pub(crate) struct IncreasingSkippingIterator {
     index: usize,
     end: usize,
@@ -200,11 +211,11 @@ mod tests {
     fn test_skipping_iterator_levels_monotonically_decreasing() {
         fn get_first_non_monotonically_decreasing(iter: &mut SkippingIterator) -> Option<NodeId> {
             let mut prev_level = None;
-            while let Some(node_id) = iter.next() {
-                if let Some(prev_level) = prev_level {
-                    if node_id.level() >= prev_level {
-                        return Some(node_id);
-                    }
+            for node_id in iter {
+                if let Some(prev_level) = prev_level
+                    && node_id.level() >= prev_level
+                {
+                    return Some(node_id);
                 }
 
                 prev_level = Some(node_id.level());
@@ -392,11 +403,11 @@ mod tests {
             iter: &mut IncreasingSkippingIterator,
         ) -> Option<NodeId> {
             let mut prev_level = None;
-            while let Some(node_id) = iter.next() {
-                if let Some(prev_level) = prev_level {
-                    if node_id.level() <= prev_level {
-                        return Some(node_id);
-                    }
+            for node_id in iter {
+                if let Some(prev_level) = prev_level
+                    && node_id.level() <= prev_level
+                {
+                    return Some(node_id);
                 }
 
                 prev_level = Some(node_id.level());
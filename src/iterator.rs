@@ -1,15 +1,87 @@
 use std::iter::FusedIterator;
+use std::ops::{RangeBounds, Sub};
 
 use crate::PostfixSegmentTree;
+use crate::internal::node_id::LeafNodeId;
+use crate::internal::range::to_range;
 
 impl<T> PostfixSegmentTree<T> {
     /// Returns an [`ElementIterator`], which is an iterator for elements on this tree.
     pub fn iter(&self) -> ElementIterator<'_, T> {
         ElementIterator::new(self, 0, self.len())
     }
+
+    /// Returns an [`ElementIterator`] for elements in `range`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// assert_eq!(tree.iter_range(1..3).copied().collect::<Vec<_>>(), vec![2, 3]);
+    /// assert_eq!(tree.iter_range(3..).copied().collect::<Vec<_>>(), vec![4, 5]);
+    /// ```
+    pub fn iter_range(&self, range: impl RangeBounds<usize>) -> ElementIterator<'_, T> {
+        let range = to_range(range, self.len());
+        ElementIterator::new(self, range.start, range.end)
+    }
+
+    /// Returns an iterator over all contiguous windows of length `size`.
+    /// Each window is an [`ElementIterator`] over the elements in it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// let windows: Vec<Vec<i32>> = tree.windows(3).map(|w| w.copied().collect()).collect();
+    /// assert_eq!(windows, vec![vec![1, 2, 3], vec![2, 3, 4]]);
+    /// ```
+    pub fn windows(&self, size: usize) -> Windows<'_, T> {
+        assert!(size != 0);
+
+        Windows {
+            tree: self,
+            index: 0,
+            size,
+        }
+    }
+
+    /// Returns an iterator over `size` elements at a time, starting at the beginning.
+    /// Each chunk is an [`ElementIterator`] over the elements in it, and the last chunk may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// let chunks: Vec<Vec<i32>> = tree.chunks(2).map(|c| c.copied().collect()).collect();
+    /// assert_eq!(chunks, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    /// ```
+    pub fn chunks(&self, size: usize) -> Chunks<'_, T> {
+        assert!(size != 0);
+
+        Chunks {
+            tree: self,
+            index: 0,
+            size,
+        }
+    }
 }
 
 /// Iterator for elements on [`PostfixSegmentTree`].
+#[derive(Clone)]
 pub struct ElementIterator<'a, T> {
     tree: &'a PostfixSegmentTree<T>,
     index: usize,
@@ -26,14 +98,12 @@ impl<'a, T> Iterator for ElementIterator<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index + 1 >= self.end {
+        if self.index >= self.end {
             return None;
         }
 
         let value = self.tree.get(self.index);
-        if self.index < self.end {
-            self.index += 1;
-        }
+        self.index += 1;
 
         value
     }
@@ -57,16 +127,13 @@ impl<'a, T> Iterator for ElementIterator<'a, T> {
     }
 
     fn nth(&mut self, n: usize) -> Option<Self::Item> {
-        if self.index + n + 1 >= self.end {
+        if n >= self.end - self.index {
+            self.index = self.end;
             return None;
         }
 
-        let value = self.tree.get(self.index);
-        if self.index < self.end {
-            self.index += 1;
-        }
-
-        value
+        self.index += n;
+        self.next()
     }
 }
 
@@ -81,7 +148,7 @@ impl<'a, T> DoubleEndedIterator for ElementIterator<'a, T> {
         }
 
         let value = self.tree.get(self.end - 1);
-        if self.end >= self.index + 1 {
+        if self.end > self.index {
             self.end -= 1;
         }
 
@@ -89,15 +156,311 @@ impl<'a, T> DoubleEndedIterator for ElementIterator<'a, T> {
     }
 
     fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
-        if self.index + n >= self.end {
+        if n >= self.end - self.index {
+            self.end = self.index;
             return None;
         }
 
         let value = self.tree.get(self.end - n - 1);
-        if self.end >= self.index + n + 1 {
+        if self.end > self.index + n {
             self.end -= n + 1;
         }
 
         value
     }
 }
+
+/// Iterator over overlapping windows of elements on [`PostfixSegmentTree`].
+///
+/// See [`PostfixSegmentTree::windows`].
+#[derive(Clone)]
+pub struct Windows<'a, T> {
+    tree: &'a PostfixSegmentTree<T>,
+    index: usize,
+    size: usize,
+}
+
+impl<'a, T> Iterator for Windows<'a, T> {
+    type Item = ElementIterator<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index + self.size > self.tree.len() {
+            return None;
+        }
+
+        let window = ElementIterator::new(self.tree, self.index, self.index + self.size);
+        self.index += 1;
+
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.tree.len() + 1).saturating_sub(self.index + self.size);
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> FusedIterator for Windows<'a, T> {}
+
+impl<'a, T> ExactSizeIterator for Windows<'a, T> {}
+
+/// Iterator over non-overlapping chunks of elements on [`PostfixSegmentTree`].
+///
+/// See [`PostfixSegmentTree::chunks`].
+#[derive(Clone)]
+pub struct Chunks<'a, T> {
+    tree: &'a PostfixSegmentTree<T>,
+    index: usize,
+    size: usize,
+}
+
+impl<'a, T> Iterator for Chunks<'a, T> {
+    type Item = ElementIterator<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.tree.len() {
+            return None;
+        }
+
+        let end = usize::min(self.index + self.size, self.tree.len());
+        let chunk = ElementIterator::new(self.tree, self.index, end);
+        self.index = end;
+
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.tree.len() - self.index).div_ceil(self.size);
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> FusedIterator for Chunks<'a, T> {}
+
+impl<'a, T> ExactSizeIterator for Chunks<'a, T> {}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Sub<Output = T> + Clone,
+{
+    /// Returns a [`Differences`], which is an iterator for `elements[index + 1] - elements[index]` of adjacent elements.
+    ///
+    /// It yields [`len`]` - 1` differences, or nothing if the tree has less than 2 elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// // cumulative measurements
+    /// let tree = PostfixSegmentTree::from_iter([10, 12, 17, 17]);
+    /// assert_eq!(tree.differences().collect::<Vec<_>>(), vec![2, 5, 0]);
+    /// ```
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn differences(&self) -> Differences<'_, T> {
+        let mut elements = self.iter();
+        let previous = elements.next();
+        Differences { elements, previous }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a PostfixSegmentTree<T> {
+    type Item = &'a T;
+    type IntoIter = ElementIterator<'a, T>;
+
+    /// Returns an [`ElementIterator`], which is the same as [`PostfixSegmentTree::iter`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// let mut sum = 0;
+    /// for element in &tree {
+    ///     sum += element;
+    /// }
+    /// assert_eq!(sum, 6);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator for differences of adjacent elements on [`PostfixSegmentTree`].
+///
+/// See [`PostfixSegmentTree::differences`].
+#[derive(Clone)]
+pub struct Differences<'a, T> {
+    elements: ElementIterator<'a, T>,
+    previous: Option<&'a T>,
+}
+
+impl<T> Iterator for Differences<'_, T>
+where
+    T: Sub<Output = T> + Clone,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let previous = self.previous?;
+        let element = self.elements.next()?;
+        self.previous = Some(element);
+
+        Some(element.clone() - previous.clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.elements.size_hint()
+    }
+}
+
+impl<T> FusedIterator for Differences<'_, T> where T: Sub<Output = T> + Clone {}
+
+impl<T> ExactSizeIterator for Differences<'_, T> where T: Sub<Output = T> + Clone {}
+
+impl<T> IntoIterator for PostfixSegmentTree<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    /// Returns an [`IntoIter`], which moves elements out of this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// let mut elements = Vec::new();
+    /// for element in tree {
+    ///     elements.push(element);
+    /// }
+    /// assert_eq!(elements, vec![1, 2, 3]);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            nodes: self.nodes.into_iter(),
+            index: 0,
+            end: self.len,
+        }
+    }
+}
+
+/// Iterator that moves elements out of [`PostfixSegmentTree`].
+///
+/// Parent nodes are dropped while iterating.
+///
+/// See [`PostfixSegmentTree::into_iter`].
+pub struct IntoIter<T> {
+    // nodes from the leaf node of `index`, until the last parent node of `end - 1`
+    nodes: std::vec::IntoIter<T>,
+    index: usize,
+    end: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.end {
+            return None;
+        }
+
+        let element = self.nodes.next();
+
+        // skip parent nodes that follow the leaf node
+        for _ in 0..LeafNodeId::new(self.index).max_level() {
+            self.nodes.next();
+        }
+        self.index += 1;
+
+        element
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.index >= self.end {
+            return None;
+        }
+
+        // skip parent nodes that follow the leaf node
+        self.end -= 1;
+        for _ in 0..LeafNodeId::new(self.end).max_level() {
+            self.nodes.next_back();
+        }
+
+        self.nodes.next_back()
+    }
+}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_iterator() {
+        const N: u32 = 10;
+        for len in 0..N {
+            let model: Vec<u32> = (0..len).collect();
+            let tree = PostfixSegmentTree::from_iter(model.clone());
+            assert!(tree.iter().eq(model.iter()));
+            assert!(tree.iter().rev().eq(model.iter().rev()));
+            assert_eq!(tree.iter().last(), model.iter().last());
+
+            for front in 0..=len as usize {
+                let ns = (0..=len as usize + 1).chain([usize::MAX - 1, usize::MAX]);
+                for n in ns {
+                    let mut iter = tree.iter();
+                    let mut expected = model.iter();
+                    assert_eq!(iter.nth(front), expected.nth(front));
+                    assert_eq!(iter.nth_back(n), expected.nth_back(n));
+                    assert_eq!(iter.len(), expected.len());
+                    assert_eq!(iter.nth(n), expected.nth(n));
+                    assert!(iter.eq(expected));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_into_iter() {
+        const N: u32 = 20;
+        for len in 0..N {
+            for front in 0..=len as usize {
+                let model: Vec<u32> = (0..len).collect();
+                let tree = PostfixSegmentTree::from_iter(model.clone());
+
+                let mut iter = tree.into_iter();
+                let mut expected = model.into_iter();
+                assert!(iter.by_ref().take(front).eq(expected.by_ref().take(front)));
+                assert_eq!(iter.len(), expected.len());
+                assert!(iter.rev().eq(expected.rev()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_differences() {
+        const N: i32 = 10;
+        for len in 0..N {
+            let model: Vec<i32> = (0..len).map(|i| i * i - 3 * i).collect();
+            let tree = PostfixSegmentTree::from_iter(model.clone());
+
+            let expected: Vec<i32> = model.windows(2).map(|w| w[1] - w[0]).collect();
+            let differences = tree.differences();
+            assert_eq!(differences.len(), expected.len());
+            assert_eq!(differences.collect::<Vec<_>>(), expected);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+
+/// A mutation of [`PostfixSegmentTree`], which is recorded as the inverse of a mutation in the journal.
+enum Operation<T> {
+    Push(T),
+    Pop,
+    Update(usize, T),
+    Insert(usize, T),
+    Remove(usize),
+}
+
+impl<T> Operation<T>
+where
+    T: Combine + Clone,
+{
+    /// Applies the operation to `tree`, and returns the inverse of it.
+    fn apply(self, tree: &mut PostfixSegmentTree<T>) -> Self {
+        match self {
+            Operation::Push(element) => {
+                tree.push(element);
+                Operation::Pop
+            }
+            Operation::Pop => Operation::Push(tree.pop().expect("journal is out of sync")),
+            Operation::Update(index, element) => {
+                Operation::Update(index, tree.replace(index, element))
+            }
+            Operation::Insert(index, element) => {
+                tree.insert(index, element);
+                Operation::Remove(index)
+            }
+            Operation::Remove(index) => Operation::Insert(index, tree.remove(index)),
+        }
+    }
+}
+
+/// A wrapper of [`PostfixSegmentTree`] that journals mutations for undo and redo.
+///
+/// Every mutation through the wrapper records its inverse operation, such as the old element of an update,
+/// rather than a snapshot of the tree. So undoing or redoing a mutation costs the same as the mutation.
+/// A new mutation clears the redo history, like editors.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{JournaledPostfixSegmentTree, PostfixSegmentTree};
+///
+/// let mut tree = JournaledPostfixSegmentTree::new(PostfixSegmentTree::from_iter([1, 2, 3]));
+/// tree.update(0, 10);
+/// tree.push(4);
+/// tree.remove(1);
+/// assert_eq!(tree.tree().prefix_sum(3), 17);
+///
+/// assert!(tree.undo());
+/// assert!(tree.undo());
+/// assert_eq!(tree.tree().iter().collect::<Vec<_>>(), vec![&10, &2, &3]);
+///
+/// assert!(tree.redo());
+/// assert_eq!(tree.tree().prefix_sum(4), 19);
+///
+/// tree.insert(0, 0);
+/// assert!(!tree.redo());
+/// ```
+pub struct JournaledPostfixSegmentTree<T> {
+    tree: PostfixSegmentTree<T>,
+    undo: Vec<Operation<T>>,
+    redo: Vec<Operation<T>>,
+}
+
+impl<T> JournaledPostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Wraps `tree` with empty history.
+    pub fn new(tree: PostfixSegmentTree<T>) -> Self {
+        Self {
+            tree,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying tree.
+    pub fn tree(&self) -> &PostfixSegmentTree<T> {
+        &self.tree
+    }
+
+    pub fn into_inner(self) -> PostfixSegmentTree<T> {
+        self.tree
+    }
+
+    /// Returns the number of mutations that can be undone.
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Returns the number of mutations that can be redone.
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Forgets the history, so the current state can't be undone.
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// [`PostfixSegmentTree::push`] that can be undone.
+    pub fn push(&mut self, element: T) {
+        self.record(Operation::Push(element));
+    }
+
+    /// [`PostfixSegmentTree::pop`] that can be undone.
+    pub fn pop(&mut self) -> Option<T> {
+        let element = self.tree.pop()?;
+        self.redo.clear();
+        self.undo.push(Operation::Push(element.clone()));
+        Some(element)
+    }
+
+    /// [`PostfixSegmentTree::update`] that can be undone.
+    pub fn update(&mut self, index: usize, element: T) {
+        self.record(Operation::Update(index, element));
+    }
+
+    /// [`PostfixSegmentTree::insert`] that can be undone.
+    pub fn insert(&mut self, index: usize, element: T) {
+        self.record(Operation::Insert(index, element));
+    }
+
+    /// [`PostfixSegmentTree::remove`] that can be undone.
+    pub fn remove(&mut self, index: usize) -> T {
+        let element = self.tree.remove(index);
+        self.redo.clear();
+        self.undo.push(Operation::Insert(index, element.clone()));
+        element
+    }
+
+    /// Undoes the last mutation. Returns `false` if there's nothing to undo.
+    ///
+    /// # Time complexity
+    ///
+    /// Same as the mutation
+    pub fn undo(&mut self) -> bool {
+        let Some(operation) = self.undo.pop() else {
+            return false;
+        };
+
+        self.redo.push(operation.apply(&mut self.tree));
+        true
+    }
+
+    /// Redoes the last undone mutation. Returns `false` if there's nothing to redo.
+    ///
+    /// # Time complexity
+    ///
+    /// Same as the mutation
+    pub fn redo(&mut self) -> bool {
+        let Some(operation) = self.redo.pop() else {
+            return false;
+        };
+
+        self.undo.push(operation.apply(&mut self.tree));
+        true
+    }
+
+    fn record(&mut self, operation: Operation<T>) {
+        let inverse = operation.apply(&mut self.tree);
+        self.redo.clear();
+        self.undo.push(inverse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo_restores_states() {
+        let mut tree = JournaledPostfixSegmentTree::new(PostfixSegmentTree::from_iter(0..5i64));
+        let mut states: Vec<Vec<i64>> = vec![tree.tree().iter().copied().collect()];
+        for step in 0..40i64 {
+            let len = tree.tree().len();
+            match step % 5 {
+                0 => tree.push(step),
+                1 => tree.update((step as usize * 7) % len, step),
+                2 => tree.insert((step as usize * 3) % (len + 1), step),
+                3 => {
+                    tree.remove((step as usize * 5) % len);
+                }
+                _ => {
+                    tree.pop();
+                }
+            }
+            states.push(tree.tree().iter().copied().collect());
+        }
+
+        let check = |tree: &JournaledPostfixSegmentTree<i64>, state: &[i64]| {
+            assert!(tree.tree().iter().eq(state.iter()));
+            assert_eq!(tree.tree().prefix_sum(state.len()), state.iter().sum());
+        };
+        for state in states.iter().rev().skip(1) {
+            assert!(tree.undo());
+            check(&tree, state);
+        }
+        assert!(!tree.undo());
+        for state in states.iter().skip(1) {
+            assert!(tree.redo());
+            check(&tree, state);
+        }
+        assert!(!tree.redo());
+    }
+}
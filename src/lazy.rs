@@ -0,0 +1,268 @@
+use std::ops::{Range, RangeBounds};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+use crate::internal::node_id::{LeafNodeId, NodeId};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::range::to_range;
+use crate::internal::skipping_iterator::SkippingIterator;
+
+/// Repeated [`Combine::combine`] of a value, which is required to apply a range update to a node at once.
+///
+/// # Laws
+///
+/// * `a.scale(n) == a ⊕ a ⊕ ... ⊕ a` (`n` times), and `a.scale(0) == identity()`
+///
+/// [`Combine::combine`]: crate::Combine::combine
+pub trait Scale {
+    fn scale(&self, count: usize) -> Self;
+}
+
+macro_rules! impl_scale {
+    ($($ty:ty),*) => {
+        $(
+            impl Scale for $ty {
+                fn scale(&self, count: usize) -> Self {
+                    *self * count as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_scale!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+/// A variant of [`PostfixSegmentTree`] with lazy propagation, which supports range additions.
+///
+/// Each node has a lazy tag in a parallel array, which is a pending addition to every element the node covers.
+/// Tags are never pushed down. Instead, tags of ancestors are accumulated while descending on queries.
+/// So both [`range_add`] and [`sum`] are *O*(log [`len`]).
+///
+/// There is no `insert` or `remove`, because a tag applies to a fixed range of indices.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::LazyPostfixSegmentTree;
+///
+/// let mut tree = LazyPostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+/// tree.range_add(1..4, 10);
+/// assert_eq!(tree.get(0), 1);
+/// assert_eq!(tree.get(2), 13);
+/// assert_eq!(tree.sum(0, 5), 45);
+/// assert_eq!(tree.sum(3, 2), 19);
+/// ```
+///
+/// [`range_add`]: LazyPostfixSegmentTree::range_add
+/// [`sum`]: LazyPostfixSegmentTree::sum
+/// [`len`]: LazyPostfixSegmentTree::len
+pub struct LazyPostfixSegmentTree<T> {
+    // sums of nodes, which don't include tags of their ancestors
+    sums: PostfixSegmentTree<T>,
+    // tags of nodes, indexed by the node index
+    tags: Vec<T>,
+}
+
+impl<T> LazyPostfixSegmentTree<T>
+where
+    T: Aggregate + Scale + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            sums: PostfixSegmentTree::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.sums.len()
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.sums.is_empty()
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, element: T) {
+        // new parent nodes have no tags, so they're the sums of their children.
+        self.sums.push(element);
+        self.tags.resize_with(self.sums.nodes_len(), T::identity);
+    }
+
+    /// Returns an element at `index`, including pending additions.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: LazyPostfixSegmentTree::len
+    pub fn get(&self, index: usize) -> T {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        let mut element = self.sums.get_leaf_node(id).clone();
+        for parent in ParentsIterator::new(id, self.len()) {
+            element.combine(&self.tags[parent.node_index()]);
+        }
+
+        element
+    }
+
+    /// Adds `delta` to every element in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: LazyPostfixSegmentTree::len
+    pub fn range_add(&mut self, range: impl RangeBounds<usize>, delta: T) {
+        let range = to_range(range, self.len());
+        if range.is_empty() {
+            return;
+        }
+
+        for root in SkippingIterator::new(self.len()) {
+            self.add_node(root, &range, &delta);
+        }
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`, including pending additions.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: LazyPostfixSegmentTree::len
+    pub fn prefix_sum(&self, index: usize) -> T {
+        self.sum(0, index)
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`, including pending additions.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: LazyPostfixSegmentTree::len
+    pub fn sum(&self, index: usize, len: usize) -> T {
+        assert!(index <= self.len());
+        assert!(len <= self.len() - index);
+
+        let range = index..index + len;
+        let mut sum = T::identity();
+        for root in SkippingIterator::new(self.len()) {
+            self.sum_node(root, &range, &T::identity(), &mut sum);
+        }
+
+        sum
+    }
+
+    fn add_node(&mut self, id: NodeId, range: &Range<usize>, delta: &T) {
+        let covered = id.first_index()..id.index() + 1;
+        if range.end <= covered.start || covered.end <= range.start {
+            return;
+        }
+
+        let node_index = id.node_index();
+        if range.start <= covered.start && covered.end <= range.end {
+            self.tags[node_index].combine(delta);
+            self.sums.nodes[node_index].combine(&delta.scale(covered.len()));
+            return;
+        }
+
+        // partially covered nodes are never leaf nodes
+        self.add_node(id.left_child(), range, delta);
+        self.add_node(id.right_child(), range, delta);
+
+        let mut sum = self.sums.get_node(id.left_child()).clone();
+        sum.combine(self.sums.get_node(id.right_child()));
+        sum.combine(&self.tags[node_index].scale(covered.len()));
+        self.sums.nodes[node_index] = sum;
+    }
+
+    /// Combines the sum of `range` in the subtree of `id` into `sum`,
+    /// where `pending` is the sum of tags of the ancestors of `id`.
+    fn sum_node(&self, id: NodeId, range: &Range<usize>, pending: &T, sum: &mut T) {
+        let covered = id.first_index()..id.index() + 1;
+        if range.end <= covered.start || covered.end <= range.start {
+            return;
+        }
+
+        if range.start <= covered.start && covered.end <= range.end {
+            sum.combine(self.sums.get_node(id));
+            sum.combine(&pending.scale(covered.len()));
+            return;
+        }
+
+        let mut pending = pending.clone();
+        pending.combine(&self.tags[id.node_index()]);
+        self.sum_node(id.left_child(), range, &pending, sum);
+        self.sum_node(id.right_child(), range, &pending, sum);
+    }
+}
+
+impl<T> Default for LazyPostfixSegmentTree<T>
+where
+    T: Aggregate + Scale + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for LazyPostfixSegmentTree<T>
+where
+    T: Aggregate + Scale + Clone,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let sums = PostfixSegmentTree::from_iter(iter);
+        let tags = (0..sums.nodes_len()).map(|_| T::identity()).collect();
+        Self { sums, tags }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lazy_matches_naive() {
+        let mut elements: Vec<i64> = (0..13).collect();
+        let mut tree = LazyPostfixSegmentTree::from_iter(elements.iter().copied());
+
+        for step in 0..60i64 {
+            if step % 7 == 0 {
+                tree.push(step);
+                elements.push(step);
+            }
+
+            let len = elements.len();
+            let start = (step as usize * 5) % len;
+            let end = start + (step as usize * 3) % (len - start + 1);
+            tree.range_add(start..end, step - 30);
+            for element in &mut elements[start..end] {
+                *element += step - 30;
+            }
+
+            for (index, element) in elements.iter().enumerate() {
+                assert_eq!(tree.get(index), *element);
+            }
+            for index in 0..=len {
+                for count in 0..=len - index {
+                    assert_eq!(
+                        tree.sum(index, count),
+                        elements[index..index + count].iter().sum()
+                    );
+                }
+            }
+        }
+    }
+}
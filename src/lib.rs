@@ -1,3 +1,4 @@
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 //! [`PostfixSegmentTree`] is a variant of Segment Tree that can calculate `push` in amortized *O*(1) time.
 //!
 //! # Overview and Comparision
@@ -34,18 +35,77 @@
 //! while *O*(n) is possible with specialized bulk implementation.
 //!
 //! And since it's based on a `Vec`:
-//! * `insert`: *O*(log [`len`])
-//! * `remove`: *O*(log [`len`])
+//! * `insert`: *O*([`len`])
+//! * `remove`: *O*([`len`])
 //!
 //! But unlike Segment Tree and Fenwick Tree, the implementation is relatively straightforward,
 //! since access is *O*(1) and doesn't need scary tree operations.
 //!
+//! # Aggregation
+//!
+//! "Sum" is not limited to addition. Elements can be any [`Aggregate`], which is an associative operation with an identity,
+//! such as min, max, product, and bitwise-or. Every `AddAssign<&T> + Default` type is an [`Aggregate`] of addition.
+//!
+//! Structural operations such as [`push`], [`update`], `insert`, and `remove` only require [`Combine`] and `Clone`,
+//! so elements without an identity or `Default` can be stored too. They can be queried with [`reduce`].
+//!
+//! # Overflow
+//!
+//! Integers overflow like `+=`, which panics in debug builds and wraps in release builds.
+//! Choose overflow semantics explicitly with [`std::num::Wrapping`] or [`std::num::Saturating`] elements,
+//! which are [`Aggregate`]s of addition without any wrapper of this crate,
+//! or detect overflow with [`checked_sum`] and [`checked_update`] of [`CheckedCombine`] elements.
+//!
+//! Saturating addition is associative only for unsigned integers.
+//! For signed integers, `(i8::MAX + 1) + -1` saturates to `126` while `i8::MAX + (1 + -1)` is `127`,
+//! so sums of `Saturating` signed integers depend on how nodes are grouped.
+//!
+//! ```
+//! use std::num::{Saturating, Wrapping};
+//!
+//! use postfix_segment_tree::PostfixSegmentTree;
+//!
+//! let tree = PostfixSegmentTree::from_iter([200u8, 100, 50].map(Wrapping));
+//! assert_eq!(tree.prefix_sum(3), Wrapping(94));
+//!
+//! let tree = PostfixSegmentTree::from_iter([200u8, 100, 50].map(Saturating));
+//! assert_eq!(tree.prefix_sum(3), Saturating(u8::MAX));
+//! assert_eq!(tree.sum(2, 1), Saturating(50));
+//! ```
+//!
+//! # Persistence
+//!
+//! Internal nodes are derived from elements, so it's enough to persist just the elements.
+//! With the `serde` feature, the tree implements `Serialize` and `Deserialize` as a sequence of its elements,
+//! and deserialization rebuilds internal nodes in *O*(*n*).
+//! `serde::nodes` keeps internal nodes too, so loading only checks them.
+//! Without it, [`encode_to`] and [`decode_from`] persist the elements in a versioned binary format with an integrity check.
+//!
+//! ```
+//! # #[cfg(feature = "serde")]
+//! # {
+//! use postfix_segment_tree::PostfixSegmentTree;
+//!
+//! let tree = PostfixSegmentTree::from_iter([3, 1, 4, 1, 5]);
+//! let json = serde_json::to_string(&tree).unwrap();
+//! assert_eq!(json, "[3,1,4,1,5]");
+//!
+//! let loaded: PostfixSegmentTree<i32> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(loaded.prefix_sum(5), tree.prefix_sum(5));
+//! # }
+//! ```
+//!
 //! [`prefix_sum`]: PostfixSegmentTree::prefix_sum
 //! [`sum`]: PostfixSegmentTree::sum
 //! [`update`]: PostfixSegmentTree::update
 //! [`push`]: PostfixSegmentTree::push
 //! [`get`]: PostfixSegmentTree::get
 //! [`len`]: PostfixSegmentTree::len
+//! [`reduce`]: PostfixSegmentTree::reduce
+//! [`checked_sum`]: PostfixSegmentTree::checked_sum
+//! [`checked_update`]: PostfixSegmentTree::checked_update
+//! [`encode_to`]: PostfixSegmentTree::encode_to
+//! [`decode_from`]: PostfixSegmentTree::decode_from
 //!
 //! # Encoding Layout
 //!
@@ -91,24 +151,144 @@
 //!
 //! It actually forms a minimal set of full binary trees,
 //! but it's a hybrid of Segment Tree and Fenwick Tree, so let's call it a tree.
+mod aggregate;
+#[cfg(feature = "allocator_api")]
+mod allocator;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod batch;
+mod beats;
+mod bit_tree;
+mod broadcast;
+mod bulk;
+mod cached;
+mod checked;
+#[cfg(feature = "compression")]
+mod compressed;
+mod cow;
+mod cursor;
+mod element_mut;
+mod encode;
+mod error;
+mod extract_if;
+#[cfg(feature = "mmap")]
+mod file_backed;
+mod implicit;
 mod index;
 mod internal;
 mod iterator;
+mod journal;
+mod lazy;
+#[cfg(any(test, feature = "model"))]
+pub mod model;
+mod multiset;
+pub mod node;
+mod persistent;
+mod range_add;
+#[cfg(feature = "raw")]
+pub mod raw;
+mod relaxed;
+mod rolling;
+mod rope;
+mod search;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod slice;
+mod sliding;
+mod sparse;
+mod sums;
+mod tree_2d;
+mod tree_nd;
+mod versioned;
+mod virtual_list;
+mod weighted;
 
-pub use crate::iterator::ElementIterator;
+#[cfg(feature = "num-traits")]
+pub use crate::aggregate::Num;
+pub use crate::aggregate::{
+    Additive, Aggregate, Bounded, Combine, Gcd, Max, MaxSubarray, Min, Stats, ToF64, Xor,
+};
+pub use crate::batch::Batch;
+pub use crate::beats::BeatsPostfixSegmentTree;
+pub use crate::bit_tree::BitTree;
+pub use crate::broadcast::BroadcastPostfixSegmentTree;
+pub use crate::cached::CachedPostfixSegmentTree;
+pub use crate::checked::CheckedCombine;
+#[cfg(feature = "compression")]
+pub use crate::compressed::{BlockCompressible, CompressedPostfixSegmentTree};
+pub use crate::cow::CowPostfixSegmentTree;
+pub use crate::cursor::CursorMut;
+pub use crate::element_mut::{ElementMut, ElementsMut, IterMut};
+pub use crate::encode::Encode;
+pub use crate::error::{DecodeError, TryReserveError, ValidationError};
+pub use crate::extract_if::ExtractIf;
+#[cfg(feature = "mmap")]
+pub use crate::file_backed::FilePostfixSegmentTree;
+pub use crate::implicit::ImplicitPostfixSegmentTree;
+pub use crate::iterator::{Chunks, Differences, ElementIterator, IntoIter, Windows};
+pub use crate::journal::JournaledPostfixSegmentTree;
+pub use crate::lazy::{LazyPostfixSegmentTree, Scale};
+pub use crate::multiset::OrderStatisticMultiset;
+pub use crate::persistent::PersistentPostfixSegmentTree;
+pub use crate::range_add::{RangeAddPointQuery, RangeAddRangeSum};
+pub use crate::relaxed::{AtomicElement, RelaxedPostfixSegmentTree};
+pub use crate::rolling::RollingWindows;
+pub use crate::rope::TreeString;
+pub use crate::slice::{TreeSlice, TreeSliceMut};
+pub use crate::sliding::{SlidingExtrema, SlidingHint};
+pub use crate::sparse::SparsePostfixSegmentTree;
+pub use crate::sums::{ChunkSums, PrefixSums, WindowSums, WindowSumsByDifference};
+pub use crate::tree_2d::PostfixSegmentTree2D;
+pub use crate::tree_nd::PostfixSegmentTreeNd;
+pub use crate::versioned::VersionedPostfixSegmentTree;
+pub use crate::virtual_list::VirtualList;
+pub use crate::weighted::DynamicWeightedIndex;
+#[cfg(feature = "derive")]
+pub use postfix_segment_tree_derive::Aggregate;
 
 use crate::internal::consts;
-use crate::internal::node_id::{LeafNodeId, get_nodes_len_for};
-use crate::internal::skipping_iterator::{IncreasingSkippingIterator, SkippingIterator};
-use std::ops::AddAssign;
+use crate::internal::node_id::{LeafNodeId, checked_get_nodes_len_for, get_nodes_len_for};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::range::to_range;
+use crate::internal::skipping_iterator::{SkippingIterator, range_iterator};
+#[cfg(feature = "allocator_api")]
+use std::alloc::{Allocator, Global};
+use std::ops::{RangeBounds, Sub};
 
 /// A variant of Segment Tree that can calculate `push` in amortized *O*(1) time.
+#[cfg(not(feature = "allocator_api"))]
 pub struct PostfixSegmentTree<T> {
     pub(crate) nodes: Vec<T>,
     pub(crate) len: usize,
 }
 
-// memory managements operations
+/// A variant of Segment Tree that can calculate `push` in amortized *O*(1) time.
+///
+/// Nodes are allocated with `A`. See [`new_in`] for which operations are available for allocators other than [`Global`].
+///
+/// [`new_in`]: PostfixSegmentTree::new_in
+#[cfg(feature = "allocator_api")]
+pub struct PostfixSegmentTree<T, A: Allocator = Global> {
+    pub(crate) nodes: Vec<T, A>,
+    pub(crate) len: usize,
+}
+
+// names the tree with the allocator `A`, which is dropped without the `allocator_api` feature.
+// so `impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A)` is generic over allocators only if they exist.
+#[cfg(feature = "allocator_api")]
+macro_rules! tree_in {
+    ($t:ty, $a:ty) => {
+        $crate::PostfixSegmentTree<$t, $a>
+    };
+}
+#[cfg(not(feature = "allocator_api"))]
+macro_rules! tree_in {
+    ($t:ty, $a:ty) => {
+        $crate::PostfixSegmentTree<$t>
+    };
+}
+pub(crate) use tree_in;
+
 impl<T> PostfixSegmentTree<T> {
     pub fn new() -> Self {
         Self {
@@ -116,7 +296,10 @@ impl<T> PostfixSegmentTree<T> {
             len: 0,
         }
     }
+}
 
+// memory managements operations
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A) {
     /// Returns the total number of nodes
     ///
     /// `nodes_len` == [`crate::internal::node_id::get_nodes_len_for`]\(`len`) == `len` \* 2 - `len.count_ones()` will hold
@@ -154,6 +337,23 @@ impl<T> PostfixSegmentTree<T> {
         self.len
     }
 
+    /// Returns `true` if the tree contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::new();
+    /// assert!(tree.is_empty());
+    ///
+    /// tree.push(1);
+    /// assert!(!tree.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn nodes_capacity(&self) -> usize {
         self.nodes.capacity()
     }
@@ -164,32 +364,91 @@ impl<T> PostfixSegmentTree<T> {
     }
 
     /// Reserves capacity for at least `additional` more elements to be inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`remaining_capacity`].
+    ///
+    /// [`remaining_capacity`]: PostfixSegmentTree::remaining_capacity
     pub fn reserve(&mut self, additional: usize) {
-        let new_capacity = self.len() + additional;
-        assert!(new_capacity <= consts::MAX_LEN);
-
-        let new_nodes_capacity = get_nodes_len_for(new_capacity);
-        let nodes_len = self.nodes_len();
-        if new_nodes_capacity > nodes_len {
-            let additional_nodes = new_nodes_capacity - nodes_len;
-            self.reserve_nodes(additional_nodes)
-        }
+        let additional_nodes = self
+            .additional_nodes_for(additional)
+            .expect("capacity overflow");
+        self.reserve_nodes(additional_nodes)
     }
 
     pub fn reserve_nodes_exact(&mut self, additional: usize) {
         self.nodes.reserve_exact(additional);
     }
 
+    /// Reserves the minimum capacity for at least `additional` more elements to be inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds [`remaining_capacity`].
+    ///
+    /// [`remaining_capacity`]: PostfixSegmentTree::remaining_capacity
     pub fn reserve_exact(&mut self, additional: usize) {
-        let new_capacity = self.len() + additional;
-        assert!(new_capacity <= consts::MAX_LEN);
-
-        let new_nodes_capacity = get_nodes_len_for(new_capacity);
-        let nodes_len = self.nodes_len();
-        if new_nodes_capacity > nodes_len {
-            let additional_nodes = new_nodes_capacity - nodes_len;
-            self.reserve_nodes_exact(additional_nodes)
+        let additional_nodes = self
+            .additional_nodes_for(additional)
+            .expect("capacity overflow");
+        self.reserve_nodes_exact(additional_nodes)
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to be inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::{PostfixSegmentTree, TryReserveError};
+    ///
+    /// let mut tree: PostfixSegmentTree<u64> = PostfixSegmentTree::new();
+    /// assert_eq!(tree.try_reserve(10), Ok(()));
+    /// assert_eq!(tree.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let additional_nodes = self
+            .additional_nodes_for(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        Ok(self.nodes.try_reserve(additional_nodes)?)
+    }
+
+    /// Tries to reserve the minimum capacity for at least `additional` more elements to be inserted.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let additional_nodes = self
+            .additional_nodes_for(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        Ok(self.nodes.try_reserve_exact(additional_nodes)?)
+    }
+
+    /// Returns how many more elements can be inserted before hitting the platform limit.
+    ///
+    /// The limit depends on the pointer width and `size_of::<T>()`, since nodes are stored in a [`Vec`].
+    /// It doesn't take the available memory into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree: PostfixSegmentTree<u64> = PostfixSegmentTree::new();
+    /// let remaining = tree.remaining_capacity();
+    /// tree.push(1);
+    /// assert_eq!(tree.remaining_capacity(), remaining - 1);
+    /// ```
+    pub fn remaining_capacity(&self) -> usize {
+        consts::max_len_for::<T>() - self.len()
+    }
+
+    /// Returns the number of nodes to reserve for `additional` more elements,
+    /// or `None` if it exceeds the platform limit.
+    fn additional_nodes_for(&self, additional: usize) -> Option<usize> {
+        if additional > self.remaining_capacity() {
+            return None;
         }
+
+        let new_nodes_capacity = checked_get_nodes_len_for(self.len() + additional)?;
+        Some(new_nodes_capacity.saturating_sub(self.nodes_len()))
     }
 
     pub fn shrink_to_fit(&mut self) {
@@ -201,7 +460,8 @@ impl<T> PostfixSegmentTree<T> {
     }
 
     pub fn shrink_to(&mut self, min_capacity: usize) {
-        assert!(min_capacity <= consts::MAX_LEN);
+        // it can't have more capacity than the platform limit anyway
+        let min_capacity = usize::min(min_capacity, consts::max_len_for::<T>());
 
         let min_nodes_capacity = get_nodes_len_for(min_capacity);
         self.shrink_nodes_to(min_nodes_capacity)
@@ -225,21 +485,264 @@ impl<T> PostfixSegmentTree<T> {
             return;
         }
 
-        assert!(len <= consts::MAX_LEN);
         let nodes_len = get_nodes_len_for(len);
         self.nodes.truncate(nodes_len);
         self.len = len;
     }
+
+    /// Removes all elements, keeping the allocated capacity of nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// let capacity = tree.nodes_capacity();
+    /// tree.clear();
+    ///
+    /// assert!(tree.is_empty());
+    /// assert_eq!(tree.nodes_capacity(), capacity);
+    /// ```
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Removes the last element and returns it, or `None` if it is empty.
+    ///
+    /// Partial sums of the other elements are not affected, so nothing is recalculated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// assert_eq!(tree.pop(), Some(3));
+    /// assert_eq!(tree.prefix_sum(2), 3);
+    ///
+    /// tree.truncate(0);
+    /// assert_eq!(tree.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.pop_leaf())
+    }
+}
+
+impl<T> Default for PostfixSegmentTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for PostfixSegmentTree<T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            len: self.len,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.nodes.clone_from(&source.nodes);
+        self.len = source.len;
+    }
 }
 
 impl<T> FromIterator<T> for PostfixSegmentTree<T>
 where
-    for<'a> T: AddAssign<&'a T> + Default,
+    T: Combine + Clone,
 {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         let mut tree = Self::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T> From<Vec<T>> for PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// See [`PostfixSegmentTree::from_vec`]
+    fn from(elements: Vec<T>) -> Self {
+        Self::from_vec(elements)
+    }
+}
+
+impl<T> From<&[T]> for PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// See [`PostfixSegmentTree::from_slice`]
+    fn from(elements: &[T]) -> Self {
+        Self::from_slice(elements)
+    }
+}
+
+impl<T> Extend<T> for PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Pushes all elements of `iter`, after reserving nodes for the lower bound of its `size_hint`.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2]);
+    /// tree.extend([3, 4]);
+    /// assert_eq!(tree.prefix_sum(4), 10);
+    /// ```
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        self.reserve(usize::min(lower, self.remaining_capacity()));
+
         for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<'a, T> Extend<&'a T> for PostfixSegmentTree<T>
+where
+    T: Combine + Clone + 'a,
+{
+    /// Pushes clones of all elements of `iter`.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::new();
+    /// tree.extend(&[1, 2, 3]);
+    /// assert_eq!(tree.prefix_sum(3), 6);
+    /// ```
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().cloned());
+    }
+}
+
+impl<'a, T> FromIterator<&'a T> for PostfixSegmentTree<T>
+where
+    T: Combine + Clone + 'a,
+{
+    fn from_iter<I: IntoIterator<Item = &'a T>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+// constructors
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Constructs a tree by moving `elements` into leaf nodes.
+    ///
+    /// Nodes are allocated once, and each parent node is calculated once right after its children,
+    /// so it skips the per-element overhead of [`push`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_vec(vec![1, 2, 3, 4]);
+    /// assert_eq!(tree.nodes_len(), 7);
+    /// assert_eq!(tree.prefix_sum(3), 6);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) where *n* is `elements.len()`
+    ///
+    /// [`push`]: PostfixSegmentTree::push
+    pub fn from_vec(elements: Vec<T>) -> Self {
+        let mut tree = Self::new();
+        tree.reserve_exact(elements.len());
+
+        for element in elements {
+            tree.push_leaf(element); // CLEAN: parents of the new element
+        }
+
+        tree
+    }
+
+    /// Constructs a tree by cloning `elements` into leaf nodes.
+    ///
+    /// Like [`from_vec`], nodes are allocated once and each parent node is calculated once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_slice(&[1, 2, 3, 4]);
+    /// assert_eq!(tree.prefix_sum(4), 10);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) where *n* is `elements.len()`
+    ///
+    /// [`from_vec`]: PostfixSegmentTree::from_vec
+    pub fn from_slice(elements: &[T]) -> Self {
+        let mut tree = Self::new();
+        tree.reserve_exact(elements.len());
+
+        for element in elements {
+            tree.push_leaf(element.clone()); // CLEAN: parents of the new element
+        }
+
+        tree
+    }
+
+    /// Reconstructs a tree from inclusive prefix sums,
+    /// where `prefix_sums[i]` is the sum of `elements[..=i]`.
+    ///
+    /// Elements are restored by differencing adjacent prefix sums.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_prefix_sums(&[1, 3, 6, 10]);
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    /// assert_eq!(tree.prefix_sum(3), 6);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) where *n* is `prefix_sums.len()`
+    pub fn from_prefix_sums(prefix_sums: &[T]) -> Self
+    where
+        T: Sub<Output = T>,
+    {
+        let mut tree = Self::new();
+        tree.reserve(prefix_sums.len());
+
+        // the first prefix sum is the first element itself
+        let mut previous: Option<&T> = None;
+        for prefix_sum in prefix_sums {
+            let element = match previous {
+                Some(previous) => prefix_sum.clone() - previous.clone(),
+                None => prefix_sum.clone(),
+            };
             tree.push(element);
+            previous = Some(prefix_sum);
         }
 
         tree
@@ -247,9 +750,9 @@ where
 }
 
 // sum query
-impl<T> PostfixSegmentTree<T>
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A)
 where
-    for<'a> T: AddAssign<&'a T> + Default,
+    T: Aggregate,
 {
     /// Returns the equivalent of `self.iter().take(index).sum()`
     ///
@@ -273,9 +776,13 @@ where
     pub fn prefix_sum(&self, index: usize) -> T {
         assert!(index <= self.len());
 
-        let mut sum = T::default();
+        // nodes are combined in strict left-to-right order, for non-commutative operations.
+        let mut sum = T::identity();
+        let mut next = 0;
         for id in SkippingIterator::new(index) {
-            sum += self.get_node(id);
+            debug_assert_eq!(id.first_index(), next);
+            sum.combine(self.get_node(id));
+            next = id.index() + 1;
         }
 
         sum
@@ -329,28 +836,91 @@ where
         assert!(index <= self.len());
         assert!(len <= self.len() - index);
 
-        let mut sum = T::default();
-        let mut iter = SkippingIterator::new(index + len);
-        let pivot = iter.skip_to_pivot(index);
-
-        // sum index..pivot
-        for id in IncreasingSkippingIterator::new(index, pivot) {
-            sum += self.get_node(id);
+        // nodes are combined in strict left-to-right order, for non-commutative operations.
+        let mut sum = T::identity();
+        let mut next = index;
+        for id in range_iterator(index, index + len) {
+            debug_assert_eq!(id.first_index(), next);
+            sum.combine(self.get_node(id));
+            next = id.index() + 1;
         }
 
-        // sum pivot..index+count
-        for id in iter {
-            sum += self.get_node(id);
+        sum
+    }
+
+    /// Returns the sum of elements in `range`, which is equivalent to `self.iter().take(range.end).skip(range.start).sum()`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// assert_eq!(tree.sum_range(..), 10);
+    /// assert_eq!(tree.sum_range(1..3), 5);
+    /// assert_eq!(tree.sum_range(1..=3), 9);
+    /// assert_eq!(tree.sum_range(..2), 3);
+    /// assert_eq!(tree.sum_range(2..), 7);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `range.end`)
+    pub fn sum_range(&self, range: impl RangeBounds<usize>) -> T {
+        let range = to_range(range, self.len());
+        self.sum(range.start, range.len())
+    }
+}
+
+// reduce query
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A)
+where
+    T: Combine + Clone,
+{
+    /// Returns the equivalent of `self.iter().skip(index).take(len).cloned().reduce(combine)`
+    ///
+    /// Unlike [`sum`], it doesn't require an identity, and returns `None` for the empty range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// assert_eq!(tree.reduce(0, 0), None);
+    /// assert_eq!(tree.reduce(1, 2), Some(5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    ///
+    /// [`sum`]: PostfixSegmentTree::sum
+    pub fn reduce(&self, index: usize, len: usize) -> Option<T> {
+        assert!(index <= self.len());
+        assert!(len <= self.len() - index);
+
+        // nodes are combined in strict left-to-right order, for non-commutative operations.
+        let mut ids = range_iterator(index, index + len);
+        let first = ids.next()?;
+        debug_assert_eq!(first.first_index(), index);
+
+        let mut sum = self.get_node(first).clone();
+        let mut next = first.index() + 1;
+        for id in ids {
+            debug_assert_eq!(id.first_index(), next);
+            sum.combine(self.get_node(id));
+            next = id.index() + 1;
         }
 
-        sum
+        Some(sum)
     }
 }
 
 // update operations
-impl<T> PostfixSegmentTree<T>
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A)
 where
-    for<'a> T: AddAssign<&'a T> + Default,
+    T: Combine + Clone,
 {
     /// Analogous to `elements[index] = element`
     ///
@@ -385,6 +955,121 @@ where
         self.recalculate_nodes_after_update(id); // CLEAN: parents of `id`
     }
 
+    /// Analogous to `std::mem::replace(&mut elements[index], element)`
+    ///
+    /// Unlike [`update`], it returns the previous element, so it can be recycled.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// assert_eq!(tree.replace(1, 4), 2);
+    ///
+    /// assert_eq!(tree[1], 4);
+    /// assert_eq!(tree.prefix_sum(3), 8);
+    /// ```
+    ///
+    /// # time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`update`]: PostfixSegmentTree::update
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn replace(&mut self, index: usize, element: T) -> T {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        let replaced = std::mem::replace(self.get_leaf_node_mut(id), element); // DIRTY: parents of `id`
+
+        self.recalculate_nodes_after_update(id); // CLEAN: parents of `id`
+        replaced
+    }
+
+    /// Modifies `elements[index]` in place with `f`, then recalculates partial sums.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// tree.update_with(1, |element| *element *= 10);
+    ///
+    /// assert_eq!(tree[1], 20);
+    /// assert_eq!(tree.prefix_sum(3), 24);
+    /// ```
+    ///
+    /// # time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn update_with(&mut self, index: usize, f: impl FnOnce(&mut T)) {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        f(self.get_leaf_node_mut(id)); // DIRTY: parents of `id`
+
+        self.recalculate_nodes_after_update(id); // CLEAN: parents of `id`
+    }
+
+    /// Analogous to `elements.swap(a, b)`
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// tree.swap(0, 2);
+    ///
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+    /// assert_eq!(tree.prefix_sum(1), 3);
+    /// ```
+    ///
+    /// # time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(a < self.len());
+        assert!(b < self.len());
+
+        if a == b {
+            return;
+        }
+
+        let a = LeafNodeId::new(a);
+        let b = LeafNodeId::new(b);
+        self.swap_leaf_nodes(a, b); // DIRTY: parents of `a` and `b`
+
+        self.recalculate_nodes_after_update(a); // CLEAN: parents of `a`
+        self.recalculate_nodes_after_update(b); // CLEAN: parents of `b`
+    }
+
+    /// Recalculates every partial sum from the elements.
+    ///
+    /// Partial sums are always kept up-to-date by the other operations,
+    /// so it is only needed after elements are modified bypassing them.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// tree.rebuild();
+    /// assert_eq!(tree.prefix_sum(3), 6);
+    /// ```
+    ///
+    /// # time complexity
+    ///
+    /// *O*([`nodes_len`])
+    ///
+    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    pub fn rebuild(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+
+        self.recalculate_nodes_after_bulk_update(LeafNodeId::new(0)); // CLEAN: all parents
+    }
+
     /// Appends an element to the back of the collection.
     ///
     /// # time complexity
@@ -404,12 +1089,92 @@ where
     ///
     /// [`nodes_capacity`]: PostfixSegmentTree::nodes_capacity
     pub fn push(&mut self, element: T) {
-        assert!(self.len() <= consts::MAX_LEN - 1);
+        assert!(self.remaining_capacity() > 0, "capacity overflow");
 
-        let new_leaf = self.push_default_dirty(); // DIRTY: parents of `self.len() - 1` after the operation, which is `inserted_at`
-        *self.get_leaf_node_mut(new_leaf) = element; // DIRTY: parents of `inserted_at`
+        self.push_leaf(element); // CLEAN: parents of `self.len() - 1` after the operation
+    }
 
-        self.recalculate_nodes_after_update(new_leaf); // CLEAN: parents of `inserted_at
+    /// Clones and appends all elements in `elements`.
+    ///
+    /// Nodes are reserved at once, and only new nodes are calculated, each exactly once.
+    /// Existing nodes are never recalculated thanks to the postfix layout.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2]);
+    /// tree.extend_from_slice(&[3, 4, 5]);
+    /// assert_eq!(tree.prefix_sum(5), 15);
+    /// ```
+    ///
+    /// # time complexity
+    ///
+    /// Amortized *O*(`elements.len()`)
+    pub fn extend_from_slice(&mut self, elements: &[T]) {
+        self.reserve(elements.len());
+
+        for element in elements {
+            self.push_leaf(element.clone()); // CLEAN: parents of the new element
+        }
+    }
+
+    /// Resizes the tree in place so that [`len`] is equal to `new_len`.
+    ///
+    /// If `new_len` is greater than [`len`], the tree is extended by clones of `value`.
+    /// Otherwise, it is truncated.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::new();
+    /// tree.resize(4, 1);
+    /// assert_eq!(tree.prefix_sum(4), 4);
+    ///
+    /// tree.resize(2, 0);
+    /// assert_eq!(tree.prefix_sum(2), 2);
+    /// ```
+    ///
+    /// # time complexity
+    ///
+    /// Amortized *O*(`new_len - len`) to extend, *O*(1) to truncate.
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        self.resize_with(new_len, || value.clone());
+    }
+
+    /// Resizes the tree in place so that [`len`] is equal to `new_len`.
+    ///
+    /// If `new_len` is greater than [`len`], the tree is extended by values generated by `f`.
+    /// Otherwise, it is truncated.
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::new();
+    /// let mut next = 0;
+    /// tree.resize_with(4, || {
+    ///     next += 1;
+    ///     next
+    /// });
+    /// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    /// ```
+    ///
+    /// # time complexity
+    ///
+    /// Amortized *O*(`new_len - len`) to extend, *O*(1) to truncate.
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn resize_with(&mut self, new_len: usize, mut f: impl FnMut() -> T) {
+        if new_len <= self.len() {
+            self.truncate(new_len);
+            return;
+        }
+
+        self.reserve(new_len - self.len());
+        while self.len() < new_len {
+            self.push_leaf(f()); // CLEAN: parents of the new element
+        }
     }
 
     /// Shifts all elements from `index` to the right, then inserts an `element` at `index`.
@@ -420,14 +1185,13 @@ where
     ///
     /// [`len`]: PostfixSegmentTree::len
     pub fn insert(&mut self, index: usize, element: T) {
-        assert!(self.len() <= consts::MAX_LEN - 1);
+        assert!(self.remaining_capacity() > 0, "capacity overflow");
         assert!(index <= self.len());
 
-        let new_leaf = self.push_default_dirty(); // DIRTY: parents of `self.len() - 1` after the operation, which is `inserted_at`
-        *self.get_leaf_node_mut(new_leaf) = element; // DIRTY: parents of `inserted_at`
+        self.push_leaf(element); // CLEAN: parents of `self.len() - 1` after the operation
 
         let id = LeafNodeId::new(index);
-        self.rotate_leaf_nodes_right_by_one_dirty(id); // DIRTY: all parents of `>= id`, which includes `new_leaf`
+        self.rotate_leaf_nodes_right_by_one_dirty(id); // DIRTY: all parents of `>= id`
 
         self.recalculate_nodes_after_bulk_update(id); // CLEAN: all parents of `>= id`
     }
@@ -445,9 +1209,51 @@ where
         let id = LeafNodeId::new(index);
 
         self.rotate_leaf_nodes_left_by_one_dirty(id); // DIRTY: all parents of `>= id`
-        let popped = self.pop();
+        let popped = self.pop_leaf();
 
-        self.recalculate_nodes_after_bulk_update(id); // CLEAN: all parents of `>= id`
+        // nothing to recalculate when the last element is removed
+        if index < self.len() {
+            self.recalculate_nodes_after_bulk_update(id); // CLEAN: all parents of `>= id`
+        }
         popped
     }
 }
+
+// delta operations
+impl<T, #[cfg(feature = "allocator_api")] A: Allocator> tree_in!(T, A)
+where
+    T: Combine,
+{
+    /// Analogous to `elements[index] += delta`
+    ///
+    /// `delta` is combined into the leaf node and its parents directly, without recalculating them from children.
+    /// So it is only valid for commutative operations such as addition,
+    /// where `(a ⊕ delta) ⊕ b == (a ⊕ b) ⊕ delta` holds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// tree.add(1, 10);
+    ///
+    /// assert_eq!(tree[1], 12);
+    /// assert_eq!(tree.prefix_sum(3), 16);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        self.get_leaf_node_mut(id).combine(&delta);
+        for node_id in ParentsIterator::new(id, self.len()) {
+            self.nodes[node_id.node_index()].combine(&delta);
+        }
+    }
+}
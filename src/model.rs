@@ -0,0 +1,155 @@
+//! Differential testing of [`PostfixSegmentTree`] against a naive `Vec` model.
+//!
+//! [`check`] applies a sequence of [`Operation`]s to both a tree and a `Vec`,
+//! and asserts that every query of the tree matches a linear sum of the `Vec` after each operation.
+//! [`Operation::parse`] decodes any bytes into operations, so fuzzers can drive it directly.
+//! See `fuzz/fuzz_targets/model.rs` for a `cargo fuzz` target.
+//!
+//! # Examples
+//!
+//! ```
+//! use postfix_segment_tree::model::{Operation, check};
+//!
+//! check(&[Operation::Push(1), Operation::Push(2), Operation::Insert(0, 3), Operation::Remove(1)]);
+//! check(&Operation::parse(b"any bytes decode to valid operations"));
+//! ```
+
+use crate::PostfixSegmentTree;
+use crate::internal::node_id::get_nodes_len_for;
+
+/// An operation on the tree.
+///
+/// Indices are taken modulo the valid range at the time the operation is applied,
+/// and operations that need an element are skipped on an empty tree, so every sequence is valid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Operation {
+    Push(i64),
+    Pop,
+    Update(usize, i64),
+    Insert(usize, i64),
+    Remove(usize),
+    Swap(usize, usize),
+    Truncate(usize),
+}
+
+impl Operation {
+    /// Decodes operations from `bytes`, three bytes per operation.
+    pub fn parse(bytes: &[u8]) -> Vec<Operation> {
+        bytes
+            .chunks_exact(3)
+            .map(|chunk| {
+                let (a, b) = (chunk[1] as usize, chunk[2] as usize);
+                let element = i16::from_le_bytes([chunk[1], chunk[2]]) as i64;
+                // biased to growth, so trees span several levels
+                match chunk[0] % 16 {
+                    6 => Operation::Pop,
+                    7 | 8 => Operation::Update(a, chunk[2] as i8 as i64),
+                    9 | 10 => Operation::Insert(a, chunk[2] as i8 as i64),
+                    11 => Operation::Remove(a),
+                    12 | 13 => Operation::Swap(a, b),
+                    14 => Operation::Truncate(a),
+                    _ => Operation::Push(element),
+                }
+            })
+            .collect()
+    }
+
+    fn apply(&self, tree: &mut PostfixSegmentTree<i64>, model: &mut Vec<i64>) {
+        let len = model.len();
+        match *self {
+            Operation::Push(element) => {
+                tree.push(element);
+                model.push(element);
+            }
+            Operation::Pop => assert_eq!(tree.pop(), model.pop()),
+            Operation::Update(index, element) if len > 0 => {
+                tree.update(index % len, element);
+                model[index % len] = element;
+            }
+            Operation::Insert(index, element) => {
+                tree.insert(index % (len + 1), element);
+                model.insert(index % (len + 1), element);
+            }
+            Operation::Remove(index) if len > 0 => {
+                assert_eq!(tree.remove(index % len), model.remove(index % len));
+            }
+            Operation::Swap(a, b) if len > 0 => {
+                tree.swap(a % len, b % len);
+                model.swap(a % len, b % len);
+            }
+            Operation::Truncate(new_len) => {
+                tree.truncate(new_len % (len + 1));
+                model.truncate(new_len % (len + 1));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Applies `operations` to a tree and a `Vec`, and panics on the first mismatch.
+///
+/// After each operation, it compares elements, every [`prefix_sum`], [`postfix_sum`] and [`sum`],
+/// and all nodes against a tree built from the `Vec`.
+/// So it takes *O*(*n*<sup>2</sup> log *n*) per operation, and is meant for small trees.
+///
+/// [`prefix_sum`]: PostfixSegmentTree::prefix_sum
+/// [`postfix_sum`]: PostfixSegmentTree::postfix_sum
+/// [`sum`]: PostfixSegmentTree::sum
+pub fn check(operations: &[Operation]) {
+    let mut tree = PostfixSegmentTree::new();
+    let mut model = Vec::new();
+    for (step, operation) in operations.iter().enumerate() {
+        operation.apply(&mut tree, &mut model);
+
+        let context = || format!("after {operation:?} at step {step}");
+        assert_eq!(tree.len(), model.len(), "{}", context());
+        assert_eq!(
+            tree.nodes_len(),
+            get_nodes_len_for(model.len()),
+            "{}",
+            context()
+        );
+        assert!(tree.iter().eq(model.iter()), "{}", context());
+        assert_eq!(tree.validate(), Ok(()), "{}", context());
+        assert_eq!(
+            tree.nodes,
+            PostfixSegmentTree::from_iter(model.iter().copied()).nodes,
+            "{}",
+            context()
+        );
+        for index in 0..=model.len() {
+            let prefix: i64 = model[..index].iter().sum();
+            let postfix: i64 = model[index..].iter().sum();
+            assert_eq!(tree.prefix_sum(index), prefix, "{}", context());
+            assert_eq!(tree.postfix_sum(index), postfix, "{}", context());
+            let mut sum = 0;
+            for len in 0..=model.len() - index {
+                assert_eq!(tree.sum(index, len), sum, "{}", context());
+                if let Some(element) = model.get(index + len) {
+                    sum += element;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_random_operations() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..20 {
+            let bytes: Vec<u8> = (0..600)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect();
+            check(&Operation::parse(&bytes));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::ops::{Bound, RangeBounds};
+
+use crate::PostfixSegmentTree;
+
+/// A multiset of small non-negative integers, which supports order statistics in *O*(log *n*).
+///
+/// It stores the count of each value in a [`PostfixSegmentTree`] indexed by the value, like a Fenwick Tree.
+/// The tree grows to the largest inserted value by pushing empty counts, which is amortized *O*(1) per value,
+/// so memory is proportional to the largest value rather than the number of items.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::OrderStatisticMultiset;
+///
+/// let mut set = OrderStatisticMultiset::new();
+/// for value in [5, 1, 3, 3, 8] {
+///     set.insert(value);
+/// }
+///
+/// assert_eq!(set.len(), 5);
+/// assert_eq!(set.rank(3), 1);
+/// assert_eq!(set.kth(2), Some(3));
+/// assert_eq!(set.count_range(2..=5), 3);
+///
+/// assert!(set.remove(3));
+/// assert_eq!(set.kth(2), Some(5));
+/// ```
+pub struct OrderStatisticMultiset {
+    counts: PostfixSegmentTree<usize>,
+    len: usize,
+}
+
+impl OrderStatisticMultiset {
+    /// Creates an empty multiset.
+    pub fn new() -> Self {
+        Self {
+            counts: PostfixSegmentTree::new(),
+            len: 0,
+        }
+    }
+
+    /// Creates an empty multiset that can store values in `0..universe` without reallocation.
+    pub fn with_universe(universe: usize) -> Self {
+        Self {
+            counts: PostfixSegmentTree::from_vec(vec![0; universe]),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of items, counting duplicates.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the multiset contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of items equal to `value`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn count(&self, value: usize) -> usize {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    /// Returns `true` if the multiset contains `value`.
+    pub fn contains(&self, value: usize) -> bool {
+        self.count(value) > 0
+    }
+
+    /// Inserts an item of `value`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*), and amortized *O*(1) per value to grow the universe.
+    pub fn insert(&mut self, value: usize) {
+        if value >= self.counts.len() {
+            self.counts.resize(value + 1, 0);
+        }
+
+        self.counts.add(value, 1);
+        self.len += 1;
+    }
+
+    /// Removes an item of `value`, and returns `true` if it was present.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn remove(&mut self, value: usize) -> bool {
+        let count = self.count(value);
+        if count == 0 {
+            return false;
+        }
+
+        self.counts.update(value, count - 1);
+        self.len -= 1;
+        true
+    }
+
+    /// Returns the number of items less than `value`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn rank(&self, value: usize) -> usize {
+        self.counts.prefix_sum(usize::min(value, self.counts.len()))
+    }
+
+    /// Returns the `k`-th smallest item (0-based), or `None` if there are no more than `k` items.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn kth(&self, k: usize) -> Option<usize> {
+        self.counts.kth(&k)
+    }
+
+    /// Returns the number of items in `range`. Values out of the universe are not counted.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn count_range(&self, range: impl RangeBounds<usize>) -> usize {
+        let universe = self.counts.len();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => universe,
+        };
+
+        let start = usize::min(start, universe);
+        let end = usize::min(end, universe);
+        if start >= end {
+            return 0;
+        }
+
+        self.counts.sum(start, end - start)
+    }
+
+    /// Returns an iterator over items in ascending order, repeating duplicates.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .flat_map(|(value, &count)| std::iter::repeat_n(value, count))
+    }
+}
+
+impl Default for OrderStatisticMultiset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<usize> for OrderStatisticMultiset {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for value in iter {
+            set.insert(value);
+        }
+
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiset_matches_sorted_vec() {
+        let mut model: Vec<usize> = Vec::new();
+        let mut set = OrderStatisticMultiset::new();
+        for step in 0..200usize {
+            let value = (step * 37) % 23;
+            if step % 3 == 2 {
+                let position = model.iter().position(|&v| v == value);
+                assert_eq!(set.remove(value), position.is_some());
+                if let Some(position) = position {
+                    model.remove(position);
+                }
+            } else {
+                set.insert(value);
+                let position = model.partition_point(|&v| v <= value);
+                model.insert(position, value);
+            }
+
+            assert_eq!(set.len(), model.len());
+            assert!(set.iter().eq(model.iter().copied()));
+            for k in 0..=model.len() {
+                assert_eq!(set.kth(k), model.get(k).copied());
+            }
+            for value in 0..30 {
+                assert_eq!(set.rank(value), model.partition_point(|&v| v < value));
+                assert_eq!(
+                    set.count(value),
+                    model.iter().filter(|&&v| v == value).count()
+                );
+                for end in value..30 {
+                    let expected = model.iter().filter(|&&v| value <= v && v < end).count();
+                    assert_eq!(set.count_range(value..end), expected);
+                }
+            }
+        }
+    }
+}
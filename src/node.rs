@@ -0,0 +1,392 @@
+//! Public addressing of nodes.
+//!
+//! See [`crate#encoding-layout`] for how nodes are laid out.
+//! A node is addressed by the index of the last element that it covers and its level,
+//! and it is stored at [`NodeAddress::node_index`] in the underlying nodes.
+
+use std::ops::{Range, RangeBounds};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::error::ValidationError;
+use crate::internal::consts;
+use crate::internal::node_id::{LeafNodeId, NodeId, checked_get_nodes_len_for, get_nodes_len_for};
+use crate::internal::range::to_range;
+use crate::internal::skipping_iterator::range_iterator;
+
+/// An address of a node, which covers `2^level` elements that end at `index`.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::node::NodeAddress;
+///
+/// let address = NodeAddress::new(3, 2).unwrap();
+/// assert_eq!(address.node_index(), 6);
+/// assert_eq!(address.elements(), 0..4);
+/// assert_eq!(NodeAddress::from_node_index(6), Some(address));
+/// assert_eq!(NodeAddress::from_node_index(usize::MAX), None);
+///
+/// // element 2 has no node of level 1
+/// assert_eq!(NodeAddress::new(2, 1), None);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct NodeAddress {
+    index: usize,
+    level: u32,
+}
+
+impl NodeAddress {
+    /// Returns the address of the node of `level` that ends at `index`, if it exists.
+    pub fn new(index: usize, level: u32) -> Option<Self> {
+        if index > consts::MAX_LEN || level > LeafNodeId::new(index).max_level() {
+            return None;
+        }
+
+        Some(Self { index, level })
+    }
+
+    /// Returns the address of the node stored at `node_index`, if a tree can have that many nodes.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `node_index`)
+    pub fn from_node_index(node_index: usize) -> Option<Self> {
+        // find the last leaf node at or before `node_index`
+        let mut low = 0;
+        let mut high = usize::min(node_index, consts::MAX_LEN);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if get_nodes_len_for(mid) <= node_index {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+
+        let level = u32::try_from(node_index - get_nodes_len_for(low)).ok()?;
+        Self::new(low, level)
+    }
+
+    /// The index of the last element that this node covers.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The level of this node. Leaf nodes are level 0.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// The index of this node in the underlying nodes.
+    pub fn node_index(&self) -> usize {
+        NodeId::from(*self).node_index()
+    }
+
+    /// The range of elements that this node covers.
+    pub fn elements(&self) -> Range<usize> {
+        NodeId::from(*self).first_index()..self.index + 1
+    }
+}
+
+impl From<NodeId> for NodeAddress {
+    fn from(id: NodeId) -> Self {
+        Self {
+            index: id.index(),
+            level: id.level(),
+        }
+    }
+}
+
+impl From<NodeAddress> for NodeId {
+    fn from(address: NodeAddress) -> Self {
+        NodeId::new(address.index, address.level)
+    }
+}
+
+impl<T> PostfixSegmentTree<T> {
+    /// Returns the value of the node at `address`, which is the sum of [`NodeAddress::elements`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    /// use postfix_segment_tree::node::NodeAddress;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// assert_eq!(tree.node(NodeAddress::new(3, 2).unwrap()), Some(&10));
+    /// assert_eq!(tree.node(NodeAddress::new(4, 0).unwrap()), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn node(&self, address: NodeAddress) -> Option<&T> {
+        if address.index >= self.len() {
+            return None;
+        }
+
+        Some(self.get_node(address.into()))
+    }
+
+    /// Visits the nodes that cover `range` exactly, in left-to-right order,
+    /// with the range of elements that each node covers and its value.
+    ///
+    /// It is the decomposition used by [`sum`], so custom queries can be built on top of it.
+    /// The range of a node can be converted to [`NodeAddress`] to descend into its children.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5, 6, 7]);
+    /// let mut nodes = Vec::new();
+    /// tree.visit_covering_nodes(1..7, |elements, value| nodes.push((elements, *value)));
+    /// assert_eq!(nodes, vec![(1..2, 2), (2..4, 7), (4..6, 11), (6..7, 7)]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `range.end`)
+    ///
+    /// [`sum`]: PostfixSegmentTree::sum
+    pub fn visit_covering_nodes(
+        &self,
+        range: impl RangeBounds<usize>,
+        mut visit: impl FnMut(Range<usize>, &T),
+    ) {
+        let range = to_range(range, self.len());
+        for id in range_iterator(range.start, range.end) {
+            visit(id.first_index()..id.index() + 1, self.get_node(id));
+        }
+    }
+
+    /// Returns all nodes, in the layout described in [`crate#encoding-layout`].
+    ///
+    /// The node at `nodes[i]` is at [`NodeAddress::from_node_index`]\(`i`),
+    /// which gives the index of the last element that it covers and its level.
+    /// Conversely, a node of `level` that ends at `index` is at [`NodeAddress::node_index`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    /// use postfix_segment_tree::node::NodeAddress;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// assert_eq!(tree.as_nodes(), &[1, 2, 3, 3, 4, 7, 10]);
+    ///
+    /// let address = NodeAddress::from_node_index(5).unwrap();
+    /// assert_eq!((address.index(), address.level()), (3, 1));
+    /// assert_eq!(address.elements(), 2..4);
+    /// ```
+    pub fn as_nodes(&self) -> &[T] {
+        &self.nodes
+    }
+
+    /// Decomposes the tree into its nodes and the number of elements.
+    ///
+    /// Nodes are laid out as described in [`crate#encoding-layout`],
+    /// and they can be reassembled with [`from_raw_parts`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3]);
+    /// let (nodes, len) = tree.into_raw_parts();
+    /// assert_eq!(nodes, vec![1, 2, 3, 3]);
+    /// assert_eq!(len, 3);
+    ///
+    /// let tree = PostfixSegmentTree::from_raw_parts(nodes, len).unwrap();
+    /// assert_eq!(tree.prefix_sum(3), 6);
+    /// ```
+    ///
+    /// [`from_raw_parts`]: PostfixSegmentTree::from_raw_parts
+    pub fn into_raw_parts(self) -> (Vec<T>, usize) {
+        (self.nodes, self.len)
+    }
+
+    /// Creates a tree from `nodes` and the number of elements `len` without checking invariants.
+    ///
+    /// It is memory-safe, but if `nodes` is not a valid tree of `len` elements,
+    /// queries and mutations may return wrong results or panic.
+    /// Use [`from_raw_parts`] to check them.
+    ///
+    /// [`from_raw_parts`]: PostfixSegmentTree::from_raw_parts
+    pub fn from_raw_parts_unchecked(nodes: Vec<T>, len: usize) -> Self {
+        Self { nodes, len }
+    }
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone + PartialEq,
+{
+    /// Creates a tree from `nodes` and the number of elements `len`, such as those of [`into_raw_parts`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first violation if the number of nodes doesn't match `len`,
+    /// or an internal node doesn't equal the combination of its children.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(`len`)
+    ///
+    /// [`into_raw_parts`]: PostfixSegmentTree::into_raw_parts
+    pub fn from_raw_parts(nodes: Vec<T>, len: usize) -> Result<Self, ValidationError> {
+        let tree = Self::from_raw_parts_unchecked(nodes, len);
+        tree.validate()?;
+        Ok(tree)
+    }
+
+    /// Checks that the number of nodes matches [`len`], and every internal node equals the combination of its children.
+    ///
+    /// It is useful after poking at nodes directly, or when a bug is suspected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::node::NodeAddress;
+    /// use postfix_segment_tree::{PostfixSegmentTree, ValidationError};
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// assert_eq!(tree.validate(), Ok(()));
+    ///
+    /// let (mut nodes, len) = tree.into_raw_parts();
+    /// nodes[5] = 0;
+    /// let tree = PostfixSegmentTree::from_raw_parts_unchecked(nodes, len);
+    /// assert_eq!(
+    ///     tree.validate(),
+    ///     Err(ValidationError::Node(NodeAddress::new(3, 1).unwrap()))
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first violation, in the order of node indices.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let expected = checked_get_nodes_len_for(self.len);
+        if expected != Some(self.nodes.len()) {
+            return Err(ValidationError::NodesLen {
+                len: self.len,
+                nodes_len: self.nodes.len(),
+                expected,
+            });
+        }
+
+        // nodes are visited in the order of node indices
+        for index in 0..self.len {
+            let leaf = LeafNodeId::new(index);
+            for level in 1..=leaf.max_level() {
+                let id = leaf.with_level(level);
+                let node = self.get_node(id);
+                let mut expected = node.clone();
+                expected.combine_from(
+                    self.get_node(id.left_child()),
+                    self.get_node(id.right_child()),
+                );
+                if expected != *node {
+                    return Err(ValidationError::Node(id.into()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_node_index() {
+        let mut node_index = 0;
+        for index in 0..100 {
+            for level in 0..=LeafNodeId::new(index).max_level() {
+                let address = NodeAddress::new(index, level).unwrap();
+                assert_eq!(address.node_index(), node_index);
+                assert_eq!(NodeAddress::from_node_index(node_index), Some(address));
+                node_index += 1;
+            }
+        }
+
+        assert_eq!(NodeAddress::from_node_index(usize::MAX), None);
+        let max_level = LeafNodeId::new(consts::MAX_LEN).max_level();
+        let last = NodeAddress::new(consts::MAX_LEN, max_level).unwrap();
+        assert_eq!(NodeAddress::from_node_index(last.node_index()), Some(last));
+        assert_eq!(NodeAddress::from_node_index(last.node_index() + 1), None);
+    }
+
+    #[test]
+    fn test_visit_covering_nodes() {
+        const N: u32 = 20;
+        let tree = PostfixSegmentTree::from_iter(0..N);
+        for start in 0..=N as usize {
+            for end in start..=N as usize {
+                let mut next = start;
+                let mut sum = 0;
+                tree.visit_covering_nodes(start..end, |elements, value| {
+                    assert_eq!(elements.start, next);
+                    assert!(elements.len().is_power_of_two());
+                    assert_eq!(*value, tree.iter_range(elements.clone()).sum());
+                    next = elements.end;
+                    sum += value;
+                });
+                assert_eq!(next, end);
+                assert_eq!(sum, tree.iter_range(start..end).sum());
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_raw_parts() {
+        for len in 0..40 {
+            let (nodes, raw_len) = PostfixSegmentTree::from_iter(0..len as u32).into_raw_parts();
+            assert_eq!(raw_len, len);
+            let tree = PostfixSegmentTree::from_raw_parts(nodes.clone(), len).unwrap();
+            assert!(tree.iter().copied().eq(0..len as u32));
+
+            for node_index in 0..nodes.len() {
+                let mut corrupted = nodes.clone();
+                corrupted[node_index] += 100;
+                let address = NodeAddress::from_node_index(node_index).unwrap();
+                let expected = if address.level() == 0 {
+                    // the parent of the leaf is checked first, if it exists
+                    NodeAddress::new(address.index() | 1, 1).filter(|parent| parent.index() < len)
+                } else {
+                    Some(address)
+                };
+                assert_eq!(
+                    PostfixSegmentTree::from_raw_parts(corrupted, len).err(),
+                    expected.map(ValidationError::Node)
+                );
+            }
+
+            let mut extra = nodes.clone();
+            extra.push(0);
+            assert!(matches!(
+                PostfixSegmentTree::from_raw_parts(extra, len),
+                Err(ValidationError::NodesLen { .. })
+            ));
+        }
+        assert_eq!(
+            PostfixSegmentTree::<u32>::from_raw_parts(Vec::new(), usize::MAX).err(),
+            Some(ValidationError::NodesLen {
+                len: usize::MAX,
+                nodes_len: 0,
+                expected: None
+            })
+        );
+    }
+}
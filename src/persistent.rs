@@ -0,0 +1,287 @@
+use crate::aggregate::{Aggregate, Combine};
+use crate::internal::node_id::{LeafNodeId, NodeId};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::skipping_iterator::range_iterator;
+
+/// A partially persistent variant of [`PostfixSegmentTree`], which can query any earlier version.
+///
+/// Every mutation increments the [`version`] of the tree,
+/// and each node keeps the history of its values with the versions when they were written.
+/// A query at a version reads the latest value of each node written no later than the version,
+/// so it is *O*(log *n* \* log *versions*), where *versions* is the number of writes to a node.
+/// A mutation appends a value to the history of *O*(log *n*) nodes, so it takes *O*(log *n*) memory per mutation.
+///
+/// `insert` and `remove` are missing on purpose: a shift would append a version to every node after it.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PersistentPostfixSegmentTree;
+///
+/// let mut balances = PersistentPostfixSegmentTree::from_iter([100, 200]);
+/// let opened = balances.version();
+///
+/// balances.update(0, 150);
+/// let updated = balances.version();
+/// balances.push(50);
+///
+/// assert_eq!(balances.prefix_sum(3), 400);
+/// assert_eq!(balances.prefix_sum_at(2, updated), 350);
+/// assert_eq!(balances.prefix_sum_at(2, opened), 300);
+/// assert_eq!(balances.len_at(opened), 2);
+/// assert_eq!(balances.get_at(0, opened), Some(&100));
+/// ```
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+/// [`version`]: PersistentPostfixSegmentTree::version
+pub struct PersistentPostfixSegmentTree<T> {
+    // histories of nodes indexed by the node index, in increasing order of versions
+    nodes: Vec<Vec<(u64, T)>>,
+    // versions when elements were pushed, which are nondecreasing
+    pushed: Vec<u64>,
+    version: u64,
+}
+
+impl<T> PersistentPostfixSegmentTree<T> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            pushed: Vec::new(),
+            version: 0,
+        }
+    }
+
+    /// Returns the current version, which is the number of mutations so far.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.pushed.len()
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.pushed.is_empty()
+    }
+
+    /// Returns the number of elements at `version`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PersistentPostfixSegmentTree::len
+    pub fn len_at(&self, version: u64) -> usize {
+        self.pushed.partition_point(|&pushed| pushed <= version)
+    }
+
+    /// Returns the element at `index` of the current version.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        Some(self.latest(LeafNodeId::new(index).node_index()))
+    }
+
+    /// Returns the element at `index` of `version`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *versions*)
+    pub fn get_at(&self, index: usize, version: u64) -> Option<&T> {
+        if index >= self.len_at(version) {
+            return None;
+        }
+
+        Some(self.node_at(LeafNodeId::new(index).node_index(), version))
+    }
+
+    /// Returns the value of the node at `node_index` of `version`.
+    fn node_at(&self, node_index: usize, version: u64) -> &T {
+        let history = &self.nodes[node_index];
+        let position = history.partition_point(|&(written, _)| written <= version);
+        &history[position - 1].1
+    }
+
+    fn latest(&self, node_index: usize) -> &T {
+        &self.nodes[node_index].last().unwrap().1
+    }
+}
+
+impl<T> PersistentPostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Appends an element to the back of the collection as a new version.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, element: T) {
+        self.version += 1;
+        self.push_at_current_version(element);
+    }
+
+    /// Updates the element at `index` as a new version.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PersistentPostfixSegmentTree::len
+    pub fn update(&mut self, index: usize, element: T) {
+        assert!(index < self.len());
+
+        self.version += 1;
+        let id = LeafNodeId::new(index);
+        self.write(id.node_index(), element);
+        for parent in ParentsIterator::new(id, self.len()) {
+            let sum = self.sum_of_children(parent);
+            self.write(parent.node_index(), sum);
+        }
+    }
+
+    fn push_at_current_version(&mut self, element: T) {
+        let id = LeafNodeId::new(self.len());
+        self.pushed.push(self.version);
+        self.nodes.push(vec![(self.version, element)]);
+        for parent in ParentsIterator::new(id, self.len()) {
+            debug_assert_eq!(self.nodes.len(), parent.node_index());
+            let sum = self.sum_of_children(parent);
+            self.nodes.push(vec![(self.version, sum)]);
+        }
+    }
+
+    /// Writes `value` to the node at `node_index` at the current version.
+    fn write(&mut self, node_index: usize, value: T) {
+        let history = &mut self.nodes[node_index];
+        match history.last_mut() {
+            Some((written, last)) if *written == self.version => *last = value,
+            _ => history.push((self.version, value)),
+        }
+    }
+
+    fn sum_of_children(&self, id: NodeId) -> T {
+        let mut sum = self.latest(id.left_child().node_index()).clone();
+        sum.combine(self.latest(id.right_child().node_index()));
+        sum
+    }
+}
+
+impl<T> PersistentPostfixSegmentTree<T>
+where
+    T: Aggregate + Clone,
+{
+    /// Returns the equivalent of `self.iter().take(index).sum()` of the current version.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn prefix_sum(&self, index: usize) -> T {
+        assert!(index <= self.len());
+
+        let mut sum = T::identity();
+        for id in range_iterator(0, index) {
+            sum.combine(self.latest(id.node_index()));
+        }
+
+        sum
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()` of `version`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index` \* log *versions*)
+    pub fn prefix_sum_at(&self, index: usize, version: u64) -> T {
+        self.sum_at(0, index, version)
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()` of `version`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`index` + `len`) \* log *versions*)
+    pub fn sum_at(&self, index: usize, len: usize, version: u64) -> T {
+        let len_at = self.len_at(version);
+        assert!(index <= len_at);
+        assert!(len <= len_at - index);
+
+        let mut sum = T::identity();
+        for id in range_iterator(index, index + len) {
+            sum.combine(self.node_at(id.node_index(), version));
+        }
+
+        sum
+    }
+}
+
+impl<T> Default for PersistentPostfixSegmentTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for PersistentPostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Creates a tree of elements at the version 0.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        for element in iter {
+            tree.push_at_current_version(element);
+        }
+
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queries_at_past_versions() {
+        let mut tree = PersistentPostfixSegmentTree::from_iter(0..5i64);
+        let mut snapshots: Vec<Vec<i64>> = vec![(0..5).collect()];
+        for step in 0..60i64 {
+            let mut model = snapshots.last().unwrap().clone();
+            if step % 4 == 0 {
+                tree.push(step);
+                model.push(step);
+            } else {
+                let index = (step as usize * 7) % model.len();
+                tree.update(index, step);
+                model[index] = step;
+            }
+            snapshots.push(model);
+            assert_eq!(tree.version(), snapshots.len() as u64 - 1);
+        }
+
+        for (version, model) in snapshots.iter().enumerate() {
+            let version = version as u64;
+            assert_eq!(tree.len_at(version), model.len());
+            for index in 0..=model.len() {
+                assert_eq!(
+                    tree.prefix_sum_at(index, version),
+                    model[..index].iter().sum()
+                );
+            }
+            for (index, element) in model.iter().enumerate() {
+                assert_eq!(tree.get_at(index, version), Some(element));
+                assert_eq!(
+                    tree.sum_at(index, model.len() - index, version),
+                    model[index..].iter().sum()
+                );
+            }
+        }
+    }
+}
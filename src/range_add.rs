@@ -0,0 +1,334 @@
+use std::ops::{RangeBounds, Sub};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+use crate::internal::range::to_range;
+use crate::lazy::Scale;
+
+/// A dual of [`PostfixSegmentTree`], which supports range additions and point queries.
+///
+/// It stores differences of adjacent elements in a [`PostfixSegmentTree`],
+/// so an element is the prefix sum of differences,
+/// and a range addition only changes the differences at both ends of the range.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::RangeAddPointQuery;
+///
+/// let mut values = RangeAddPointQuery::from_iter([1, 2, 3, 4, 5]);
+/// values.range_add(1..4, 10);
+/// values.range_add(3.., 100);
+/// assert_eq!(values.get(0), 1);
+/// assert_eq!(values.get(2), 13);
+/// assert_eq!(values.get(3), 114);
+/// assert_eq!(values.get(4), 105);
+/// ```
+pub struct RangeAddPointQuery<T> {
+    differences: PostfixSegmentTree<T>,
+}
+
+impl<T> RangeAddPointQuery<T>
+where
+    T: Aggregate + Clone + Sub<Output = T>,
+{
+    pub fn new() -> Self {
+        Self {
+            differences: PostfixSegmentTree::new(),
+        }
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.differences.len()
+    }
+
+    /// Returns `true` if it contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Returns an element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn get(&self, index: usize) -> T {
+        assert!(index < self.len());
+
+        self.differences.prefix_sum(index + 1)
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: RangeAddPointQuery::len
+    pub fn push(&mut self, element: T) {
+        let last = self.differences.prefix_sum(self.len());
+        self.differences.push(element - last);
+    }
+
+    /// Adds `delta` to every element in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: RangeAddPointQuery::len
+    pub fn range_add(&mut self, range: impl RangeBounds<usize>, delta: T) {
+        let range = to_range(range, self.len());
+        if range.is_empty() {
+            return;
+        }
+
+        // elements after the range are not changed
+        if range.end < self.len() {
+            self.differences
+                .add(range.end, T::identity() - delta.clone());
+        }
+        self.differences.add(range.start, delta);
+    }
+}
+
+impl<T> Default for RangeAddPointQuery<T>
+where
+    T: Aggregate + Clone + Sub<Output = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for RangeAddPointQuery<T>
+where
+    T: Aggregate + Clone + Sub<Output = T>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        // differences are calculated without querying the tree
+        let mut differences = PostfixSegmentTree::new();
+        let mut previous = T::identity();
+        for element in iter {
+            differences.push(element.clone() - previous);
+            previous = element;
+        }
+
+        Self { differences }
+    }
+}
+
+/// A composite of two [`PostfixSegmentTree`]s, which supports range additions and range sums.
+///
+/// It is the standard trick of Fenwick Trees. Adding `delta` to `start..end` is recorded as
+/// * `delta` at `start` and `-delta` at `end` in the first tree,
+/// * `delta * start` at `start` and `-delta * end` at `end` in the second tree,
+///
+/// so the prefix sum of `index` elements is `first.prefix_sum(index) * index - second.prefix_sum(index)`.
+/// Initial elements are recorded as negated values in the second tree.
+///
+/// Unlike [`LazyPostfixSegmentTree`], elements need to be invertible with `Sub`.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::RangeAddRangeSum;
+///
+/// let mut values = RangeAddRangeSum::from_iter([1, 2, 3, 4, 5]);
+/// values.range_add(1..4, 10);
+/// assert_eq!(values.range_sum(..), 45);
+/// assert_eq!(values.range_sum(3..), 19);
+/// assert_eq!(values.get(2), 13);
+///
+/// values.push(6);
+/// assert_eq!(values.range_sum(4..), 11);
+/// ```
+///
+/// [`LazyPostfixSegmentTree`]: crate::LazyPostfixSegmentTree
+pub struct RangeAddRangeSum<T> {
+    // both have an extra slot at the end, which records the end of ranges that end at `len`,
+    // so pushed elements are not affected by them.
+    deltas: PostfixSegmentTree<T>,
+    corrections: PostfixSegmentTree<T>,
+}
+
+impl<T> RangeAddRangeSum<T>
+where
+    T: Aggregate + Scale + Clone + Sub<Output = T>,
+{
+    pub fn new() -> Self {
+        Self {
+            deltas: PostfixSegmentTree::from_iter([T::identity()]),
+            corrections: PostfixSegmentTree::from_iter([T::identity()]),
+        }
+    }
+
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.deltas.len() - 1
+    }
+
+    /// Returns `true` if it contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: RangeAddRangeSum::len
+    pub fn push(&mut self, element: T) {
+        self.corrections.add(self.len(), T::identity() - element);
+
+        self.deltas.push(T::identity());
+        self.corrections.push(T::identity());
+    }
+
+    /// Adds `delta` to every element in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: RangeAddRangeSum::len
+    pub fn range_add(&mut self, range: impl RangeBounds<usize>, delta: T) {
+        let range = to_range(range, self.len());
+        if range.is_empty() {
+            return;
+        }
+
+        self.deltas.add(range.end, T::identity() - delta.clone());
+        self.corrections
+            .add(range.end, T::identity() - delta.scale(range.end));
+        self.corrections.add(range.start, delta.scale(range.start));
+        self.deltas.add(range.start, delta);
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn prefix_sum(&self, index: usize) -> T {
+        assert!(index <= self.len());
+
+        self.deltas.prefix_sum(index).scale(index) - self.corrections.prefix_sum(index)
+    }
+
+    /// Returns the sum of elements in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: RangeAddRangeSum::len
+    pub fn range_sum(&self, range: impl RangeBounds<usize>) -> T {
+        let range = to_range(range, self.len());
+        self.prefix_sum(range.end) - self.prefix_sum(range.start)
+    }
+
+    /// Returns an element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn get(&self, index: usize) -> T {
+        assert!(index < self.len());
+
+        self.range_sum(index..index + 1)
+    }
+}
+
+impl<T> Default for RangeAddRangeSum<T>
+where
+    T: Aggregate + Scale + Clone + Sub<Output = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for RangeAddRangeSum<T>
+where
+    T: Aggregate + Scale + Clone + Sub<Output = T>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deltas = PostfixSegmentTree::new();
+        let mut corrections = PostfixSegmentTree::new();
+        for element in iter {
+            deltas.push(T::identity());
+            corrections.push(T::identity() - element);
+        }
+
+        deltas.push(T::identity());
+        corrections.push(T::identity());
+        Self {
+            deltas,
+            corrections,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_add_point_query_matches_naive() {
+        let mut elements: Vec<i64> = (0..10).map(|i| i * i).collect();
+        let mut values = RangeAddPointQuery::from_iter(elements.iter().copied());
+
+        for step in 0..50i64 {
+            if step % 6 == 0 {
+                values.push(-step);
+                elements.push(-step);
+            }
+
+            let len = elements.len();
+            let start = (step as usize * 7) % len;
+            let end = start + (step as usize * 3) % (len - start + 1);
+            values.range_add(start..end, step - 20);
+            for element in &mut elements[start..end] {
+                *element += step - 20;
+            }
+
+            for (index, element) in elements.iter().enumerate() {
+                assert_eq!(values.get(index), *element);
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_add_range_sum_matches_naive() {
+        let mut elements: Vec<i64> = (0..10).map(|i| i * 3 - 7).collect();
+        let mut values = RangeAddRangeSum::from_iter(elements.iter().copied());
+
+        for step in 0..50i64 {
+            if step % 6 == 0 {
+                values.push(step);
+                elements.push(step);
+            }
+
+            let len = elements.len();
+            let start = (step as usize * 7) % len;
+            let end = start + (step as usize * 3) % (len - start + 1);
+            values.range_add(start..end, step - 20);
+            for element in &mut elements[start..end] {
+                *element += step - 20;
+            }
+
+            for start in 0..=len {
+                for end in start..=len {
+                    assert_eq!(
+                        values.range_sum(start..end),
+                        elements[start..end].iter().sum()
+                    );
+                }
+            }
+        }
+    }
+}
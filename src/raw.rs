@@ -0,0 +1,213 @@
+//! Node-level API for building derived structures, such as lazy tags and custom searches.
+//!
+//! It exposes the index math of [`crate#encoding-layout`] that the tree uses internally,
+//! and mutable access to nodes. It is behind the `raw` feature, and exempt from semver:
+//! breaking changes may be made in minor versions, following changes of the internals.
+//!
+//! Mutable accessors don't keep parents consistent. Call [`PostfixSegmentTree::recalculate`]
+//! on changed ancestors from the lowest level, or check the tree with [`PostfixSegmentTree::validate`].
+//!
+//! # Examples
+//!
+//! ```
+//! use postfix_segment_tree::PostfixSegmentTree;
+//! use postfix_segment_tree::raw::{LeafNodeId, covering_nodes};
+//!
+//! let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+//! let leaf = LeafNodeId::new(1).unwrap();
+//! *tree.raw_node_mut(leaf.into()).unwrap() = 20;
+//! for parent in leaf.parents(tree.len()) {
+//!     tree.recalculate(parent);
+//! }
+//! assert_eq!(tree.prefix_sum(5), 33);
+//!
+//! let widths: Vec<usize> = covering_nodes(1..5).map(|id| id.elements().len()).collect();
+//! assert_eq!(widths, vec![1, 2, 1]);
+//! ```
+
+use std::ops::Range;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::internal::consts;
+use crate::internal::node_id::{self, checked_get_nodes_len_for};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::skipping_iterator::{SkippingIterator, range_iterator};
+use crate::node::NodeAddress;
+
+/// Navigation between nodes, which is exempt from semver like the rest of this module.
+impl NodeAddress {
+    /// Returns the left child, or `None` if this is a leaf node.
+    pub fn left_child(&self) -> Option<NodeAddress> {
+        (self.level() > 0).then(|| node_id::NodeId::from(*self).left_child().into())
+    }
+
+    /// Returns the right child, or `None` if this is a leaf node.
+    pub fn right_child(&self) -> Option<NodeAddress> {
+        (self.level() > 0).then(|| node_id::NodeId::from(*self).right_child().into())
+    }
+}
+
+impl From<LeafNodeId> for NodeAddress {
+    fn from(id: LeafNodeId) -> Self {
+        id.to_internal().with_level(0).into()
+    }
+}
+
+/// An identifier of the leaf node of the element at `index`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LeafNodeId {
+    index: usize,
+}
+
+impl LeafNodeId {
+    /// Returns the leaf node of the element at `index`, or `None` if `index` exceeds the maximum length.
+    pub fn new(index: usize) -> Option<Self> {
+        (index <= consts::MAX_LEN).then_some(Self { index })
+    }
+
+    /// The index of the element.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The index of this node in [`PostfixSegmentTree::as_nodes`].
+    pub fn node_index(&self) -> usize {
+        self.to_internal().node_index()
+    }
+
+    /// The level of the highest node that ends at this element, which is stored right after it.
+    pub fn max_level(&self) -> u32 {
+        self.to_internal().max_level()
+    }
+
+    /// Returns the node of `level` that ends at this element, if it exists.
+    pub fn with_level(&self, level: u32) -> Option<NodeAddress> {
+        NodeAddress::new(self.index, level)
+    }
+
+    /// Iterates over the nodes of a tree of `len` elements that cover this element, from the lowest level.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element is not in the tree.
+    pub fn parents(&self, len: usize) -> impl Iterator<Item = NodeAddress> + use<> {
+        assert!(self.index < len, "index {} is out of bounds", self.index);
+
+        ParentsIterator::new(self.to_internal(), len).map(NodeAddress::from)
+    }
+
+    fn to_internal(self) -> node_id::LeafNodeId {
+        node_id::LeafNodeId::new(self.index)
+    }
+}
+
+/// Returns the number of nodes for `len` elements, or `None` if it overflows.
+pub fn nodes_len_for(len: usize) -> Option<usize> {
+    checked_get_nodes_len_for(len)
+}
+
+/// Iterates over the nodes that cover `range` exactly, in left-to-right order.
+///
+/// It is the decomposition used by [`PostfixSegmentTree::sum`].
+pub fn covering_nodes(range: Range<usize>) -> impl Iterator<Item = NodeAddress> {
+    assert!(
+        range.start <= range.end && range.end <= consts::MAX_LEN,
+        "invalid range {range:?}"
+    );
+
+    range_iterator(range.start, range.end).map(NodeAddress::from)
+}
+
+/// Iterates over the roots of a tree of `len` elements, in left-to-right order.
+///
+/// Roots are the highest nodes that cover all elements without overlaps, and their widths are decreasing.
+pub fn roots(len: usize) -> impl Iterator<Item = NodeAddress> {
+    assert!(len <= consts::MAX_LEN, "capacity overflow");
+
+    SkippingIterator::new(len).map(NodeAddress::from)
+}
+
+impl<T> PostfixSegmentTree<T> {
+    /// Returns the mutable node at `address`, if it exists. Parents are not recalculated.
+    ///
+    /// It is the mutable counterpart of [`PostfixSegmentTree::node`].
+    pub fn raw_node_mut(&mut self, address: NodeAddress) -> Option<&mut T> {
+        if address.index() >= self.len() {
+            return None;
+        }
+
+        Some(&mut self.nodes[address.node_index()])
+    }
+
+    /// Returns all mutable nodes. Parents are not recalculated.
+    pub fn as_nodes_mut(&mut self) -> &mut [T] {
+        &mut self.nodes
+    }
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Recalculates the internal node at `address` from its children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `address` is a leaf node or not in the tree.
+    pub fn recalculate(&mut self, address: NodeAddress) {
+        assert!(address.level() > 0, "leaf nodes can't be recalculated");
+        assert!(
+            address.index() < self.len(),
+            "index {} is out of bounds",
+            address.index()
+        );
+
+        self.recalculate_node(address.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_navigation_matches_layout() {
+        const N: usize = 40;
+        let tree = PostfixSegmentTree::from_iter(0..N as u32);
+        assert_eq!(nodes_len_for(N), Some(tree.nodes_len()));
+        for index in 0..N {
+            let leaf = LeafNodeId::new(index).unwrap();
+            assert_eq!(NodeAddress::from(leaf).node_index(), leaf.node_index());
+            for parent in leaf.parents(N) {
+                let (left, right) = (parent.left_child().unwrap(), parent.right_child().unwrap());
+                assert_eq!(left.elements().end, right.elements().start);
+                assert_eq!(
+                    parent.elements(),
+                    left.elements().start..right.elements().end
+                );
+                assert!(parent.elements().contains(&index));
+                assert_eq!(
+                    tree.node(parent),
+                    Some(&tree.iter_range(parent.elements()).sum())
+                );
+            }
+            for level in 1..=leaf.max_level() {
+                let parent = leaf.with_level(level).unwrap();
+                assert_eq!(parent.right_child(), leaf.with_level(level - 1));
+            }
+            assert_eq!(NodeAddress::from(leaf).left_child(), None);
+            assert_eq!(leaf.with_level(leaf.max_level() + 1), None);
+        }
+
+        let roots: Vec<NodeAddress> = roots(N).collect();
+        assert_eq!(roots.first().unwrap().elements().start, 0);
+        assert_eq!(roots.last().unwrap().elements().end, N);
+        assert!(
+            roots
+                .windows(2)
+                .all(|pair| pair[0].elements().end == pair[1].elements().start)
+        );
+        assert!(covering_nodes(0..N).eq(roots.iter().copied()));
+    }
+}
@@ -0,0 +1,321 @@
+use std::sync::atomic::{
+    AtomicI32, AtomicI64, AtomicIsize, AtomicU32, AtomicU64, AtomicUsize, Ordering, fence,
+};
+
+use crate::internal::node_id::{LeafNodeId, NodeId, get_nodes_len_for};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::skipping_iterator::{IncreasingSkippingIterator, SkippingIterator};
+
+/// An atomic primitive that can be an element of [`RelaxedPostfixSegmentTree`].
+pub trait AtomicElement: Sync {
+    type Value: Copy;
+
+    fn new(value: Self::Value) -> Self;
+    fn load(&self, order: Ordering) -> Self::Value;
+    fn store(&self, value: Self::Value, order: Ordering);
+    fn swap(&self, value: Self::Value, order: Ordering) -> Self::Value;
+    /// Adds `value` with wrap around on overflow, like [`AtomicElement::wrapping_add`].
+    fn fetch_add(&self, value: Self::Value, order: Ordering) -> Self::Value;
+
+    /// The identity of [`AtomicElement::wrapping_add`].
+    fn zero() -> Self::Value;
+    fn wrapping_add(left: Self::Value, right: Self::Value) -> Self::Value;
+    fn wrapping_sub(left: Self::Value, right: Self::Value) -> Self::Value;
+}
+
+macro_rules! impl_atomic_element {
+    ($($atomic:ty => $value:ty),* $(,)?) => {
+        $(
+            impl AtomicElement for $atomic {
+                type Value = $value;
+
+                fn new(value: Self::Value) -> Self {
+                    <$atomic>::new(value)
+                }
+
+                fn load(&self, order: Ordering) -> Self::Value {
+                    <$atomic>::load(self, order)
+                }
+
+                fn store(&self, value: Self::Value, order: Ordering) {
+                    <$atomic>::store(self, value, order)
+                }
+
+                fn swap(&self, value: Self::Value, order: Ordering) -> Self::Value {
+                    <$atomic>::swap(self, value, order)
+                }
+
+                fn fetch_add(&self, value: Self::Value, order: Ordering) -> Self::Value {
+                    <$atomic>::fetch_add(self, value, order)
+                }
+
+                fn zero() -> Self::Value {
+                    0
+                }
+
+                fn wrapping_add(left: Self::Value, right: Self::Value) -> Self::Value {
+                    left.wrapping_add(right)
+                }
+
+                fn wrapping_sub(left: Self::Value, right: Self::Value) -> Self::Value {
+                    left.wrapping_sub(right)
+                }
+            }
+        )*
+    };
+}
+
+impl_atomic_element! {
+    AtomicU32 => u32,
+    AtomicU64 => u64,
+    AtomicUsize => usize,
+    AtomicI32 => i32,
+    AtomicI64 => i64,
+    AtomicIsize => isize,
+}
+
+/// A fixed-length [`PostfixSegmentTree`] of atomic primitives that can be updated and queried through `&self`.
+///
+/// It trades consistency for zero coordination overhead, so it fits to monitoring dashboards
+/// that tolerate slight staleness.
+///
+/// * Every node is accessed with [`Ordering::Relaxed`].
+/// * While an update is in progress, readers may observe aggregates mid-update:
+///   the leaf node may be updated while some of its parents are not yet.
+/// * An update swaps the leaf node and adds the difference to its parents atomically,
+///   so concurrent updates never leave stale parents behind once they return.
+/// * The writer can call [`refresh_fence`] to publish a consistent point.
+///   Readers that observe the new [`epoch`] will observe every update before the fence.
+///
+/// Arithmetic wraps around on overflow.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::AtomicU64;
+/// use postfix_segment_tree::RelaxedPostfixSegmentTree;
+///
+/// let tree: RelaxedPostfixSegmentTree<AtomicU64> = [1, 2, 3].into_iter().collect();
+/// tree.update(1, 4);
+/// tree.refresh_fence();
+///
+/// assert_eq!(tree.epoch(), 1);
+/// assert_eq!(tree.prefix_sum(3), 8);
+/// ```
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+/// [`refresh_fence`]: RelaxedPostfixSegmentTree::refresh_fence
+/// [`epoch`]: RelaxedPostfixSegmentTree::epoch
+pub struct RelaxedPostfixSegmentTree<A> {
+    nodes: Vec<A>,
+    len: usize,
+    epoch: AtomicU64,
+}
+
+impl<A: AtomicElement> RelaxedPostfixSegmentTree<A> {
+    /// Returns the total number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of [`refresh_fence`] calls so far.
+    ///
+    /// It is loaded with [`Ordering::Acquire`], so every update before the observed fence is visible.
+    ///
+    /// [`refresh_fence`]: RelaxedPostfixSegmentTree::refresh_fence
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Acquire)
+    }
+
+    /// Publishes a consistent point for readers.
+    pub fn refresh_fence(&self) {
+        fence(Ordering::Release);
+        self.epoch.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns an element at `index`.
+    ///
+    /// # Time Complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, index: usize) -> Option<A::Value> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let id = LeafNodeId::new(index);
+        Some(self.nodes[id.node_index()].load(Ordering::Relaxed))
+    }
+
+    /// Analogous to `elements[index] = element`.
+    ///
+    /// Readers may observe the parents of `index` mid-update until it returns.
+    /// It can be called from multiple threads at a time.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: RelaxedPostfixSegmentTree::len
+    pub fn update(&self, index: usize, element: A::Value) {
+        assert!(index < self.len());
+
+        let id = LeafNodeId::new(index);
+        let previous = self.nodes[id.node_index()].swap(element, Ordering::Relaxed);
+        let delta = A::wrapping_sub(element, previous);
+
+        for node_id in ParentsIterator::new(id, self.len()) {
+            self.nodes[node_id.node_index()].fetch_add(delta, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`, with relaxed staleness.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn prefix_sum(&self, index: usize) -> A::Value {
+        assert!(index <= self.len());
+
+        let mut sum = A::zero();
+        for id in SkippingIterator::new(index) {
+            sum = A::wrapping_add(sum, self.load_node(id));
+        }
+
+        sum
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`, with relaxed staleness.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `index`)
+    pub fn sum(&self, index: usize, len: usize) -> A::Value {
+        assert!(index <= self.len());
+        assert!(len <= self.len() - index);
+
+        let mut sum = A::zero();
+        let mut iter = SkippingIterator::new(index + len);
+        let pivot = iter.skip_to_pivot(index);
+
+        for id in IncreasingSkippingIterator::new(index, pivot) {
+            sum = A::wrapping_add(sum, self.load_node(id));
+        }
+
+        for id in iter {
+            sum = A::wrapping_add(sum, self.load_node(id));
+        }
+
+        sum
+    }
+
+    fn load_node(&self, id: NodeId) -> A::Value {
+        self.nodes[id.node_index()].load(Ordering::Relaxed)
+    }
+}
+
+impl<A: AtomicElement> FromIterator<A::Value> for RelaxedPostfixSegmentTree<A> {
+    fn from_iter<I: IntoIterator<Item = A::Value>>(iter: I) -> Self {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            len: 0,
+            epoch: AtomicU64::new(0),
+        };
+
+        for element in iter {
+            // nodes are only appended, so parents can be calculated right away.
+            let id = LeafNodeId::new(tree.len);
+            tree.nodes.push(A::new(element));
+            tree.len += 1;
+            for level in 1..=id.max_level() {
+                let node_id = id.with_level(level);
+                let left = tree.load_node(node_id.left_child());
+                let right = tree.load_node(node_id.right_child());
+                tree.nodes.push(A::new(A::wrapping_add(left, right)));
+            }
+        }
+
+        debug_assert_eq!(tree.nodes.len(), get_nodes_len_for(tree.len));
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_sums_after_refresh_fence() {
+        let mut model: Vec<i64> = (0..37).map(|i| (i * 13) % 11 - 5).collect();
+        let tree: RelaxedPostfixSegmentTree<AtomicI64> = model.iter().copied().collect();
+
+        for step in 0..model.len() {
+            let index = (step * 7) % model.len();
+            let element = -(step as i64) * 3;
+            tree.update(index, element);
+            model[index] = element;
+        }
+        tree.refresh_fence();
+
+        assert_eq!(tree.epoch(), 1);
+        for index in 0..=model.len() {
+            assert_eq!(tree.prefix_sum(index), model[..index].iter().sum::<i64>());
+        }
+        for index in 0..model.len() {
+            assert_eq!(tree.get(index), Some(model[index]));
+            for len in 0..=model.len() - index {
+                assert_eq!(
+                    tree.sum(index, len),
+                    model[index..index + len].iter().sum::<i64>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrent_readers_and_writers() {
+        const LEN: usize = 100;
+        const WRITERS: usize = 4;
+        const ROUNDS: u64 = 200;
+
+        let tree: RelaxedPostfixSegmentTree<AtomicU64> = std::iter::repeat_n(0, LEN).collect();
+
+        thread::scope(|scope| {
+            // Writers own disjoint leaves, but they share parents.
+            for writer in 0..WRITERS {
+                let tree = &tree;
+                scope.spawn(move || {
+                    for round in 1..=ROUNDS {
+                        for index in (writer..LEN).step_by(WRITERS) {
+                            tree.update(index, round);
+                        }
+                    }
+                });
+            }
+
+            for _ in 0..2 {
+                let tree = &tree;
+                scope.spawn(move || {
+                    // Every node only grows, so no sum can exceed the final one.
+                    for _ in 0..ROUNDS {
+                        for index in 0..=LEN {
+                            assert!(tree.prefix_sum(index) <= index as u64 * ROUNDS);
+                        }
+                    }
+                });
+            }
+        });
+        tree.refresh_fence();
+
+        for index in 0..=LEN {
+            assert_eq!(tree.prefix_sum(index), index as u64 * ROUNDS);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+
+/// A ring of [`PostfixSegmentTree`]s for time-partitioned retention windows.
+///
+/// Time is split into buckets of `bucket_duration`, and each bucket is split into `slots_per_bucket` slots.
+/// It retains the latest `buckets` buckets, and the oldest bucket is cleared as time advances into a new bucket.
+/// Each bucket is backed by a tree of slots, so a partial bucket can be queried in *O*(log *slots*),
+/// and totals of buckets are backed by another tree, so a range of buckets can be queried in *O*(log *buckets*).
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::RollingWindows;
+///
+/// // 3 buckets of 60 seconds, with 6 slots of 10 seconds for each bucket
+/// let mut windows = RollingWindows::new(3, 60, 6);
+/// windows.record(5, 1);
+/// windows.record(70, 2);
+/// windows.record(135, 4);
+/// windows.record(150, 8);
+///
+/// // the last 1 bucket (60..120) and the current bucket until 140 (120..140)
+/// assert_eq!(windows.sum_last(1, 140), 2 + 4);
+/// assert_eq!(windows.sum_last(2, 179), 1 + 2 + 4 + 8);
+///
+/// // advancing to 180..240 clears the oldest bucket 0..60
+/// windows.record(185, 16);
+/// assert_eq!(windows.sum_last(2, 239), 2 + 4 + 8 + 16);
+/// ```
+pub struct RollingWindows<T> {
+    buckets: Vec<PostfixSegmentTree<T>>,
+    totals: PostfixSegmentTree<T>,
+    bucket_duration: u64,
+    slot_duration: u64,
+    head: u64,
+}
+
+impl<T> RollingWindows<T>
+where
+    T: Aggregate + Clone,
+{
+    /// Creates `buckets` buckets of `bucket_duration`, each of which has `slots_per_bucket` slots.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets` or `slots_per_bucket` is 0, or `bucket_duration` is not divisible by `slots_per_bucket`.
+    pub fn new(buckets: usize, bucket_duration: u64, slots_per_bucket: usize) -> Self {
+        assert!(buckets > 0);
+        assert!(slots_per_bucket > 0);
+        assert!(bucket_duration > 0 && bucket_duration.is_multiple_of(slots_per_bucket as u64));
+
+        Self {
+            buckets: (0..buckets).map(|_| identities(slots_per_bucket)).collect(),
+            totals: identities(buckets),
+            bucket_duration,
+            slot_duration: bucket_duration / slots_per_bucket as u64,
+            head: 0,
+        }
+    }
+
+    /// Returns the number of the current bucket, which is `time / bucket_duration` of the latest time.
+    pub fn current_bucket(&self) -> u64 {
+        self.head
+    }
+
+    /// Advances the current bucket to the one of `time`, and clears buckets that fall out of the retention.
+    ///
+    /// It does nothing if `time` is in the current bucket or before.
+    pub fn advance_to(&mut self, time: u64) {
+        let bucket = time / self.bucket_duration;
+        if bucket <= self.head {
+            return;
+        }
+
+        let cleared = u64::min(bucket - self.head, self.buckets.len() as u64);
+        for number in bucket + 1 - cleared..=bucket {
+            let position = self.position(number);
+            self.buckets[position] = identities(self.buckets[position].len());
+            self.totals.update(position, T::identity());
+        }
+
+        self.head = bucket;
+    }
+
+    /// Combines `value` into the slot of `time`, advancing the current bucket if needed.
+    ///
+    /// Returns `false` without recording if `time` is older than the retention.
+    pub fn record(&mut self, time: u64, value: T) -> bool {
+        self.advance_to(time);
+
+        let bucket = time / self.bucket_duration;
+        if !self.is_retained(bucket) {
+            return false;
+        }
+
+        let position = self.position(bucket);
+        let slot = self.slot(time);
+
+        let tree = &mut self.buckets[position];
+        tree.update_with(slot, |element| element.combine(&value));
+
+        let total = tree.sum(0, tree.len());
+        self.totals.update(position, total);
+        true
+    }
+
+    /// Returns the sum over the `k` buckets before the bucket of `now`,
+    /// plus the partial bucket of `now` until the end of the slot of `now`.
+    ///
+    /// Buckets that are out of the retention are regarded as empty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *buckets* + log *slots*)
+    pub fn sum_last(&self, k: usize, now: u64) -> T {
+        let bucket = now / self.bucket_duration;
+
+        let mut sum = self.sum_buckets(bucket.saturating_sub(k as u64), bucket);
+        if self.is_retained(bucket) {
+            let partial = self.buckets[self.position(bucket)].prefix_sum(self.slot(now) + 1);
+            sum.combine(&partial);
+        }
+
+        sum
+    }
+
+    /// Returns the sum of complete buckets numbered `start..end`.
+    fn sum_buckets(&self, start: u64, end: u64) -> T {
+        let retained_start = (self.head + 1).saturating_sub(self.buckets.len() as u64);
+        let start = u64::max(start, retained_start);
+        let end = u64::min(end, self.head + 1);
+        if start >= end {
+            return T::identity();
+        }
+
+        // the range may wrap around the ring
+        let first = self.position(start);
+        let count = (end - start) as usize;
+        if first + count <= self.buckets.len() {
+            self.totals.sum(first, count)
+        } else {
+            let mut sum = self.totals.postfix_sum(first);
+            sum.combine(&self.totals.prefix_sum(first + count - self.buckets.len()));
+            sum
+        }
+    }
+
+    fn is_retained(&self, bucket: u64) -> bool {
+        bucket <= self.head && bucket + self.buckets.len() as u64 > self.head
+    }
+
+    fn position(&self, bucket: u64) -> usize {
+        (bucket % self.buckets.len() as u64) as usize
+    }
+
+    fn slot(&self, time: u64) -> usize {
+        ((time % self.bucket_duration) / self.slot_duration) as usize
+    }
+}
+
+fn identities<T: Aggregate + Clone>(len: usize) -> PostfixSegmentTree<T> {
+    PostfixSegmentTree::from_iter((0..len).map(|_| T::identity()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_windows_matches_naive() {
+        const BUCKETS: usize = 4;
+        const BUCKET_DURATION: u64 = 12;
+        const SLOTS: usize = 3;
+
+        let mut windows = RollingWindows::new(BUCKETS, BUCKET_DURATION, SLOTS);
+        let mut events: Vec<(u64, u64)> = Vec::new();
+        let mut time = 0;
+        for step in 0..200u64 {
+            // sometimes jump far ahead to skip buckets
+            time += if step % 37 == 0 { 50 } else { step % 5 };
+            let value = step + 1;
+            assert!(windows.record(time, value));
+            events.push((time, value));
+
+            // old events are rejected
+            if time >= BUCKET_DURATION * BUCKETS as u64 {
+                assert!(!windows.record(time - BUCKET_DURATION * BUCKETS as u64, 1000));
+            }
+
+            let head = time / BUCKET_DURATION;
+            for k in 0..BUCKETS {
+                for now in head * BUCKET_DURATION..(head + 1) * BUCKET_DURATION {
+                    let slot_end = (now / 4 + 1) * 4;
+                    let start = (head.saturating_sub(k as u64)) * BUCKET_DURATION;
+                    let expected: u64 = events
+                        .iter()
+                        .filter(|&&(t, _)| t >= start && t < slot_end)
+                        .map(|&(_, v)| v)
+                        .sum();
+                    assert_eq!(windows.sum_last(k, now), expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::fmt;
+use std::ops::{AddAssign, Range, RangeBounds};
+
+use crate::PostfixSegmentTree;
+use crate::internal::range::to_range;
+
+/// The maximum length of a chunk in bytes, unless a single character is longer.
+const MAX_CHUNK_LEN: usize = 512;
+
+/// Lengths of a chunk of text, which are summed in the tree of [`TreeString`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+struct TextLen {
+    bytes: usize,
+    chars: usize,
+}
+
+impl TextLen {
+    fn of(text: &str) -> Self {
+        Self {
+            bytes: text.len(),
+            chars: text.chars().count(),
+        }
+    }
+}
+
+impl AddAssign<&TextLen> for TextLen {
+    fn add_assign(&mut self, rhs: &TextLen) {
+        self.bytes += rhs.bytes;
+        self.chars += rhs.chars;
+    }
+}
+
+/// A UTF-8 rope, which stores text in chunks with their byte and char lengths in a [`PostfixSegmentTree`].
+///
+/// A byte or char offset is located by descending the tree over lengths, which is *O*(log *chunks*).
+/// An edit within a chunk only updates the lengths of the chunk, so it is *O*(log *chunks* + *chunk*).
+/// When a chunk overflows or chunks are removed, chunks after it are shifted like [`PostfixSegmentTree::insert_slice`],
+/// which is *O*(*chunks*), but chunks are large enough that it is rare and cheap.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::TreeString;
+///
+/// let mut text = TreeString::from("hello world");
+/// text.insert_str(5, ", wonderful");
+/// assert_eq!(text.to_string(), "hello, wonderful world");
+///
+/// text.remove_range(5..16);
+/// assert_eq!(text.to_string(), "hello world");
+///
+/// let mut text = TreeString::from("añb");
+/// assert_eq!(text.len_bytes(), 4);
+/// assert_eq!(text.len_chars(), 3);
+/// assert_eq!(text.byte_to_char(3), 2);
+/// assert_eq!(text.char_to_byte(2), 3);
+/// ```
+pub struct TreeString {
+    chunks: Vec<String>,
+    lens: PostfixSegmentTree<TextLen>,
+}
+
+impl TreeString {
+    /// Creates an empty string.
+    pub fn new() -> Self {
+        Self {
+            chunks: Vec::new(),
+            lens: PostfixSegmentTree::new(),
+        }
+    }
+
+    /// Returns the length in bytes.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *chunks*)
+    pub fn len_bytes(&self) -> usize {
+        self.total().bytes
+    }
+
+    /// Returns the length in chars.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *chunks*)
+    pub fn len_chars(&self) -> usize {
+        self.total().chars
+    }
+
+    /// Returns `true` if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Inserts `text` at the byte offset `byte`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is out of bounds or not on a char boundary.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *chunks* + *chunk* + `text.len()`) if the chunk doesn't overflow,
+    /// otherwise *O*(*chunks* + `text.len()`)
+    pub fn insert_str(&mut self, byte: usize, text: &str) {
+        assert!(byte <= self.len_bytes(), "byte {byte} is out of bounds");
+        if text.is_empty() {
+            return;
+        }
+
+        if self.chunks.is_empty() {
+            self.insert_chunks(0, split_chunks(text));
+            return;
+        }
+
+        // insert at the end of the previous chunk, so appending doesn't create a new chunk
+        let (index, offset) = match self.locate(byte) {
+            (index, 0) if index > 0 => (index - 1, self.chunks[index - 1].len()),
+            (index, offset) if index == self.chunks.len() => (index - 1, offset),
+            located => located,
+        };
+        let chunk = &mut self.chunks[index];
+        assert!(
+            chunk.is_char_boundary(offset),
+            "byte {byte} is not on a char boundary"
+        );
+
+        if chunk.len() + text.len() <= MAX_CHUNK_LEN {
+            chunk.insert_str(offset, text);
+            self.lens.update(index, TextLen::of(chunk));
+            return;
+        }
+
+        if offset == 0 {
+            // inserting before the first chunk, which would be left empty by splitting it
+            self.insert_chunks(index, split_chunks(text));
+            return;
+        }
+
+        let tail = chunk.split_off(offset);
+        self.lens.update(index, TextLen::of(chunk));
+        let mut rest = String::with_capacity(text.len() + tail.len());
+        rest.push_str(text);
+        rest.push_str(&tail);
+        self.insert_chunks(index + 1, split_chunks(&rest));
+    }
+
+    /// Removes text in the byte range `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or not on char boundaries.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *chunks* + *chunk*) if the range is within a chunk, otherwise *O*(*chunks*)
+    pub fn remove_range(&mut self, range: impl RangeBounds<usize>) {
+        let range = to_range(range, self.len_bytes());
+        if range.is_empty() {
+            return;
+        }
+
+        let (first, start) = self.locate(range.start);
+        // the chunk that contains the last removed byte
+        let last = self
+            .lens
+            .partition_point_by_prefix(|len| len.bytes < range.end);
+        let end = range.end - self.lens.prefix_sum(last).bytes;
+        assert!(
+            self.chunks[first].is_char_boundary(start) && self.chunks[last].is_char_boundary(end),
+            "range {range:?} is not on char boundaries"
+        );
+
+        if first == last {
+            self.chunks[first].replace_range(start..end, "");
+        } else {
+            self.chunks[first].truncate(start);
+            self.chunks[last].replace_range(..end, "");
+            self.lens.update(last, TextLen::of(&self.chunks[last]));
+            self.remove_chunks(first + 1..last);
+        }
+
+        // remove empty chunks, so every chunk has at least a char
+        for index in [first + 1, first] {
+            let Some(chunk) = self.chunks.get(index) else {
+                continue;
+            };
+
+            if chunk.is_empty() {
+                self.remove_chunks(index..index + 1);
+            } else {
+                self.lens.update(index, TextLen::of(chunk));
+            }
+        }
+    }
+
+    /// Converts the byte offset `byte` to the char offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is out of bounds or not on a char boundary.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *chunks* + *chunk*)
+    pub fn byte_to_char(&self, byte: usize) -> usize {
+        assert!(byte <= self.len_bytes(), "byte {byte} is out of bounds");
+
+        let (index, offset) = self.locate(byte);
+        let before = self.lens.prefix_sum(index).chars;
+        match self.chunks.get(index) {
+            Some(chunk) => before + chunk[..offset].chars().count(),
+            None => before,
+        }
+    }
+
+    /// Converts the char offset `char` to the byte offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char` is out of bounds.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *chunks* + *chunk*)
+    pub fn char_to_byte(&self, char: usize) -> usize {
+        assert!(char <= self.len_chars(), "char {char} is out of bounds");
+
+        let index = self.lens.partition_point_by_prefix(|len| len.chars <= char);
+        let before = self.lens.prefix_sum(index);
+        match self.chunks.get(index) {
+            Some(chunk) => {
+                let offset = chunk
+                    .char_indices()
+                    .nth(char - before.chars)
+                    .map_or(chunk.len(), |(offset, _)| offset);
+                before.bytes + offset
+            }
+            None => before.bytes,
+        }
+    }
+
+    /// Returns an iterator over chunks of the text in order.
+    pub fn chunks(&self) -> impl Iterator<Item = &str> {
+        self.chunks.iter().map(String::as_str)
+    }
+
+    fn total(&self) -> TextLen {
+        self.lens.prefix_sum(self.lens.len())
+    }
+
+    /// Returns the index of the chunk that contains the byte offset `byte`, and the offset in the chunk.
+    /// The end of the text is `(chunks, 0)`.
+    fn locate(&self, byte: usize) -> (usize, usize) {
+        let index = self.lens.partition_point_by_prefix(|len| len.bytes <= byte);
+        (index, byte - self.lens.prefix_sum(index).bytes)
+    }
+
+    fn insert_chunks(&mut self, index: usize, chunks: Vec<String>) {
+        let lens: Vec<TextLen> = chunks.iter().map(|chunk| TextLen::of(chunk)).collect();
+        self.lens.insert_slice(index, &lens);
+        self.chunks.splice(index..index, chunks);
+    }
+
+    fn remove_chunks(&mut self, range: Range<usize>) {
+        self.lens.remove_range(range.clone());
+        self.chunks.drain(range);
+    }
+}
+
+/// Splits `text` into chunks of at most [`MAX_CHUNK_LEN`] bytes on char boundaries.
+fn split_chunks(mut text: &str) -> Vec<String> {
+    let mut chunks = Vec::with_capacity(text.len().div_ceil(MAX_CHUNK_LEN));
+    while !text.is_empty() {
+        let mut end = usize::min(MAX_CHUNK_LEN, text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            // a char can't be longer than 4 bytes
+            end = text.chars().next().map_or(text.len(), char::len_utf8);
+        }
+
+        let (chunk, rest) = text.split_at(end);
+        chunks.push(chunk.to_string());
+        text = rest;
+    }
+
+    chunks
+}
+
+impl Default for TreeString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&str> for TreeString {
+    fn from(text: &str) -> Self {
+        let mut string = Self::new();
+        string.insert_chunks(0, split_chunks(text));
+        string
+    }
+}
+
+impl fmt::Display for TreeString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in &self.chunks {
+            f.write_str(chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_string_matches_string() {
+        let mut model = String::new();
+        let mut text = TreeString::new();
+        for step in 0..300usize {
+            let len = model.len();
+            if step % 3 == 2 && len > 0 {
+                let mut start = (step * 131) % len;
+                while !model.is_char_boundary(start) {
+                    start -= 1;
+                }
+                let mut end = usize::min(start + (step * 17) % 700, len);
+                while !model.is_char_boundary(end) {
+                    end -= 1;
+                }
+                text.remove_range(start..end);
+                model.replace_range(start..end, "");
+            } else {
+                let mut byte = (step * 97) % (len + 1);
+                while !model.is_char_boundary(byte) {
+                    byte -= 1;
+                }
+                let inserted = "añ€😀b".repeat(step % 40);
+                text.insert_str(byte, &inserted);
+                model.insert_str(byte, &inserted);
+            }
+
+            assert_eq!(text.to_string(), model);
+            assert_eq!(text.len_bytes(), model.len());
+            assert_eq!(text.len_chars(), model.chars().count());
+            assert!(text.chunks().all(|chunk| !chunk.is_empty()));
+            for (char, (byte, _)) in model.char_indices().enumerate().step_by(7) {
+                assert_eq!(text.byte_to_char(byte), char);
+                assert_eq!(text.char_to_byte(char), byte);
+            }
+            assert_eq!(text.byte_to_char(model.len()), model.chars().count());
+        }
+    }
+
+    #[test]
+    fn test_insert_at_start_of_full_chunk() {
+        let model = "a".repeat(MAX_CHUNK_LEN);
+        let mut text = TreeString::from(model.as_str());
+        text.insert_str(0, "b");
+
+        assert_eq!(text.to_string(), format!("b{model}"));
+        assert!(text.chunks().all(|chunk| !chunk.is_empty()));
+    }
+
+    #[test]
+    fn test_remove_whole_chunks() {
+        let model = "a".repeat(MAX_CHUNK_LEN * 3);
+        for start in [0, MAX_CHUNK_LEN, MAX_CHUNK_LEN * 2] {
+            let mut text = TreeString::from(model.as_str());
+            text.remove_range(start..);
+            assert_eq!(text.to_string(), model[..start]);
+            assert_eq!(text.chunks().count(), start / MAX_CHUNK_LEN);
+
+            let mut text = TreeString::from(model.as_str());
+            text.remove_range(..start + MAX_CHUNK_LEN);
+            assert_eq!(text.len_bytes(), model.len() - start - MAX_CHUNK_LEN);
+            assert!(text.chunks().all(|chunk| !chunk.is_empty()));
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+use crate::internal::node_id::NodeId;
+use crate::internal::skipping_iterator::SkippingIterator;
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate + Clone + PartialOrd,
+{
+    /// Returns the element index where each of the cumulative `boundaries` is crossed.
+    ///
+    /// For each `boundary`, it is the smallest `index` that `self.prefix_sum(index + 1) >= boundary`,
+    /// or [`len`] if no such `index` exists.
+    /// It is a batched form of the lower bound query on prefix sums,
+    /// so elements are expected to be non-negative and `boundaries` are expected to be ascending.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// // prefix sums:                           1, 3, 6, 10
+    /// assert_eq!(tree.bucket_indices(&[0, 1, 2, 3, 7, 10, 11]), vec![0, 0, 1, 1, 3, 3, 4]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*k* log [`len`]) where *k* is `boundaries.len()`
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn bucket_indices(&self, boundaries: &[T]) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(boundaries.len());
+
+        // roots of the full binary trees, the leftmost one on the top.
+        let mut stack: Vec<NodeId> = SkippingIterator::new(self.len()).collect();
+        stack.reverse();
+
+        let mut sum = T::identity();
+        for boundary in boundaries {
+            // the sweep resumes from where the previous boundary was crossed.
+            while let Some(&id) = stack.last() {
+                let mut candidate = sum.clone();
+                candidate.combine(self.get_node(id));
+                if candidate < *boundary {
+                    // the boundary is not crossed within `id`
+                    sum = candidate;
+                    stack.pop();
+                    continue;
+                }
+
+                if id.level() == 0 {
+                    break;
+                }
+
+                // the boundary is crossed within `id`, descend to find the exact leaf.
+                stack.pop();
+                stack.push(id.right_child());
+                stack.push(id.left_child());
+            }
+
+            let index = stack.last().map_or(self.len(), |id| id.index());
+            indices.push(index);
+        }
+
+        indices
+    }
+
+    /// Returns the smallest `index` that `self.prefix_sum(index + 1) >= target`, or [`len`] if no such `index` exists.
+    ///
+    /// Elements are expected to be non-negative, so prefix sums are ascending.
+    /// It descends the tree once, rather than binary searching over [`prefix_sum`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 0, 3]);
+    /// // prefix sums:                           1, 3, 3, 6
+    /// assert_eq!(tree.lower_bound(&0), 0);
+    /// assert_eq!(tree.lower_bound(&3), 1);
+    /// assert_eq!(tree.lower_bound(&4), 3);
+    /// assert_eq!(tree.lower_bound(&7), 4);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    /// [`prefix_sum`]: PostfixSegmentTree::prefix_sum
+    pub fn lower_bound(&self, target: &T) -> usize {
+        self.search_prefix(|sum| sum >= target)
+    }
+
+    /// Returns the smallest `index` that `self.prefix_sum(index + 1) > target`, or [`len`] if no such `index` exists.
+    ///
+    /// Elements are expected to be non-negative, so prefix sums are ascending.
+    /// It is the element that contains the offset `target`, when elements are lengths of consecutive segments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 0, 3]);
+    /// // prefix sums:                           1, 3, 3, 6
+    /// assert_eq!(tree.upper_bound(&0), 0);
+    /// assert_eq!(tree.upper_bound(&3), 3);
+    /// assert_eq!(tree.upper_bound(&6), 4);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn upper_bound(&self, target: &T) -> usize {
+        self.search_prefix(|sum| sum > target)
+    }
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate + Clone + PartialOrd,
+{
+    /// Returns the index of the bucket that contains the `k`-th item (0-based), when elements are counts of items,
+    /// or `None` if there are no more than `k` items.
+    ///
+    /// Items are ordered by their buckets, so it is the smallest `index` that `self.prefix_sum(index + 1) > k`.
+    /// Empty buckets are never returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// // items: [0, 0, 2, 2, 2]
+    /// let counts = PostfixSegmentTree::from_iter([2, 0, 3]);
+    /// assert_eq!(counts.kth(&0), Some(0));
+    /// assert_eq!(counts.kth(&1), Some(0));
+    /// assert_eq!(counts.kth(&2), Some(2));
+    /// assert_eq!(counts.kth(&5), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn kth(&self, k: &T) -> Option<usize> {
+        let index = self.upper_bound(k);
+        (index < self.len()).then_some(index)
+    }
+
+    /// Returns the index of the bucket that contains the `p`-quantile of items, when elements are integer counts of items,
+    /// or `None` if there are no items.
+    ///
+    /// It follows the nearest-rank method: the `ceil(p * total)`-th item (1-based) is selected, and `p = 0` selects the first item.
+    /// So `quantile(0.5)` of `[0, 0, 1, 1]` items is `0`, and ties are broken toward the lower bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in `0.0..=1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// // items: [0, 0, 1, 1, 3, 3, 3, 3]
+    /// let counts = PostfixSegmentTree::from_iter([2u64, 2, 0, 4]);
+    /// assert_eq!(counts.quantile(0.0), Some(0));
+    /// assert_eq!(counts.quantile(0.25), Some(0));
+    /// assert_eq!(counts.quantile(0.5), Some(1));
+    /// assert_eq!(counts.quantile(0.51), Some(3));
+    /// assert_eq!(counts.quantile(1.0), Some(3));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn quantile(&self, p: f64) -> Option<usize>
+    where
+        T: TryInto<u64> + TryFrom<u64>,
+    {
+        assert!((0.0..=1.0).contains(&p), "quantile {p} is out of 0.0..=1.0");
+
+        let total: u64 = self.sum(0, self.len()).try_into().ok()?;
+        if total == 0 {
+            return None;
+        }
+
+        let rank = ((p * total as f64).ceil() as u64).clamp(1, total);
+        self.kth(&T::try_from(rank - 1).ok()?)
+    }
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate + Clone,
+{
+    /// Returns the smallest `index` that `pred(&self.prefix_sum(index + 1))` is `false`,
+    /// or [`len`] if no such `index` exists, like [`slice::partition_point`] over prefix sums.
+    ///
+    /// `pred` is expected to be monotonic over prefix sums, which means that it is `true` for prefix sums of a prefix of indices
+    /// and `false` for the rest. It descends the tree once with partial sums accumulated from the left,
+    /// so it works for any [`Aggregate`] whose prefix sums are monotonic for `pred`, such as composite aggregates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::{Max, PostfixSegmentTree};
+    ///
+    /// // the first index whose running maximum exceeds 4
+    /// let tree = PostfixSegmentTree::from_iter([3, 1, 4, 5, 9, 2].map(Max::new));
+    /// assert_eq!(tree.partition_point_by_prefix(|max| max.0 <= Some(4)), 3);
+    /// assert_eq!(tree.partition_point_by_prefix(|max| max.0 <= Some(9)), 6);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn partition_point_by_prefix(&self, mut pred: impl FnMut(&T) -> bool) -> usize {
+        self.search_prefix(|sum| !pred(sum))
+    }
+
+    /// Returns the smallest `index` that `crossed(&self.prefix_sum(index + 1))` is `true`,
+    /// or [`len`] if no such `index` exists.
+    ///
+    /// `crossed` is expected to be monotonic over prefix sums.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`])
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub(crate) fn search_prefix(&self, mut crossed: impl FnMut(&T) -> bool) -> usize {
+        let mut sum = T::identity();
+        let mut stack: Vec<NodeId> = SkippingIterator::new(self.len()).collect();
+        stack.reverse();
+
+        while let Some(id) = stack.pop() {
+            let mut candidate = sum.clone();
+            candidate.combine(self.get_node(id));
+            if !crossed(&candidate) {
+                sum = candidate;
+                continue;
+            }
+
+            if id.level() == 0 {
+                return id.index();
+            }
+
+            stack.push(id.right_child());
+            stack.push(id.left_child());
+        }
+
+        self.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PostfixSegmentTree;
+
+    #[test]
+    fn test_bucket_indices_matches_linear_scan() {
+        fn linear(elements: &[u32], boundary: u32) -> usize {
+            let mut sum = 0;
+            for (index, element) in elements.iter().enumerate() {
+                sum += element;
+                if sum >= boundary {
+                    return index;
+                }
+            }
+
+            elements.len()
+        }
+
+        const N: usize = 40;
+        for len in 0..N {
+            let elements: Vec<u32> = (0..len as u32).map(|i| i % 3).collect();
+            let tree = PostfixSegmentTree::from_iter(elements.iter().copied());
+
+            let total: u32 = elements.iter().sum();
+            let boundaries: Vec<u32> = (0..=total + 1).collect();
+            let expected: Vec<usize> = boundaries.iter().map(|&b| linear(&elements, b)).collect();
+            assert_eq!(tree.bucket_indices(&boundaries), expected);
+        }
+    }
+
+    #[test]
+    fn test_bounds_match_linear_scan() {
+        const N: usize = 40;
+        for len in 0..N {
+            let elements: Vec<u32> = (0..len as u32).map(|i| i % 3).collect();
+            let tree = PostfixSegmentTree::from_iter(elements.iter().copied());
+            let prefix_sums: Vec<u32> = (1..=len)
+                .map(|index| elements[..index].iter().sum())
+                .collect();
+
+            let total: u32 = elements.iter().sum();
+            for target in 0..=total + 1 {
+                let lower = prefix_sums.iter().position(|&sum| sum >= target);
+                let upper = prefix_sums.iter().position(|&sum| sum > target);
+                assert_eq!(tree.lower_bound(&target), lower.unwrap_or(len));
+                assert_eq!(tree.upper_bound(&target), upper.unwrap_or(len));
+            }
+        }
+    }
+
+    #[test]
+    fn test_partition_point_by_prefix_matches_linear_scan() {
+        const N: usize = 40;
+        for len in 0..N {
+            let elements: Vec<u32> = (0..len as u32).map(|i| (i * 7) % 5).collect();
+            let tree = PostfixSegmentTree::from_iter(elements.iter().copied());
+
+            let total: u32 = elements.iter().sum();
+            for limit in 0..=total + 1 {
+                let expected = (0..len)
+                    .find(|&index| elements[..=index].iter().sum::<u32>() > limit)
+                    .unwrap_or(len);
+                assert_eq!(
+                    tree.partition_point_by_prefix(|&sum| sum <= limit),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantile_matches_sorted_items() {
+        const N: usize = 20;
+        for len in 0..N {
+            let counts: Vec<u64> = (0..len as u64).map(|i| (i * 5) % 4).collect();
+            let tree = PostfixSegmentTree::from_iter(counts.iter().copied());
+
+            let items: Vec<usize> = (0..len)
+                .flat_map(|index| std::iter::repeat_n(index, counts[index] as usize))
+                .collect();
+            for (k, item) in items.iter().enumerate() {
+                assert_eq!(tree.kth(&(k as u64)), Some(*item));
+            }
+            assert_eq!(tree.kth(&(items.len() as u64)), None);
+
+            for percent in 0..=100 {
+                let p = percent as f64 / 100.0;
+                let rank = ((p * items.len() as f64).ceil() as usize).max(1);
+                assert_eq!(tree.quantile(p), items.get(rank - 1).copied());
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! The tree itself serializes as a sequence of its elements.
+//! See [`nodes`] for a larger representation that loads without recombining nodes.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+
+/// Serializes only the elements as a sequence, since internal nodes are derived from them.
+impl<T> Serialize for PostfixSegmentTree<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Deserializes a sequence of elements, and rebuilds internal nodes in *O*(*n*) with [`from_vec`].
+///
+/// [`from_vec`]: PostfixSegmentTree::from_vec
+impl<'de, T> Deserialize<'de> for PostfixSegmentTree<T>
+where
+    T: Deserialize<'de> + Combine + Clone,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer).map(Self::from_vec)
+    }
+}
+
+/// Serializes the full nodes and the number of elements, for `#[serde(with = "postfix_segment_tree::serde::nodes")]`.
+///
+/// It is about twice as large as the elements, but loading it only checks nodes with [`from_raw_parts`]
+/// instead of rebuilding them, which suits very large trees.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PostfixSegmentTree;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Counters {
+///     #[serde(with = "postfix_segment_tree::serde::nodes")]
+///     tree: PostfixSegmentTree<u32>,
+/// }
+///
+/// let counters = Counters { tree: PostfixSegmentTree::from_iter([1, 2, 3]) };
+/// let json = serde_json::to_string(&counters).unwrap();
+/// assert_eq!(json, r#"{"tree":[[1,2,3,3],3]}"#);
+///
+/// let loaded: Counters = serde_json::from_str(&json).unwrap();
+/// assert_eq!(loaded.tree.prefix_sum(3), 6);
+///
+/// // nodes that don't match their children are rejected
+/// assert!(serde_json::from_str::<Counters>(r#"{"tree":[[1,2,4,3],3]}"#).is_err());
+/// ```
+///
+/// [`from_raw_parts`]: PostfixSegmentTree::from_raw_parts
+pub mod nodes {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::PostfixSegmentTree;
+    use crate::aggregate::Combine;
+
+    /// Serializes `tree` as a pair of its nodes and the number of elements.
+    pub fn serialize<T, S>(tree: &PostfixSegmentTree<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        (tree.as_nodes(), tree.len()).serialize(serializer)
+    }
+
+    /// Deserializes a pair of nodes and the number of elements, and checks them in *O*(*n*) without rebuilding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with the [`ValidationError`] if the nodes aren't a valid tree.
+    ///
+    /// [`ValidationError`]: crate::ValidationError
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<PostfixSegmentTree<T>, D::Error>
+    where
+        T: Deserialize<'de> + Combine + Clone + PartialEq,
+        D: Deserializer<'de>,
+    {
+        let (nodes, len) = <(Vec<T>, usize)>::deserialize(deserializer)?;
+        PostfixSegmentTree::from_raw_parts(nodes, len).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::IntoDeserializer;
+    use serde::de::value::Error;
+    use serde_test::{Token, assert_de_tokens_error, assert_ser_tokens, assert_tokens};
+
+    use super::*;
+
+    struct Nodes(PostfixSegmentTree<i32>);
+
+    impl Serialize for Nodes {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            nodes::serialize(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Nodes {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            nodes::deserialize(deserializer).map(Nodes)
+        }
+    }
+
+    impl PartialEq for Nodes {
+        fn eq(&self, other: &Self) -> bool {
+            self.0.as_nodes() == other.0.as_nodes() && self.0.len() == other.0.len()
+        }
+    }
+
+    impl std::fmt::Debug for Nodes {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_tuple("Nodes").field(&self.0.as_nodes()).finish()
+        }
+    }
+
+    #[test]
+    fn test_serialize_only_elements() {
+        let tree = PostfixSegmentTree::from_iter([3, 1, 4]);
+        assert_ser_tokens(
+            &tree,
+            &[
+                Token::Seq { len: Some(3) },
+                Token::I32(3),
+                Token::I32(1),
+                Token::I32(4),
+                Token::SeqEnd,
+            ],
+        );
+
+        let empty = PostfixSegmentTree::<i32>::new();
+        assert_ser_tokens(&empty, &[Token::Seq { len: Some(0) }, Token::SeqEnd]);
+    }
+
+    #[test]
+    fn test_deserialize_rebuilds_nodes() {
+        let elements: Vec<i64> = (0..100).map(|i| (i * 37) % 23 - 11).collect();
+        let tree = PostfixSegmentTree::from_iter(elements.iter().copied());
+
+        let deserializer = IntoDeserializer::<Error>::into_deserializer(elements.clone());
+        let loaded = PostfixSegmentTree::<i64>::deserialize(deserializer).unwrap();
+
+        assert_eq!(loaded.len(), tree.len());
+        assert_eq!(loaded.nodes_len(), tree.nodes_len());
+        for index in 0..=elements.len() {
+            assert_eq!(loaded.prefix_sum(index), tree.prefix_sum(index));
+        }
+    }
+
+    #[test]
+    fn test_nodes_round_trip() {
+        let tree = PostfixSegmentTree::from_iter([3, 1, 4]);
+        assert_tokens(
+            &Nodes(tree),
+            &[
+                Token::Tuple { len: 2 },
+                Token::Seq { len: Some(4) },
+                Token::I32(3),
+                Token::I32(1),
+                Token::I32(4),
+                Token::I32(4),
+                Token::SeqEnd,
+                Token::U64(3),
+                Token::TupleEnd,
+            ],
+        );
+        assert_tokens(
+            &Nodes(PostfixSegmentTree::new()),
+            &[
+                Token::Tuple { len: 2 },
+                Token::Seq { len: Some(0) },
+                Token::SeqEnd,
+                Token::U64(0),
+                Token::TupleEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_nodes_rejects_invalid_trees() {
+        assert_de_tokens_error::<Nodes>(
+            &[
+                Token::Tuple { len: 2 },
+                Token::Seq { len: Some(3) },
+                Token::I32(1),
+                Token::I32(2),
+                Token::I32(4),
+                Token::SeqEnd,
+                Token::U64(2),
+                Token::TupleEnd,
+            ],
+            "node 2 of level 1 for elements 0..2 doesn't equal the combination of its children",
+        );
+        assert_de_tokens_error::<Nodes>(
+            &[
+                Token::Tuple { len: 2 },
+                Token::Seq { len: Some(3) },
+                Token::I32(1),
+                Token::I32(2),
+                Token::I32(3),
+                Token::SeqEnd,
+                Token::U64(3),
+                Token::TupleEnd,
+            ],
+            "3 elements need 4 nodes, but there are 3 nodes",
+        );
+    }
+
+    #[test]
+    fn test_deserialize_rejects_invalid_elements() {
+        let deserializer = IntoDeserializer::<Error>::into_deserializer(vec!["1", "2"]);
+        assert!(PostfixSegmentTree::<i64>::deserialize(deserializer).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use std::ops::{Index, RangeBounds};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::{Aggregate, Combine};
+use crate::element_mut::IterMut;
+use crate::internal::node_id::LeafNodeId;
+use crate::internal::range::to_range;
+use crate::iterator::ElementIterator;
+
+/// A borrowed view of a sub-range of [`PostfixSegmentTree`], which is returned by [`PostfixSegmentTree::slice`].
+///
+/// Indices of the view are relative to the start of the sub-range, and queries are answered by the underlying tree,
+/// so it is as cheap as the tree itself and nothing is copied.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PostfixSegmentTree;
+///
+/// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+/// let slice = tree.slice(1..4);
+/// assert_eq!(slice.len(), 3);
+/// assert_eq!(slice[0], 2);
+/// assert_eq!(slice.prefix_sum(2), 5);
+/// assert_eq!(slice.sum_range(..), 9);
+///
+/// let nested = slice.slice(1..);
+/// assert_eq!(nested.iter().copied().collect::<Vec<_>>(), vec![3, 4]);
+/// ```
+pub struct TreeSlice<'a, T> {
+    tree: &'a PostfixSegmentTree<T>,
+    start: usize,
+    end: usize,
+}
+
+impl<T> PostfixSegmentTree<T> {
+    /// Returns a [`TreeSlice`] of elements in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> TreeSlice<'_, T> {
+        let range = to_range(range, self.len());
+        TreeSlice {
+            tree: self,
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+impl<'a, T> TreeSlice<'a, T> {
+    /// Returns the number of elements in the view.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the view contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns an element at `index` of the view.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        self.tree.get(self.start + index)
+    }
+
+    /// Returns an [`ElementIterator`] over elements in the view.
+    pub fn iter(&self) -> ElementIterator<'a, T> {
+        ElementIterator::new(self.tree, self.start, self.end)
+    }
+
+    /// Returns a nested view of elements in `range`, which is relative to this view.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> TreeSlice<'a, T> {
+        let range = to_range(range, self.len());
+        TreeSlice {
+            tree: self.tree,
+            start: self.start + range.start,
+            end: self.start + range.end,
+        }
+    }
+}
+
+impl<T> TreeSlice<'_, T>
+where
+    T: Aggregate,
+{
+    /// Returns the equivalent of `self.iter().take(index).sum()`
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`start` + `index`)) where `start` is the start of the view in the tree
+    pub fn prefix_sum(&self, index: usize) -> T {
+        assert!(index <= self.len());
+
+        self.tree.sum(self.start, index)
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`start` + `index` + `len`)) where `start` is the start of the view in the tree
+    pub fn sum(&self, index: usize, len: usize) -> T {
+        assert!(index <= self.len());
+        assert!(len <= self.len() - index);
+
+        self.tree.sum(self.start + index, len)
+    }
+
+    /// Returns the sum of elements in `range`, which is relative to this view.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log (`start` + `range.end`)) where `start` is the start of the view in the tree
+    pub fn sum_range(&self, range: impl RangeBounds<usize>) -> T {
+        let range = to_range(range, self.len());
+        self.sum(range.start, range.len())
+    }
+}
+
+impl<T> Clone for TreeSlice<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TreeSlice<'_, T> {}
+
+impl<T> Index<usize> for TreeSlice<'_, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<'a, T> IntoIterator for TreeSlice<'a, T> {
+    type Item = &'a T;
+    type IntoIter = ElementIterator<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A mutable view of a sub-range of [`PostfixSegmentTree`], which is returned by [`PostfixSegmentTree::slice_mut`].
+///
+/// Writes through the view only modify leaf nodes.
+/// Nodes that cover the sub-range are repaired once when it is dropped, if any element is written.
+/// If it is leaked with `std::mem::forget`, partial sums are left stale.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PostfixSegmentTree;
+///
+/// let mut tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+/// {
+///     let mut slice = tree.slice_mut(1..4);
+///     slice.set(0, 20);
+///     for element in slice.iter_mut() {
+///         *element *= 10;
+///     }
+/// }
+///
+/// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 200, 30, 40, 5]);
+/// assert_eq!(tree.prefix_sum(5), 276);
+/// ```
+pub struct TreeSliceMut<'a, T>
+where
+    T: Combine + Clone,
+{
+    tree: &'a mut PostfixSegmentTree<T>,
+    start: usize,
+    end: usize,
+    // whether elements in the range are written, so parents of them are DIRTY
+    dirty: bool,
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Returns a [`TreeSliceMut`] of elements in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1), and *O*(`range.len()` + log [`len`]) on drop if any element is written.
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn slice_mut(&mut self, range: impl RangeBounds<usize>) -> TreeSliceMut<'_, T> {
+        let range = to_range(range, self.len());
+        TreeSliceMut {
+            tree: self,
+            start: range.start,
+            end: range.end,
+            dirty: false,
+        }
+    }
+}
+
+impl<T> TreeSliceMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    /// Returns the number of elements in the view.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the view contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns an element at `index` of the view.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        self.tree.get(self.start + index)
+    }
+
+    /// Returns a mutable reference to an element at `index` of the view.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        self.dirty = true;
+        let id = LeafNodeId::new(self.start + index);
+        Some(self.tree.get_leaf_node_mut(id)) // DIRTY: parents of `id`
+    }
+
+    /// Sets an element at `index` of the view.
+    pub fn set(&mut self, index: usize, element: T) {
+        *self.get_mut(index).expect("index out of bounds") = element;
+    }
+
+    /// Returns an [`ElementIterator`] over elements in the view.
+    pub fn iter(&self) -> ElementIterator<'_, T> {
+        ElementIterator::new(self.tree, self.start, self.end)
+    }
+
+    /// Returns an iterator over mutable references to elements in the view.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.dirty = true;
+        let first_node_index = LeafNodeId::new(self.start).node_index();
+        IterMut::new(
+            &mut self.tree.nodes[first_node_index..],
+            self.start,
+            self.end,
+        ) // DIRTY: parents of the range
+    }
+
+    /// Returns an immutable view of elements in `range`, which is relative to this view.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> TreeSlice<'_, T> {
+        let range = to_range(range, self.len());
+        TreeSlice {
+            tree: self.tree,
+            start: self.start + range.start,
+            end: self.start + range.end,
+        }
+    }
+}
+
+impl<T> Drop for TreeSliceMut<'_, T>
+where
+    T: Combine + Clone,
+{
+    fn drop(&mut self) {
+        if self.dirty && !self.is_empty() {
+            self.tree
+                .recalculate_nodes_after_range_update(self.start, self.end); // CLEAN: parents of the range
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_matches_naive() {
+        const N: u32 = 12;
+        let elements: Vec<u32> = (0..N).map(|i| i * i).collect();
+        let tree = PostfixSegmentTree::from_iter(elements.iter().copied());
+
+        for start in 0..=elements.len() {
+            for end in start..=elements.len() {
+                let slice = tree.slice(start..end);
+                let expected = &elements[start..end];
+                assert_eq!(slice.len(), expected.len());
+                assert!(slice.iter().eq(expected.iter()));
+                assert_eq!(slice.get(expected.len()), None);
+
+                for index in 0..=expected.len() {
+                    assert_eq!(slice.prefix_sum(index), expected[..index].iter().sum());
+
+                    let nested = slice.slice(index..);
+                    assert!(nested.iter().eq(expected[index..].iter()));
+                    assert_eq!(nested.sum_range(..), expected[index..].iter().sum());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_slice_mut_matches_naive() {
+        const N: u32 = 12;
+        for start in 0..=N as usize {
+            for end in start..=N as usize {
+                let mut model: Vec<u32> = (0..N).collect();
+                let mut tree = PostfixSegmentTree::from_iter(model.clone());
+
+                {
+                    let mut slice = tree.slice_mut(start..end);
+                    for element in slice.iter_mut() {
+                        *element *= 3;
+                    }
+                    if let Some(element) = slice.get_mut(1) {
+                        *element += 100;
+                    }
+                }
+                for element in &mut model[start..end] {
+                    *element *= 3;
+                }
+                if let Some(element) = model[start..end].get_mut(1) {
+                    *element += 100;
+                }
+
+                assert!(tree.iter().eq(model.iter()));
+                for index in 0..=model.len() {
+                    assert_eq!(tree.prefix_sum(index), model[..index].iter().sum());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::{Max, Min};
+
+/// A hint of the workload for [`SlidingExtrema`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SlidingHint {
+    /// Elements are only pushed. Extremes are maintained with monotonic queues in amortized *O*(1).
+    AppendOnly,
+    /// Elements in the window are also updated. Extremes are maintained with trees in *O*(log *window*).
+    RandomAccess,
+}
+
+/// Minimum and maximum of the latest `window` elements.
+///
+/// The backend is selected by [`SlidingHint`]:
+/// * [`SlidingHint::AppendOnly`]: monotonic queues, amortized *O*(1) [`push`] and *O*(1) [`min`]/[`max`].
+/// * [`SlidingHint::RandomAccess`]: [`PostfixSegmentTree`]s of [`Min`] and [`Max`] over a ring buffer,
+///   *O*(log *window*) [`push`] and [`update`].
+///
+/// Monotonic queues can't handle updates, so the first [`update`] switches the backend to trees.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{SlidingExtrema, SlidingHint};
+///
+/// let mut extrema = SlidingExtrema::new(3, SlidingHint::AppendOnly);
+/// for value in [4, 2, 12, 3] {
+///     extrema.push(value);
+/// }
+/// // the window is [2, 12, 3]
+/// assert_eq!(extrema.min(), Some(2));
+/// assert_eq!(extrema.max(), Some(12));
+///
+/// // the window is [2, 1, 3]
+/// extrema.update(1, 1);
+/// assert_eq!(extrema.min(), Some(1));
+/// assert_eq!(extrema.max(), Some(3));
+/// ```
+///
+/// [`push`]: SlidingExtrema::push
+/// [`update`]: SlidingExtrema::update
+/// [`min`]: SlidingExtrema::min
+/// [`max`]: SlidingExtrema::max
+pub struct SlidingExtrema<T> {
+    window: usize,
+    backend: Backend<T>,
+}
+
+enum Backend<T> {
+    Queue {
+        values: VecDeque<T>,
+        // (sequence number, value) with monotonically increasing/decreasing values
+        min: VecDeque<(u64, T)>,
+        max: VecDeque<(u64, T)>,
+        pushed: u64,
+    },
+    Tree {
+        min: PostfixSegmentTree<Min<T>>,
+        max: PostfixSegmentTree<Max<T>>,
+        // the position of the oldest element in the ring buffer
+        start: usize,
+        len: usize,
+    },
+}
+
+impl<T> SlidingExtrema<T>
+where
+    T: PartialOrd + Clone,
+{
+    /// Creates an empty window of `window` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is 0.
+    pub fn new(window: usize, hint: SlidingHint) -> Self {
+        assert!(window > 0);
+
+        let backend = match hint {
+            SlidingHint::AppendOnly => Backend::Queue {
+                values: VecDeque::with_capacity(window),
+                min: VecDeque::new(),
+                max: VecDeque::new(),
+                pushed: 0,
+            },
+            SlidingHint::RandomAccess => Backend::Tree {
+                min: PostfixSegmentTree::from_iter((0..window).map(|_| Min(None))),
+                max: PostfixSegmentTree::from_iter((0..window).map(|_| Max(None))),
+                start: 0,
+                len: 0,
+            },
+        };
+
+        Self { window, backend }
+    }
+
+    /// Returns the current backend as a [`SlidingHint`].
+    pub fn hint(&self) -> SlidingHint {
+        match self.backend {
+            Backend::Queue { .. } => SlidingHint::AppendOnly,
+            Backend::Tree { .. } => SlidingHint::RandomAccess,
+        }
+    }
+
+    /// Returns the number of elements in the window.
+    pub fn len(&self) -> usize {
+        match &self.backend {
+            Backend::Queue { values, .. } => values.len(),
+            Backend::Tree { len, .. } => *len,
+        }
+    }
+
+    /// Returns `true` if the window contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `index`-th element from the oldest one in the window.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        match &self.backend {
+            Backend::Queue { values, .. } => values.get(index),
+            Backend::Tree { min, start, .. } => min[(start + index) % self.window].get(),
+        }
+    }
+
+    /// Pushes `value`, and evicts the oldest element if the window is full.
+    pub fn push(&mut self, value: T) {
+        let window = self.window;
+        match &mut self.backend {
+            Backend::Queue {
+                values,
+                min,
+                max,
+                pushed,
+            } => {
+                if values.len() == window {
+                    values.pop_front();
+                }
+                values.push_back(value.clone());
+
+                let sequence = *pushed;
+                *pushed += 1;
+
+                // evict elements that are out of the window
+                let oldest = *pushed - values.len() as u64;
+                while min.front().is_some_and(|(s, _)| *s < oldest) {
+                    min.pop_front();
+                }
+                while max.front().is_some_and(|(s, _)| *s < oldest) {
+                    max.pop_front();
+                }
+
+                // keep the older one among equal values to match the leftmost semantics of `Min` and `Max`
+                while min.back().is_some_and(|(_, v)| *v > value) {
+                    min.pop_back();
+                }
+                min.push_back((sequence, value.clone()));
+                while max.back().is_some_and(|(_, v)| *v < value) {
+                    max.pop_back();
+                }
+                max.push_back((sequence, value));
+            }
+            Backend::Tree {
+                min,
+                max,
+                start,
+                len,
+            } => {
+                let position = (*start + *len) % window;
+                if *len == window {
+                    *start = (*start + 1) % window;
+                } else {
+                    *len += 1;
+                }
+
+                min.update(position, Min::new(value.clone()));
+                max.update(position, Max::new(value));
+            }
+        }
+    }
+
+    /// Updates the `index`-th element from the oldest one in the window.
+    ///
+    /// It switches the backend to trees if it was [`SlidingHint::AppendOnly`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of the window.
+    pub fn update(&mut self, index: usize, value: T) {
+        assert!(index < self.len());
+
+        if let Backend::Queue { values, .. } = &mut self.backend {
+            let values = std::mem::take(values);
+            let mut switched = Self::new(self.window, SlidingHint::RandomAccess);
+            for value in values {
+                switched.push(value);
+            }
+            *self = switched;
+        }
+
+        let Backend::Tree {
+            min, max, start, ..
+        } = &mut self.backend
+        else {
+            unreachable!()
+        };
+        let position = (*start + index) % self.window;
+        min.update(position, Min::new(value.clone()));
+        max.update(position, Max::new(value));
+    }
+
+    /// Returns the minimum in the window.
+    pub fn min(&self) -> Option<T> {
+        match &self.backend {
+            Backend::Queue { min, .. } => min.front().map(|(_, value)| value.clone()),
+            Backend::Tree { min, .. } => min.sum(0, self.window).into_inner(),
+        }
+    }
+
+    /// Returns the maximum in the window.
+    pub fn max(&self) -> Option<T> {
+        match &self.backend {
+            Backend::Queue { max, .. } => max.front().map(|(_, value)| value.clone()),
+            Backend::Tree { max, .. } => max.sum(0, self.window).into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backends_match_naive() {
+        const WINDOW: usize = 5;
+        let values: Vec<i32> = (0..100).map(|i| (i * 37) % 23 - 11).collect();
+
+        for hint in [SlidingHint::AppendOnly, SlidingHint::RandomAccess] {
+            let mut extrema = SlidingExtrema::new(WINDOW, hint);
+            assert_eq!(extrema.min(), None);
+
+            for (i, &value) in values.iter().enumerate() {
+                extrema.push(value);
+
+                let window = &values[(i + 1).saturating_sub(WINDOW)..=i];
+                assert_eq!(extrema.len(), window.len());
+                assert_eq!(extrema.min(), window.iter().min().copied());
+                assert_eq!(extrema.max(), window.iter().max().copied());
+                for (index, value) in window.iter().enumerate() {
+                    assert_eq!(extrema.get(index), Some(value));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_switches_backend() {
+        let mut extrema = SlidingExtrema::new(3, SlidingHint::AppendOnly);
+        for value in [5, 1, 7, 3] {
+            extrema.push(value);
+        }
+        assert_eq!(extrema.hint(), SlidingHint::AppendOnly);
+
+        // the window is [1, 7, 3]
+        extrema.update(0, 9);
+        assert_eq!(extrema.hint(), SlidingHint::RandomAccess);
+        assert_eq!(extrema.min(), Some(3));
+        assert_eq!(extrema.max(), Some(9));
+
+        // the window is [7, 3, 2]
+        extrema.push(2);
+        assert_eq!(extrema.min(), Some(2));
+        assert_eq!(extrema.max(), Some(7));
+    }
+}
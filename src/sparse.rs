@@ -0,0 +1,225 @@
+use std::ops::RangeBounds;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+use crate::internal::range::to_range;
+
+/// A sparse variant of [`PostfixSegmentTree`], which stores runs of identity elements implicitly.
+///
+/// Only elements that are not the identity are stored with their indices, in a [`PostfixSegmentTree`] in index order.
+/// Runs of identity elements between them are only represented by gaps of indices, so they take no memory.
+/// It takes memory proportional to the number of stored elements rather than [`len`],
+/// which suits huge counter arrays that are mostly zero.
+///
+/// A sum locates the stored elements in the range by binary search, so queries remain *O*(log [`stored_len`]).
+/// Updating an element to or from the identity inserts or removes a stored element,
+/// which shifts stored elements after it like [`PostfixSegmentTree::insert`].
+/// It is amortized *O*(1) when elements are stored in index order, such as with [`push`].
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::SparsePostfixSegmentTree;
+///
+/// let mut counters = SparsePostfixSegmentTree::new(1_000_000_000);
+/// counters.update(10, 3);
+/// counters.update(500_000_000, 4);
+/// counters.update(999_999_999, 5);
+/// assert_eq!(counters.stored_len(), 3);
+/// assert_eq!(counters.prefix_sum(500_000_001), 7);
+/// assert_eq!(counters.sum_range(11..), 9);
+///
+/// counters.update(10, 0);
+/// assert_eq!(counters.stored_len(), 2);
+/// assert_eq!(counters.get(10), 0);
+/// ```
+///
+/// [`len`]: SparsePostfixSegmentTree::len
+/// [`push`]: SparsePostfixSegmentTree::push
+/// [`stored_len`]: SparsePostfixSegmentTree::stored_len
+pub struct SparsePostfixSegmentTree<T> {
+    // indices of stored elements in increasing order
+    indices: Vec<usize>,
+    elements: PostfixSegmentTree<T>,
+    len: usize,
+}
+
+impl<T> SparsePostfixSegmentTree<T>
+where
+    T: Aggregate + Clone + PartialEq,
+{
+    /// Creates a tree of `len` identity elements.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn new(len: usize) -> Self {
+        Self {
+            indices: Vec::new(),
+            elements: PostfixSegmentTree::new(),
+            len,
+        }
+    }
+
+    /// Returns the total number of elements, including implicit identity elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the tree contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements that are not the identity, which are stored explicitly.
+    pub fn stored_len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns the element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`stored_len`])
+    ///
+    /// [`stored_len`]: SparsePostfixSegmentTree::stored_len
+    pub fn get(&self, index: usize) -> T {
+        assert!(index < self.len, "index {index} is out of bounds");
+
+        match self.indices.binary_search(&index) {
+            Ok(position) => self.elements[position].clone(),
+            Err(_) => T::identity(),
+        }
+    }
+
+    /// Appends an element to the back of the collection.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, element: T) {
+        if element != T::identity() {
+            self.indices.push(self.len);
+            self.elements.push(element);
+        }
+        self.len += 1;
+    }
+
+    /// Updates the element at `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`stored_len`]) if the element is stored both before and after the update,
+    /// otherwise *O*([`stored_len`]) to shift stored elements after `index`
+    ///
+    /// [`stored_len`]: SparsePostfixSegmentTree::stored_len
+    pub fn update(&mut self, index: usize, element: T) {
+        assert!(index < self.len, "index {index} is out of bounds");
+
+        let is_identity = element == T::identity();
+        match self.indices.binary_search(&index) {
+            Ok(position) if is_identity => {
+                self.indices.remove(position);
+                self.elements.remove(position);
+            }
+            Ok(position) => self.elements.update(position, element),
+            Err(_) if is_identity => {}
+            Err(position) => {
+                self.indices.insert(position, index);
+                self.elements.insert(position, element);
+            }
+        }
+    }
+
+    /// Returns the equivalent of `self.iter().take(index).sum()`
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`stored_len`])
+    ///
+    /// [`stored_len`]: SparsePostfixSegmentTree::stored_len
+    pub fn prefix_sum(&self, index: usize) -> T {
+        assert!(index <= self.len);
+
+        self.elements.prefix_sum(self.position(index))
+    }
+
+    /// Returns the equivalent of `self.iter().skip(index).take(len).sum()`
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`stored_len`])
+    ///
+    /// [`stored_len`]: SparsePostfixSegmentTree::stored_len
+    pub fn sum(&self, index: usize, len: usize) -> T {
+        assert!(index <= self.len);
+        assert!(len <= self.len - index);
+
+        let start = self.position(index);
+        let end = self.position(index + len);
+        self.elements.sum(start, end - start)
+    }
+
+    /// Returns the sum of elements in `range`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`stored_len`])
+    ///
+    /// [`stored_len`]: SparsePostfixSegmentTree::stored_len
+    pub fn sum_range(&self, range: impl RangeBounds<usize>) -> T {
+        let range = to_range(range, self.len);
+        self.sum(range.start, range.len())
+    }
+
+    /// Returns an iterator over stored elements and their indices in index order.
+    pub fn iter_stored(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.indices.iter().copied().zip(self.elements.iter())
+    }
+
+    /// Returns the number of stored elements before `index`.
+    fn position(&self, index: usize) -> usize {
+        self.indices.partition_point(|&stored| stored < index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_matches_naive() {
+        let mut model = vec![0i64; 50];
+        let mut tree = SparsePostfixSegmentTree::new(model.len());
+        for step in 0..200usize {
+            if step % 10 == 0 {
+                let element = (step % 3) as i64;
+                tree.push(element);
+                model.push(element);
+            }
+
+            let index = (step * 37) % model.len();
+            let element = if step % 4 == 0 { 0 } else { step as i64 - 100 };
+            tree.update(index, element);
+            model[index] = element;
+
+            assert_eq!(
+                tree.stored_len(),
+                model.iter().filter(|&&element| element != 0).count()
+            );
+            assert!(
+                tree.iter_stored()
+                    .all(|(index, element)| model[index] == *element && *element != 0)
+            );
+            for index in 0..=model.len() {
+                assert_eq!(tree.prefix_sum(index), model[..index].iter().sum());
+            }
+            for index in (0..model.len()).step_by(7) {
+                assert_eq!(tree.get(index), model[index]);
+                for len in (0..=model.len() - index).step_by(5) {
+                    assert_eq!(tree.sum(index, len), model[index..index + len].iter().sum());
+                }
+            }
+        }
+    }
+}
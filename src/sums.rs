@@ -0,0 +1,376 @@
+use std::iter::FusedIterator;
+use std::ops::Sub;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+use crate::iterator::ElementIterator;
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate + Clone,
+{
+    /// Returns a [`PrefixSums`], which is an iterator for running sums `self.prefix_sum(index + 1)` of each index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4]);
+    /// assert_eq!(tree.prefix_sums().collect::<Vec<_>>(), vec![1, 3, 6, 10]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`]) for the whole iteration, rather than *O*([`len`] log [`len`]) of [`prefix_sum`]s.
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    /// [`prefix_sum`]: PostfixSegmentTree::prefix_sum
+    pub fn prefix_sums(&self) -> PrefixSums<'_, T> {
+        PrefixSums {
+            elements: self.iter(),
+            sum: T::identity(),
+        }
+    }
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate,
+{
+    /// Returns a [`ChunkSums`], which is an iterator for sums of each `size` elements, starting at the beginning.
+    /// The last chunk may be shorter.
+    ///
+    /// Each sum is combined from nodes that cover the chunk exactly,
+    /// so a chunk whose boundaries align with a node, such as a power-of-two `size`, is a single node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// assert_eq!(tree.chunk_sums(2).collect::<Vec<_>>(), vec![3, 7, 5]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`]) for each chunk
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn chunk_sums(&self, size: usize) -> ChunkSums<'_, T> {
+        assert!(size != 0);
+
+        ChunkSums {
+            tree: self,
+            index: 0,
+            size,
+        }
+    }
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate,
+{
+    /// Returns a [`WindowSums`], which is an iterator for sums of all contiguous windows of `size` elements.
+    ///
+    /// Each sum is queried from the tree, so it works with any [`Aggregate`] such as [`Max`].
+    /// For invertible sums, [`window_sums_by_difference`] is faster.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::{Max, PostfixSegmentTree};
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 5, 2, 3].map(Max::new));
+    /// let maxima: Vec<_> = tree.window_sums(2).map(|max| max.0).collect();
+    /// assert_eq!(maxima, vec![Some(5), Some(5), Some(3)]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log [`len`]) for each window
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    /// [`Max`]: crate::Max
+    /// [`window_sums_by_difference`]: PostfixSegmentTree::window_sums_by_difference
+    pub fn window_sums(&self, size: usize) -> WindowSums<'_, T> {
+        assert!(size != 0);
+
+        WindowSums {
+            tree: self,
+            index: 0,
+            size,
+        }
+    }
+}
+
+impl<T> PostfixSegmentTree<T>
+where
+    T: Aggregate + Clone + Sub<Output = T>,
+{
+    /// Returns a [`WindowSumsByDifference`], which is an iterator for sums of all contiguous windows of `size` elements.
+    ///
+    /// Sums are calculated incrementally by combining the entering element and subtracting the leaving one.
+    /// Errors of floating-point numbers may accumulate over the iteration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::PostfixSegmentTree;
+    ///
+    /// let tree = PostfixSegmentTree::from_iter([1, 2, 3, 4, 5]);
+    /// assert_eq!(tree.window_sums_by_difference(3).collect::<Vec<_>>(), vec![6, 9, 12]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`len`]) for the whole iteration
+    ///
+    /// [`len`]: PostfixSegmentTree::len
+    pub fn window_sums_by_difference(&self, size: usize) -> WindowSumsByDifference<'_, T> {
+        assert!(size != 0);
+
+        let mut entering = self.iter();
+        let mut sum = T::identity();
+        // the first window is completed on the first `next`
+        for element in entering.by_ref().take(size - 1) {
+            sum.combine(element);
+        }
+
+        WindowSumsByDifference {
+            entering,
+            leaving: self.iter(),
+            sum,
+        }
+    }
+}
+
+/// Iterator for running prefix sums of elements on [`PostfixSegmentTree`].
+///
+/// See [`PostfixSegmentTree::prefix_sums`].
+#[derive(Clone)]
+pub struct PrefixSums<'a, T> {
+    elements: ElementIterator<'a, T>,
+    // sum of elements before `elements`
+    sum: T,
+}
+
+impl<T> Iterator for PrefixSums<'_, T>
+where
+    T: Aggregate + Clone,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        self.sum.combine(element);
+        Some(self.sum.clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.elements.size_hint()
+    }
+}
+
+impl<T> FusedIterator for PrefixSums<'_, T> where T: Aggregate + Clone {}
+
+impl<T> ExactSizeIterator for PrefixSums<'_, T> where T: Aggregate + Clone {}
+
+/// Iterator for sums of non-overlapping chunks of elements on [`PostfixSegmentTree`].
+///
+/// See [`PostfixSegmentTree::chunk_sums`].
+pub struct ChunkSums<'a, T> {
+    tree: &'a PostfixSegmentTree<T>,
+    index: usize,
+    size: usize,
+}
+
+impl<T> Iterator for ChunkSums<'_, T>
+where
+    T: Aggregate,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.tree.len() {
+            return None;
+        }
+
+        let len = usize::min(self.size, self.tree.len() - self.index);
+        let sum = self.tree.sum(self.index, len);
+        self.index += len;
+
+        Some(sum)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.tree.len() - self.index).div_ceil(self.size);
+        (len, Some(len))
+    }
+}
+
+impl<T> Clone for ChunkSums<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree,
+            index: self.index,
+            size: self.size,
+        }
+    }
+}
+
+impl<T> FusedIterator for ChunkSums<'_, T> where T: Aggregate {}
+
+impl<T> ExactSizeIterator for ChunkSums<'_, T> where T: Aggregate {}
+
+/// Iterator for sums of overlapping windows of elements on [`PostfixSegmentTree`].
+///
+/// See [`PostfixSegmentTree::window_sums`].
+pub struct WindowSums<'a, T> {
+    tree: &'a PostfixSegmentTree<T>,
+    index: usize,
+    size: usize,
+}
+
+impl<T> Iterator for WindowSums<'_, T>
+where
+    T: Aggregate,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index + self.size > self.tree.len() {
+            return None;
+        }
+
+        let sum = self.tree.sum(self.index, self.size);
+        self.index += 1;
+
+        Some(sum)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.tree.len() + 1).saturating_sub(self.index + self.size);
+        (len, Some(len))
+    }
+}
+
+impl<T> Clone for WindowSums<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree,
+            index: self.index,
+            size: self.size,
+        }
+    }
+}
+
+impl<T> FusedIterator for WindowSums<'_, T> where T: Aggregate {}
+
+impl<T> ExactSizeIterator for WindowSums<'_, T> where T: Aggregate {}
+
+/// Iterator for sums of overlapping windows of elements on [`PostfixSegmentTree`], which are calculated incrementally.
+///
+/// See [`PostfixSegmentTree::window_sums_by_difference`].
+#[derive(Clone)]
+pub struct WindowSumsByDifference<'a, T> {
+    entering: ElementIterator<'a, T>,
+    leaving: ElementIterator<'a, T>,
+    // sum of elements between `leaving` and `entering`, which lacks the last element of the next window
+    sum: T,
+}
+
+impl<T> Iterator for WindowSumsByDifference<'_, T>
+where
+    T: Aggregate + Clone + Sub<Output = T>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entering = self.entering.next()?;
+        let leaving = self.leaving.next()?;
+
+        self.sum.combine(entering);
+        let sum = self.sum.clone();
+        self.sum = self.sum.clone() - leaving.clone();
+
+        Some(sum)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entering.size_hint()
+    }
+}
+
+impl<T> FusedIterator for WindowSumsByDifference<'_, T> where T: Aggregate + Clone + Sub<Output = T> {}
+
+impl<T> ExactSizeIterator for WindowSumsByDifference<'_, T> where
+    T: Aggregate + Clone + Sub<Output = T>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_sums() {
+        const N: u32 = 20;
+        for len in 0..N {
+            let tree = PostfixSegmentTree::from_iter(0..len);
+            let expected: Vec<u32> = (1..=len as usize)
+                .map(|index| tree.prefix_sum(index))
+                .collect();
+            assert_eq!(tree.prefix_sums().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_chunk_sums() {
+        const N: u32 = 20;
+        for len in 0..N {
+            let model: Vec<u32> = (0..len).map(|i| i * i).collect();
+            let tree = PostfixSegmentTree::from_iter(model.clone());
+            for size in 1..=len as usize + 1 {
+                let expected: Vec<u32> = model.chunks(size).map(|c| c.iter().sum()).collect();
+                let chunk_sums = tree.chunk_sums(size);
+                assert_eq!(chunk_sums.len(), expected.len());
+                assert_eq!(chunk_sums.collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_window_sums() {
+        const N: i32 = 20;
+        for len in 0..N {
+            let model: Vec<i32> = (0..len).map(|i| i * i - 5 * i).collect();
+            let tree = PostfixSegmentTree::from_iter(model.clone());
+            for size in 1..=len as usize + 1 {
+                let expected: Vec<i32> = model.windows(size).map(|w| w.iter().sum()).collect();
+
+                let window_sums = tree.window_sums(size);
+                assert_eq!(window_sums.len(), expected.len());
+                assert_eq!(window_sums.collect::<Vec<_>>(), expected);
+
+                let window_sums = tree.window_sums_by_difference(size);
+                assert_eq!(window_sums.len(), expected.len());
+                assert_eq!(window_sums.collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+}
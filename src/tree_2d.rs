@@ -0,0 +1,205 @@
+use std::ops::RangeBounds;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::{Aggregate, Combine};
+use crate::internal::node_id::{LeafNodeId, NodeId};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::range::to_range;
+use crate::internal::skipping_iterator::range_iterator;
+
+/// A 2D variant of [`PostfixSegmentTree`], which supports rectangle sums over rows of a fixed width.
+///
+/// It is a tree of trees. Rows are nodes of an outer tree in the same layout as [`PostfixSegmentTree`],
+/// and each node of the outer tree is a [`PostfixSegmentTree`] of column-wise sums of the rows it covers.
+/// So [`push_row`] is amortized *O*(`width`), and [`update`] and [`sum`] are *O*(log `height` \* log `width`).
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PostfixSegmentTree2D;
+///
+/// let mut grid = PostfixSegmentTree2D::new(3);
+/// grid.push_row([1, 2, 3]);
+/// grid.push_row([4, 5, 6]);
+/// grid.push_row([7, 8, 9]);
+/// assert_eq!(grid.sum(.., ..), 45);
+/// assert_eq!(grid.sum(1..3, 0..2), 4 + 5 + 7 + 8);
+///
+/// grid.update(1, 1, 50);
+/// assert_eq!(grid.get(1, 1), Some(&50));
+/// assert_eq!(grid.sum(1.., 1..), 50 + 6 + 8 + 9);
+/// ```
+///
+/// [`push_row`]: PostfixSegmentTree2D::push_row
+/// [`update`]: PostfixSegmentTree2D::update
+/// [`sum`]: PostfixSegmentTree2D::sum
+pub struct PostfixSegmentTree2D<T> {
+    // column-wise sums of rows, indexed by the node index of the outer tree
+    rows: Vec<PostfixSegmentTree<T>>,
+    height: usize,
+    width: usize,
+}
+
+impl<T> PostfixSegmentTree2D<T> {
+    /// Creates an empty tree of rows of `width` elements.
+    pub fn new(width: usize) -> Self {
+        Self {
+            rows: Vec::new(),
+            height: 0,
+            width,
+        }
+    }
+
+    /// Returns the number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of elements in a row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns `true` if the tree has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.height == 0
+    }
+
+    /// Returns the element at the row `x` and the column `y`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(1)
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x >= self.height {
+            return None;
+        }
+
+        self.rows[LeafNodeId::new(x).node_index()].get(y)
+    }
+}
+
+impl<T> PostfixSegmentTree2D<T>
+where
+    T: Combine + Clone,
+{
+    /// Appends a row to the bottom.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row doesn't have [`width`] elements.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(`width`)
+    ///
+    /// [`width`]: PostfixSegmentTree2D::width
+    pub fn push_row(&mut self, row: impl IntoIterator<Item = T>) {
+        let row = PostfixSegmentTree::from_iter(row);
+        assert_eq!(row.len(), self.width, "the row should have width elements");
+
+        let id = LeafNodeId::new(self.height);
+        self.rows.push(row);
+        self.height += 1;
+        for parent in ParentsIterator::new(id, self.height) {
+            let left = &self.rows[parent.left_child().node_index()];
+            let right = &self.rows[parent.right_child().node_index()];
+            let sums = left.iter().zip(right.iter()).map(|(left, right)| {
+                let mut sum = left.clone();
+                sum.combine(right);
+                sum
+            });
+
+            debug_assert_eq!(self.rows.len(), parent.node_index());
+            self.rows.push(PostfixSegmentTree::from_iter(sums));
+        }
+    }
+
+    /// Updates the element at the row `x` and the column `y`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `height` \* log `width`)
+    pub fn update(&mut self, x: usize, y: usize, element: T) {
+        assert!(x < self.height, "x {x} is out of bounds");
+        assert!(y < self.width, "y {y} is out of bounds");
+
+        let id = LeafNodeId::new(x);
+        self.rows[id.node_index()].update(y, element);
+        for parent in ParentsIterator::new(id, self.height) {
+            let sum = self.column_sum_of_children(parent, y);
+            self.rows[parent.node_index()].update(y, sum);
+        }
+    }
+
+    fn column_sum_of_children(&self, id: NodeId, y: usize) -> T {
+        let left = &self.rows[id.left_child().node_index()];
+        let right = &self.rows[id.right_child().node_index()];
+        let mut sum = left[y].clone();
+        sum.combine(&right[y]);
+        sum
+    }
+}
+
+impl<T> PostfixSegmentTree2D<T>
+where
+    T: Aggregate + Clone,
+{
+    /// Returns the sum of elements in rows `xs` and columns `ys`.
+    ///
+    /// Rows are combined from top to bottom, and sums of each row from left to right.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `height` \* log `width`)
+    pub fn sum(&self, xs: impl RangeBounds<usize>, ys: impl RangeBounds<usize>) -> T {
+        let xs = to_range(xs, self.height);
+        let ys = to_range(ys, self.width);
+
+        let mut sum = T::identity();
+        for id in range_iterator(xs.start, xs.end) {
+            sum.combine(&self.rows[id.node_index()].sum(ys.start, ys.len()));
+        }
+
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_2d_matches_naive() {
+        const WIDTH: usize = 7;
+        let mut model: Vec<Vec<i64>> = Vec::new();
+        let mut grid = PostfixSegmentTree2D::new(WIDTH);
+        for step in 0..40usize {
+            if step % 3 == 0 {
+                let row: Vec<i64> = (0..WIDTH)
+                    .map(|y| ((step * 7 + y * 11) % 19) as i64)
+                    .collect();
+                grid.push_row(row.iter().copied());
+                model.push(row);
+            } else {
+                let (x, y) = ((step * 5) % model.len(), (step * 3) % WIDTH);
+                grid.update(x, y, step as i64 - 20);
+                model[x][y] = step as i64 - 20;
+            }
+
+            for x0 in 0..=model.len() {
+                for x1 in x0..=model.len() {
+                    for y0 in 0..=WIDTH {
+                        for y1 in y0..=WIDTH {
+                            let expected: i64 = model[x0..x1]
+                                .iter()
+                                .map(|row| row[y0..y1].iter().sum::<i64>())
+                                .sum();
+                            assert_eq!(grid.sum(x0..x1, y0..y1), expected);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::array;
+use std::ops::Range;
+
+use crate::aggregate::Aggregate;
+use crate::internal::node_id::{LeafNodeId, get_nodes_len_for};
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::internal::skipping_iterator::range_iterator;
+
+/// A node on the path from a leaf to its highest parent in a dimension.
+struct PathNode {
+    node_index: usize,
+    // the node index of the other child, and whether the child on the path is the left one
+    sibling: Option<(usize, bool)>,
+}
+
+/// A `D`-dimensional variant of [`PostfixSegmentTree`] with a fixed shape, which supports orthogonal range sums.
+///
+/// Nodes are the product of nodes of each dimension in the same layout as [`PostfixSegmentTree`].
+/// A node covers the box of the ranges that its node in each dimension covers, and it is stored in a flattened `Vec`.
+/// So it takes about 2<sup>`D`</sup> times as many nodes as elements,
+/// and [`update`] and [`sum`] are *O*(log<sup>`D`</sup> *n*) where *n* is the largest length of dimensions.
+///
+/// A sum is combined from nodes of boxes in no meaningful order,
+/// so it is intended for commutative operations, such as counting events in multi-dimensional bins.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::PostfixSegmentTreeNd;
+///
+/// let mut events = PostfixSegmentTreeNd::<u32, 3>::new([10, 10, 4]);
+/// events.add([1, 2, 0], 1);
+/// events.add([1, 2, 0], 1);
+/// events.add([5, 7, 3], 1);
+/// events.add([9, 0, 1], 1);
+/// assert_eq!(events.sum([0..10, 0..10, 0..4]), 4);
+/// assert_eq!(events.sum([0..6, 2..8, 0..4]), 3);
+/// assert_eq!(events.sum([1..2, 2..3, 1..4]), 0);
+///
+/// events.update([1, 2, 0], 0);
+/// assert_eq!(events.get([1, 2, 0]), Some(&0));
+/// assert_eq!(events.sum([0..6, 2..8, 0..4]), 1);
+/// ```
+///
+/// [`PostfixSegmentTree`]: crate::PostfixSegmentTree
+/// [`update`]: PostfixSegmentTreeNd::update
+/// [`sum`]: PostfixSegmentTreeNd::sum
+pub struct PostfixSegmentTreeNd<T, const D: usize> {
+    shape: [usize; D],
+    // strides of node indices of each dimension in `nodes`
+    strides: [usize; D],
+    nodes: Vec<T>,
+}
+
+impl<T, const D: usize> PostfixSegmentTreeNd<T, D>
+where
+    T: Aggregate + Clone,
+{
+    /// Creates a tree of `shape` filled with the identity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of nodes overflows `usize`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(number of nodes)
+    pub fn new(shape: [usize; D]) -> Self {
+        let mut strides = [0; D];
+        let mut nodes_len: usize = 1;
+        for dimension in (0..D).rev() {
+            strides[dimension] = nodes_len;
+            nodes_len = nodes_len
+                .checked_mul(get_nodes_len_for(shape[dimension]))
+                .expect("capacity overflow");
+        }
+
+        Self {
+            shape,
+            strides,
+            nodes: vec![T::identity(); nodes_len],
+        }
+    }
+
+    /// Returns the lengths of dimensions.
+    pub fn shape(&self) -> [usize; D] {
+        self.shape
+    }
+
+    /// Returns the element at `point`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(`D`)
+    pub fn get(&self, point: [usize; D]) -> Option<&T> {
+        if (0..D).any(|dimension| point[dimension] >= self.shape[dimension]) {
+            return None;
+        }
+
+        Some(&self.nodes[self.offset(point.map(|index| LeafNodeId::new(index).node_index()))])
+    }
+
+    /// Updates the element at `point`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log<sup>`D`</sup> *n*)
+    pub fn update(&mut self, point: [usize; D], element: T) {
+        for dimension in 0..D {
+            assert!(
+                point[dimension] < self.shape[dimension],
+                "point {point:?} is out of bounds"
+            );
+        }
+
+        let paths: [Vec<PathNode>; D] = array::from_fn(|dimension| {
+            let id = LeafNodeId::new(point[dimension]);
+            let mut path = vec![PathNode {
+                node_index: id.node_index(),
+                sibling: None,
+            }];
+            for parent in ParentsIterator::new(id, self.shape[dimension]) {
+                let (left, right) = (parent.left_child(), parent.right_child());
+                let child = path.last().unwrap().node_index;
+                let sibling = if left.node_index() == child {
+                    (right.node_index(), true)
+                } else {
+                    (left.node_index(), false)
+                };
+                path.push(PathNode {
+                    node_index: parent.node_index(),
+                    sibling: Some(sibling),
+                });
+            }
+            path
+        });
+
+        // every node whose box contains `point` is the product of nodes on the paths.
+        // a node is recalculated from its children in the first dimension that it is not a leaf,
+        // where the child on the path comes before it in the lexicographic order of positions on the paths.
+        let lens = paths.each_ref().map(Vec::len);
+        let mut positions = [0; D];
+        let node_indices = |positions: &[usize; D]| -> [usize; D] {
+            array::from_fn(|dimension| paths[dimension][positions[dimension]].node_index)
+        };
+
+        let leaf = self.offset(node_indices(&positions));
+        self.nodes[leaf] = element;
+        while next_position(&mut positions, &lens) {
+            let dimension = (0..D).find(|&dimension| positions[dimension] > 0).unwrap();
+            let (sibling, is_left) = paths[dimension][positions[dimension]].sibling.unwrap();
+
+            let mut child = positions;
+            child[dimension] -= 1;
+            let child = node_indices(&child);
+            let mut other = child;
+            other[dimension] = sibling;
+
+            let (left, right) = if is_left {
+                (self.offset(child), self.offset(other))
+            } else {
+                (self.offset(other), self.offset(child))
+            };
+            let mut sum = self.nodes[left].clone();
+            sum.combine(&self.nodes[right]);
+            let node = self.offset(node_indices(&positions));
+            self.nodes[node] = sum;
+        }
+    }
+
+    /// Combines `delta` into the element at `point`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log<sup>`D`</sup> *n*)
+    pub fn add(&mut self, point: [usize; D], delta: T) {
+        let mut element = self.get(point).expect("point is out of bounds").clone();
+        element.combine(&delta);
+        self.update(point, element);
+    }
+
+    /// Returns the sum of elements in the box of `ranges`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log<sup>`D`</sup> *n*)
+    pub fn sum(&self, ranges: [Range<usize>; D]) -> T {
+        let node_indices: [Vec<usize>; D] = array::from_fn(|dimension| {
+            let range = &ranges[dimension];
+            assert!(
+                range.start <= range.end && range.end <= self.shape[dimension],
+                "range {range:?} is out of bounds"
+            );
+            range_iterator(range.start, range.end)
+                .map(|id| id.node_index())
+                .collect()
+        });
+
+        let mut sum = T::identity();
+        let lens = node_indices.each_ref().map(Vec::len);
+        if lens.contains(&0) {
+            return sum;
+        }
+
+        let mut positions = [0; D];
+        loop {
+            let node = array::from_fn(|dimension| node_indices[dimension][positions[dimension]]);
+            sum.combine(&self.nodes[self.offset(node)]);
+            if !next_position(&mut positions, &lens) {
+                return sum;
+            }
+        }
+    }
+
+    fn offset(&self, node_indices: [usize; D]) -> usize {
+        (0..D)
+            .map(|dimension| node_indices[dimension] * self.strides[dimension])
+            .sum()
+    }
+}
+
+/// Advances `positions` to the next one in the lexicographic order, where `positions[d] < lens[d]`.
+/// Returns `false` if it was the last one.
+fn next_position<const D: usize>(positions: &mut [usize; D], lens: &[usize; D]) -> bool {
+    for dimension in (0..D).rev() {
+        positions[dimension] += 1;
+        if positions[dimension] < lens[dimension] {
+            return true;
+        }
+        positions[dimension] = 0;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nd_matches_naive() {
+        const SHAPE: [usize; 3] = [5, 4, 3];
+        let mut model = [[[0i64; 3]; 4]; 5];
+        let mut tree = PostfixSegmentTreeNd::<i64, 3>::new(SHAPE);
+        for step in 0..30usize {
+            let point = [step % 5, (step * 3) % 4, (step * 7) % 3];
+            let value = (step * 13 % 17) as i64 - 8;
+            tree.update(point, value);
+            model[point[0]][point[1]][point[2]] = value;
+
+            for (x, plane) in model.iter().enumerate() {
+                for (y, row) in plane.iter().enumerate() {
+                    for (z, element) in row.iter().enumerate() {
+                        assert_eq!(tree.get([x, y, z]), Some(element));
+                    }
+                }
+            }
+            let ranges = |len: usize| {
+                (0..=len).flat_map(move |start| (start..=len).map(move |end| start..end))
+            };
+            for xs in ranges(5) {
+                for ys in ranges(4) {
+                    for zs in ranges(3) {
+                        let expected: i64 = model[xs.clone()]
+                            .iter()
+                            .flat_map(|plane| plane[ys.clone()].iter())
+                            .flat_map(|row| row[zs.clone()].iter())
+                            .sum();
+                        assert_eq!(tree.sum([xs.clone(), ys.clone(), zs.clone()]), expected);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::PostfixSegmentTree;
+use crate::aggregate::Combine;
+use crate::internal::node_id::LeafNodeId;
+use crate::internal::parents_iterator::ParentsIterator;
+use crate::node::NodeAddress;
+
+/// A wrapper of [`PostfixSegmentTree`] that tracks versions of nodes.
+///
+/// Every mutation through the wrapper increments the [`version`] of the tree,
+/// and stamps it on the nodes that are recalculated by the mutation.
+/// External mirrors of nodes, such as GPU buffers for visualization,
+/// can sync only nodes that are [`changed_since`] the version of the last sync.
+///
+/// Nodes after [`PostfixSegmentTree::nodes_len`] are removed from the tree,
+/// so mirrors should also truncate them on sync.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::{PostfixSegmentTree, VersionedPostfixSegmentTree};
+///
+/// let mut tree = VersionedPostfixSegmentTree::new(PostfixSegmentTree::from_iter([1, 2, 3, 4]));
+/// let mut mirror: Vec<i32> = (0..tree.tree().nodes_len())
+///     .map(|node_index| tree.node_at(node_index))
+///     .collect();
+/// let synced = tree.version();
+///
+/// tree.update(2, 10);
+/// let changed: Vec<_> = tree.changed_since(synced).map(|address| address.node_index()).collect();
+/// // the leaf node of element 2, and its parents
+/// assert_eq!(changed, vec![3, 5, 6]);
+///
+/// for node_index in changed {
+///     mirror[node_index] = tree.node_at(node_index);
+/// }
+/// assert_eq!(mirror, vec![1, 2, 3, 10, 4, 14, 17]);
+/// ```
+///
+/// [`version`]: VersionedPostfixSegmentTree::version
+/// [`changed_since`]: VersionedPostfixSegmentTree::changed_since
+pub struct VersionedPostfixSegmentTree<T> {
+    tree: PostfixSegmentTree<T>,
+    // versions of nodes, indexed by the node index
+    versions: Vec<u64>,
+    version: u64,
+}
+
+impl<T> VersionedPostfixSegmentTree<T>
+where
+    T: Combine + Clone,
+{
+    /// Wraps `tree`. Existing nodes have the version 0.
+    pub fn new(tree: PostfixSegmentTree<T>) -> Self {
+        Self {
+            versions: vec![0; tree.nodes_len()],
+            tree,
+            version: 0,
+        }
+    }
+
+    /// Returns the underlying tree.
+    pub fn tree(&self) -> &PostfixSegmentTree<T> {
+        &self.tree
+    }
+
+    pub fn into_inner(self) -> PostfixSegmentTree<T> {
+        self.tree
+    }
+
+    /// Returns the current version, which is the number of mutations so far.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the version of the node at `address`, which is the version when it was last recalculated.
+    pub fn node_version(&self, address: NodeAddress) -> Option<u64> {
+        self.versions.get(address.node_index()).copied()
+    }
+
+    /// Iterates addresses of nodes that are recalculated after `version`, in the order of node indices.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*([`nodes_len`])
+    ///
+    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    pub fn changed_since(&self, version: u64) -> impl Iterator<Item = NodeAddress> + '_ {
+        self.versions
+            .iter()
+            .enumerate()
+            .filter(move |&(_, &node_version)| node_version > version)
+            .map(|(node_index, _)| NodeAddress::from_node_index(node_index).expect("a node index"))
+    }
+
+    /// Returns a clone of the node stored at `node_index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node_index` is out of [`nodes_len`].
+    ///
+    /// [`nodes_len`]: PostfixSegmentTree::nodes_len
+    pub fn node_at(&self, node_index: usize) -> T {
+        self.tree.nodes[node_index].clone()
+    }
+
+    /// [`PostfixSegmentTree::update`] that stamps the leaf node of `index` and its parents.
+    pub fn update(&mut self, index: usize, element: T) {
+        self.tree.update(index, element);
+
+        self.version += 1;
+        let id = LeafNodeId::new(index);
+        self.versions[id.node_index()] = self.version;
+        for node_id in ParentsIterator::new(id, self.tree.len()) {
+            self.versions[node_id.node_index()] = self.version;
+        }
+    }
+
+    /// [`PostfixSegmentTree::push`] that stamps the new nodes.
+    pub fn push(&mut self, element: T) {
+        let nodes_len = self.tree.nodes_len();
+        self.tree.push(element);
+        self.stamp_from(nodes_len);
+    }
+
+    /// [`PostfixSegmentTree::insert`] that stamps nodes of `index..`.
+    pub fn insert(&mut self, index: usize, element: T) {
+        self.tree.insert(index, element);
+        self.stamp_from(LeafNodeId::new(index).node_index());
+    }
+
+    /// [`PostfixSegmentTree::remove`] that stamps nodes of `index..`.
+    pub fn remove(&mut self, index: usize) -> T {
+        let removed = self.tree.remove(index);
+        self.stamp_from(LeafNodeId::new(index).node_index());
+        removed
+    }
+
+    /// Stamps the current version on nodes from `node_index` to the end.
+    fn stamp_from(&mut self, node_index: usize) {
+        self.version += 1;
+
+        let nodes_len = self.tree.nodes_len();
+        self.versions.resize(nodes_len, self.version);
+        for version in &mut self.versions[usize::min(node_index, nodes_len)..] {
+            *version = self.version;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror_matches_tree() {
+        let mut tree = VersionedPostfixSegmentTree::new(PostfixSegmentTree::from_iter(0..5u64));
+        let mut mirror: Vec<u64> = tree.tree().nodes.clone();
+        let mut synced = tree.version();
+
+        for step in 0..50u64 {
+            match step % 4 {
+                0 => tree.push(step),
+                1 => tree.update((step as usize * 7) % tree.tree().len(), step),
+                2 => tree.insert((step as usize * 3) % tree.tree().len(), step),
+                _ => {
+                    tree.remove((step as usize * 5) % tree.tree().len());
+                }
+            }
+
+            let nodes_len = tree.tree().nodes_len();
+            mirror.resize(nodes_len, 0);
+            for address in tree.changed_since(synced) {
+                mirror[address.node_index()] = tree.node_at(address.node_index());
+            }
+            synced = tree.version();
+
+            assert_eq!(mirror, tree.tree().nodes);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::ops::{Range, RangeBounds};
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+use crate::internal::range::to_range;
+
+/// Layout state of a virtual scrolling list, which stores row heights in a [`PostfixSegmentTree`].
+///
+/// The top of a row is the prefix sum of heights, and the row at a position is found by a binary search over them,
+/// so both are *O*(log *rows*) even when rows have different heights.
+///
+/// Mutations report the area whose layout is changed as a dirty rect, which is a range of y positions.
+/// Dirty rects are accumulated until they're taken with [`take_dirty`].
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::VirtualList;
+///
+/// let mut list = VirtualList::from_heights([20, 30, 20]);
+/// assert_eq!(list.y_of_row(2), 50);
+/// assert_eq!(list.row_at_y(49), Some(1));
+/// assert_eq!(list.row_at_y(70), None);
+///
+/// // rows after the resized row are moved
+/// list.set_row_height(1, 40);
+/// assert_eq!(list.take_dirty(), Some(20..80));
+/// assert_eq!(list.take_dirty(), None);
+///
+/// list.insert_rows(0, [10, 10]);
+/// assert_eq!(list.row_at_y(15), Some(1));
+/// assert_eq!(list.take_dirty(), Some(0..100));
+/// ```
+///
+/// [`take_dirty`]: VirtualList::take_dirty
+pub struct VirtualList<T> {
+    rows: PostfixSegmentTree<T>,
+    dirty: Option<Range<T>>,
+}
+
+impl<T> VirtualList<T>
+where
+    T: Aggregate + Clone + PartialOrd,
+{
+    /// Creates an empty list.
+    pub fn new() -> Self {
+        Self::from_heights([])
+    }
+
+    /// Creates a list of rows with `heights`.
+    pub fn from_heights(heights: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            rows: PostfixSegmentTree::from_iter(heights),
+            dirty: None,
+        }
+    }
+
+    /// Returns the number of rows.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns `true` if the list has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Returns the height of the row `row`.
+    pub fn row_height(&self, row: usize) -> Option<&T> {
+        self.rows.get(row)
+    }
+
+    /// Returns the total height of rows.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *rows*)
+    pub fn total_height(&self) -> T {
+        self.rows.prefix_sum(self.rows.len())
+    }
+
+    /// Returns the top of the row `row`. `y_of_row(len())` is the total height.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log `row`)
+    pub fn y_of_row(&self, row: usize) -> T {
+        self.rows.prefix_sum(row)
+    }
+
+    /// Returns the row that contains `y`, or `None` if `y` is below all rows.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *rows*)
+    pub fn row_at_y(&self, y: T) -> Option<usize> {
+        let row = self.rows.search_prefix(|bottom| *bottom > y);
+        (row < self.rows.len()).then_some(row)
+    }
+
+    /// Sets the height of the row `row`.
+    ///
+    /// The row and the rows after it are dirty if the height is changed, otherwise only the row is dirty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *rows*)
+    pub fn set_row_height(&mut self, row: usize, height: T) {
+        assert!(row < self.rows.len());
+
+        let top = self.y_of_row(row);
+        if self.rows[row] == height {
+            let bottom = self.y_of_row(row + 1);
+            self.mark_dirty(top..bottom);
+            return;
+        }
+
+        let old_total = self.total_height();
+        self.rows.update(row, height);
+        let bottom = max(old_total, self.total_height());
+        self.mark_dirty(top..bottom);
+    }
+
+    /// Inserts rows of `heights` at `row`. The inserted rows and the rows after them are dirty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*rows* + *inserted*)
+    pub fn insert_rows(&mut self, row: usize, heights: impl IntoIterator<Item = T>) {
+        assert!(row <= self.rows.len());
+
+        let top = self.y_of_row(row);
+        let old_total = self.total_height();
+
+        let mut inserted = PostfixSegmentTree::from_iter(heights);
+        inserted.transfer(.., &mut self.rows, row);
+
+        let bottom = max(old_total, self.total_height());
+        self.mark_dirty(top..bottom);
+    }
+
+    /// Removes rows in `range`. The rows after them are dirty.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*rows*)
+    pub fn remove_rows(&mut self, range: impl RangeBounds<usize>) {
+        let range = to_range(range, self.rows.len());
+
+        let top = self.y_of_row(range.start);
+        let old_total = self.total_height();
+
+        let mut removed = PostfixSegmentTree::new();
+        self.rows.transfer(range, &mut removed, 0);
+
+        let bottom = max(old_total, self.total_height());
+        self.mark_dirty(top..bottom);
+    }
+
+    /// Returns the union of dirty rects since the last call, and clears it.
+    pub fn take_dirty(&mut self) -> Option<Range<T>> {
+        self.dirty.take()
+    }
+
+    fn mark_dirty(&mut self, rect: Range<T>) {
+        if rect.start >= rect.end {
+            return;
+        }
+
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => min(dirty.start, rect.start)..max(dirty.end, rect.end),
+            None => rect,
+        });
+    }
+}
+
+impl<T> Default for VirtualList<T>
+where
+    T: Aggregate + Clone + PartialOrd,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a { b } else { a }
+}
+
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a { b } else { a }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_list_matches_naive() {
+        fn row_at_y(heights: &[u32], y: u32) -> Option<usize> {
+            let mut bottom = 0;
+            for (row, height) in heights.iter().enumerate() {
+                bottom += height;
+                if y < bottom {
+                    return Some(row);
+                }
+            }
+
+            None
+        }
+
+        let mut heights: Vec<u32> = vec![3, 0, 5, 2];
+        let mut list = VirtualList::from_heights(heights.iter().copied());
+        for step in 0..40u32 {
+            match step % 3 {
+                0 => {
+                    let row = step as usize % (heights.len() + 1);
+                    let inserted = [step % 4, 1];
+                    list.insert_rows(row, inserted);
+                    heights.splice(row..row, inserted);
+                }
+                1 => {
+                    let row = step as usize % heights.len();
+                    list.set_row_height(row, step % 5);
+                    heights[row] = step % 5;
+                }
+                _ => {
+                    let start = step as usize % heights.len();
+                    let end = usize::min(start + 2, heights.len());
+                    list.remove_rows(start..end);
+                    heights.drain(start..end);
+                }
+            }
+
+            let total: u32 = heights.iter().sum();
+            assert_eq!(list.total_height(), total);
+            for row in 0..=heights.len() {
+                assert_eq!(list.y_of_row(row), heights[..row].iter().sum());
+            }
+            for y in 0..=total + 1 {
+                assert_eq!(list.row_at_y(y), row_at_y(&heights, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_dirty_rects() {
+        let mut list = VirtualList::from_heights([10, 10, 10]);
+        assert_eq!(list.take_dirty(), None);
+
+        // the same height only dirties the row itself
+        list.set_row_height(1, 10);
+        assert_eq!(list.take_dirty(), Some(10..20));
+
+        // shrinking dirties until the old bottom
+        list.set_row_height(1, 5);
+        assert_eq!(list.take_dirty(), Some(10..30));
+
+        // rects are accumulated
+        list.remove_rows(2..);
+        list.insert_rows(0, [1]);
+        assert_eq!(list.take_dirty(), Some(0..25));
+    }
+}
@@ -0,0 +1,315 @@
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "rand")]
+use rand::distr::Distribution;
+#[cfg(feature = "rand")]
+use rand::distr::uniform::SampleUniform;
+
+use crate::PostfixSegmentTree;
+use crate::aggregate::Aggregate;
+
+/// A weighted index sampler, which supports *O*(log *n*) weight updates and *O*(log *n*) sampling.
+///
+/// Weights are stored in a [`PostfixSegmentTree`], and an index is sampled by descending cumulative weights
+/// with a uniform random point in `0..total_weight`.
+/// Unlike a static alias table or cumulative array, changing a weight doesn't rebuild anything.
+///
+/// Sampling methods take a closure that returns a uniform random value in `0..total` for the given `total`,
+/// so they work with any random number generator.
+/// With the `rand` feature, it also implements [`Distribution<usize>`] like `rand::distr::weighted::WeightedIndex`.
+/// Weights are expected to be non-negative.
+///
+/// # Examples
+///
+/// ```
+/// use postfix_segment_tree::DynamicWeightedIndex;
+///
+/// let mut loot = DynamicWeightedIndex::new([5, 0, 3, 2]);
+/// assert_eq!(loot.total_weight(), 10);
+///
+/// // a uniform point in 0..10 selects an index by cumulative weights [5, 5, 8, 10]
+/// assert_eq!(loot.sample_at(&4), Some(0));
+/// assert_eq!(loot.sample_at(&5), Some(2));
+/// assert_eq!(loot.sample(|total| total - 1), Some(3));
+///
+/// loot.set_weight(0, 0);
+/// assert_eq!(loot.sample(|_| 0), Some(2));
+/// ```
+///
+/// [`Distribution<usize>`]: https://docs.rs/rand/0.9/rand/distr/trait.Distribution.html
+pub struct DynamicWeightedIndex<W> {
+    weights: PostfixSegmentTree<W>,
+}
+
+impl<W> DynamicWeightedIndex<W>
+where
+    W: Aggregate + Clone + PartialOrd,
+{
+    /// Creates a sampler of `weights`.
+    pub fn new(weights: impl IntoIterator<Item = W>) -> Self {
+        Self {
+            weights: PostfixSegmentTree::from_iter(weights),
+        }
+    }
+
+    /// Returns the number of weights.
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Returns `true` if it has no weights.
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Returns the weight of `index`.
+    pub fn weight(&self, index: usize) -> Option<&W> {
+        self.weights.get(index)
+    }
+
+    /// Returns the sum of weights.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn total_weight(&self) -> W {
+        self.weights.prefix_sum(self.weights.len())
+    }
+
+    /// Sets the weight of `index`.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn set_weight(&mut self, index: usize, weight: W) {
+        self.weights.update(index, weight);
+    }
+
+    /// Appends a weight.
+    ///
+    /// # Time complexity
+    ///
+    /// Amortized *O*(1)
+    pub fn push(&mut self, weight: W) {
+        self.weights.push(weight);
+    }
+
+    /// Returns the index whose cumulative weight range contains `point`,
+    /// which is the smallest `index` that the sum of weights until `index` is greater than `point`.
+    ///
+    /// It returns `None` if `point` is not less than the total weight.
+    /// Indices of zero weights are never returned.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn sample_at(&self, point: &W) -> Option<usize> {
+        self.weights.kth(point)
+    }
+
+    /// Samples an index with a uniform random value in `0..total` returned by `uniform(&total)`.
+    ///
+    /// It returns `None` without calling `uniform` if the total weight is not positive.
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log *n*)
+    pub fn sample(&self, uniform: impl FnOnce(&W) -> W) -> Option<usize> {
+        let total = self.total_weight();
+        if total <= W::identity() {
+            return None;
+        }
+
+        self.sample_at(&uniform(&total))
+    }
+
+    /// Samples up to `k` distinct indices in order of selection, with a uniform random value in `0..total`
+    /// returned by `uniform(&total)` for each selection.
+    ///
+    /// A selected weight is zeroed for the following selections, and all of them are restored at once afterwards,
+    /// so the weights are not changed. Fewer than `k` indices are returned if fewer weights are positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postfix_segment_tree::DynamicWeightedIndex;
+    ///
+    /// let mut raffle = DynamicWeightedIndex::new([1, 3, 0, 2]);
+    /// // always pick the first remaining ticket
+    /// assert_eq!(raffle.sample_without_replacement(2, |_| 0), vec![0, 1]);
+    /// assert_eq!(raffle.sample_without_replacement(5, |_| 0), vec![0, 1, 3]);
+    /// assert_eq!(raffle.total_weight(), 6);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(`k` log *n*)
+    pub fn sample_without_replacement(
+        &mut self,
+        k: usize,
+        mut uniform: impl FnMut(&W) -> W,
+    ) -> Vec<usize> {
+        let mut picked = Vec::with_capacity(usize::min(k, self.len()));
+        let mut restored = Vec::with_capacity(picked.capacity());
+        while picked.len() < k {
+            let Some(index) = self.sample(&mut uniform) else {
+                break;
+            };
+
+            restored.push((index, self.weights.replace(index, W::identity())));
+            picked.push(index);
+        }
+
+        self.weights.batch_update(restored);
+        picked
+    }
+}
+
+impl<W> Default for DynamicWeightedIndex<W>
+where
+    W: Aggregate + Clone + PartialOrd,
+{
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl<W> FromIterator<W> for DynamicWeightedIndex<W>
+where
+    W: Aggregate + Clone + PartialOrd,
+{
+    fn from_iter<I: IntoIterator<Item = W>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+/// Samples an index with a uniform random value in `0..total` from `rng`, as [`sample`] does.
+///
+/// The inherent [`sample`] takes precedence in method calls, so use `rng.sample(&index)` instead.
+///
+/// # Panics
+///
+/// Panics if the total weight is not positive.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "rand")]
+/// # {
+/// use postfix_segment_tree::DynamicWeightedIndex;
+/// use rand::rngs::SmallRng;
+/// use rand::{Rng, SeedableRng};
+///
+/// let mut loot = DynamicWeightedIndex::new([2.0, 0.0, 1.0]);
+/// let mut rng = SmallRng::seed_from_u64(1);
+/// assert_ne!(rng.sample(&loot), 1);
+///
+/// loot.set_weight(0, 0.0);
+/// assert_eq!(rng.sample(&loot), 2);
+/// # }
+/// ```
+///
+/// [`sample`]: DynamicWeightedIndex::sample
+#[cfg(feature = "rand")]
+impl<W> Distribution<usize> for DynamicWeightedIndex<W>
+where
+    W: Aggregate + Clone + PartialOrd + SampleUniform,
+{
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        DynamicWeightedIndex::sample(self, |total| rng.random_range(W::identity()..total.clone()))
+            .expect("total weight is not positive")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_at_matches_cumulative_weights() {
+        let mut weights: Vec<u32> = (0..20).map(|i| (i * 7) % 5).collect();
+        let mut index = DynamicWeightedIndex::new(weights.iter().copied());
+        for step in 0..20usize {
+            let position = (step * 3) % weights.len();
+            index.set_weight(position, step as u32 % 4);
+            weights[position] = step as u32 % 4;
+
+            let total: u32 = weights.iter().sum();
+            assert_eq!(index.total_weight(), total);
+            for point in 0..=total {
+                let expected =
+                    (0..weights.len()).find(|&i| weights[..=i].iter().sum::<u32>() > point);
+                assert_eq!(index.sample_at(&point), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_empty() {
+        let index = DynamicWeightedIndex::new([0.0, 0.0]);
+        assert_eq!(index.sample(|_| unreachable!()), None);
+
+        let index = DynamicWeightedIndex::new([0.0, 0.5]);
+        assert_eq!(index.sample(|total| total * 0.99), Some(1));
+    }
+
+    #[test]
+    fn test_sample_without_replacement() {
+        let weights = [3u32, 0, 1, 4, 1, 5];
+        let mut index = DynamicWeightedIndex::new(weights);
+        for seed in 0..20u32 {
+            let mut state = seed;
+            let picked = index.sample_without_replacement(4, |total| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 8) % total
+            });
+
+            assert_eq!(picked.len(), 4);
+            let mut distinct = picked.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            assert_eq!(distinct.len(), 4);
+            assert!(picked.iter().all(|&i| weights[i] > 0));
+        }
+
+        assert!(index.weights.iter().eq(weights.iter()));
+        assert_eq!(index.total_weight(), 14);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_distribution_frequencies() {
+        use rand::rngs::SmallRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut index = DynamicWeightedIndex::new([1u32, 0, 3, 4]);
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..2 {
+            let mut counts = [0u32; 4];
+            for _ in 0..8000 {
+                counts[rng.sample(&index)] += 1;
+            }
+
+            // each count is within 10% of its expectation, 1000 per unit weight
+            let total = index.total_weight();
+            for (i, count) in counts.into_iter().enumerate() {
+                let expected = 8000 * index.weight(i).unwrap() / total;
+                assert!(count.abs_diff(expected) <= expected / 10, "{counts:?}");
+            }
+
+            index.set_weight(1, 2);
+            index.set_weight(2, 1);
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    #[should_panic(expected = "total weight is not positive")]
+    fn test_distribution_without_positive_weights() {
+        use rand::rngs::SmallRng;
+        use rand::{Rng, SeedableRng};
+
+        let index = DynamicWeightedIndex::new([0.0, 0.0]);
+        SmallRng::seed_from_u64(0).sample(&index);
+    }
+}
@@ -0,0 +1,53 @@
+//! Cross-checks the structures of `benches/comparison.rs`, so they measure the same operations.
+
+#[path = "../benches/common/mod.rs"]
+mod common;
+
+use postfix_segment_tree::PostfixSegmentTree;
+
+use crate::common::{Fenwick, Indices, PrefixSumTree, SegmentTree};
+
+/// Applies the same pseudo-random operations to `T`, and returns every query result.
+fn run<T: PrefixSumTree>() -> Vec<i64> {
+    let mut results = Vec::new();
+    let mut tree = T::from_iter((0..100).map(|i| i * 7 % 1000));
+    let mut len = 100;
+    let mut indices = Indices(0x2545_f491_4f6c_dd1d);
+    for step in 0..2000 {
+        let element = (step * 31 % 1000) as i64;
+        match indices.next(7) {
+            0 => {
+                tree.push(element);
+                len += 1;
+            }
+            1 if len > 0 => tree.update(indices.next(len), element),
+            2 => {
+                tree.insert(indices.next(len + 1), element);
+                len += 1;
+            }
+            3 if len > 0 => {
+                results.push(tree.remove(indices.next(len)));
+                len -= 1;
+            }
+            4 => {
+                results.push(tree.pop().unwrap_or(-1));
+                len = len.saturating_sub(1);
+            }
+            5 => {
+                let index = indices.next(len + 1);
+                results.push(tree.sum(index, indices.next(len - index + 1)));
+            }
+            _ => results.push(tree.prefix_sum(indices.next(len + 1))),
+        }
+        results.push(tree.prefix_sum(len));
+    }
+
+    results
+}
+
+#[test]
+fn test_structures_agree() {
+    let expected = run::<PostfixSegmentTree<i64>>();
+    assert_eq!(run::<Fenwick>(), expected, "{}", Fenwick::NAME);
+    assert_eq!(run::<SegmentTree>(), expected, "{}", SegmentTree::NAME);
+}
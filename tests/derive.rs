@@ -0,0 +1,118 @@
+#![cfg(feature = "derive")]
+
+use std::marker::PhantomData;
+use std::ops::AddAssign;
+
+use postfix_segment_tree::{Aggregate, PostfixSegmentTree};
+
+#[derive(Clone, Debug, PartialEq, Aggregate)]
+struct Stats {
+    #[aggregate(sum)]
+    sum: i64,
+    count: u32,
+    #[aggregate(min)]
+    min: i64,
+    #[aggregate(max)]
+    max: i64,
+}
+
+impl Stats {
+    fn new(value: i64) -> Self {
+        Stats {
+            sum: value,
+            count: 1,
+            min: value,
+            max: value,
+        }
+    }
+}
+
+#[test]
+fn test_sum_min_max_fields() {
+    let elements = [3, -1, 4, 1, -5, 9, 2, 6];
+    let tree = PostfixSegmentTree::from_iter(elements.map(Stats::new));
+    for index in 0..=elements.len() {
+        for len in 0..=elements.len() - index {
+            let range = &elements[index..index + len];
+            assert_eq!(
+                tree.sum(index, len),
+                Stats {
+                    sum: range.iter().sum(),
+                    count: len as u32,
+                    min: range.iter().copied().min().unwrap_or(i64::MAX),
+                    max: range.iter().copied().max().unwrap_or(i64::MIN),
+                }
+            );
+        }
+    }
+}
+
+pub mod visibility {
+    use super::*;
+
+    /// Fields with doc comments and restricted visibility.
+    #[derive(Clone, Aggregate)]
+    pub struct Documented {
+        /// The total.
+        pub(crate) total: u64,
+        /// The largest element.
+        #[aggregate(max)]
+        pub(super) largest: u64,
+        pub count: u64,
+    }
+}
+
+#[test]
+fn test_doc_commented_and_restricted_fields() {
+    use visibility::Documented;
+
+    let tree = PostfixSegmentTree::from_iter([5u64, 2, 7].map(|value| Documented {
+        total: value,
+        largest: value,
+        count: 1,
+    }));
+    let sum = tree.prefix_sum(3);
+    assert_eq!((sum.total, sum.largest, sum.count), (14, 7, 3));
+}
+
+/// Counts how many elements are combined, for any function type `F`.
+#[derive(Clone)]
+struct Calls<F>(usize, PhantomData<F>);
+
+impl<F> Default for Calls<F> {
+    fn default() -> Self {
+        Calls(0, PhantomData)
+    }
+}
+
+impl<F> AddAssign<&Calls<F>> for Calls<F> {
+    fn add_assign(&mut self, rhs: &Calls<F>) {
+        self.0 += rhs.0;
+    }
+}
+
+#[derive(Clone, Aggregate)]
+struct Callbacks {
+    calls: Calls<fn(u32) -> u32>,
+    // must not be swallowed by the `->` of the previous field type
+    sum: u32,
+    #[aggregate(max)]
+    max: u32,
+}
+
+#[test]
+fn test_field_after_function_pointer_type() {
+    let tree = PostfixSegmentTree::from_iter([1, 5, 3].map(|value| Callbacks {
+        calls: Calls(1, PhantomData),
+        sum: value,
+        max: value,
+    }));
+    let sum = tree.sum(0, 3);
+    assert_eq!((sum.calls.0, sum.sum, sum.max), (3, 9, 5));
+}
+
+#[test]
+fn test_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,8 @@
+use postfix_segment_tree::Aggregate;
+
+#[derive(Aggregate)]
+enum Sum {
+    A(i64),
+}
+
+fn main() {}
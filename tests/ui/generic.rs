@@ -0,0 +1,8 @@
+use postfix_segment_tree::Aggregate;
+
+#[derive(Clone, Aggregate)]
+struct Sum<T> {
+    sum: T,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use postfix_segment_tree::Aggregate;
+
+#[derive(Clone, Aggregate)]
+struct Sum(i64);
+
+fn main() {}
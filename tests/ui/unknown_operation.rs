@@ -0,0 +1,9 @@
+use postfix_segment_tree::Aggregate;
+
+#[derive(Clone, Aggregate)]
+struct Stats {
+    #[aggregate(mean)]
+    mean: i64,
+}
+
+fn main() {}